@@ -2,7 +2,7 @@ use anyhow::{Error, Result};
 use lazy_static::lazy_static;
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::hash_map::DefaultHasher;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{Debug, Display, Formatter};
@@ -11,6 +11,8 @@ use std::hash::{Hash, Hasher};
 pub type HashMap<K, V> = std::collections::HashMap<K, V, fnv::FnvBuildHasher>;
 pub type HashSet<V> = std::collections::HashSet<V, fnv::FnvBuildHasher>;
 
+pub mod sb2;
+
 /// Represents the data inside a Scratch 3.0 (`.sb3`) file.
 ///
 /// [.sb3 format documentation](https://en.scratch-wiki.info/wiki/Scratch_File_Format)
@@ -20,10 +22,14 @@ pub struct ScratchFile {
 
     /// Maps filename to image
     pub images: HashMap<String, Image>,
+
+    /// Maps filename to sound
+    pub sounds: HashMap<String, Audio>,
 }
 
 impl ScratchFile {
-    /// Parses a Scratch file to create a ScratchFile.
+    /// Parses a Scratch file to create a ScratchFile. Scratch 2.0 files are converted to the
+    /// Scratch 3.0 format.
     pub fn parse<R>(file: R) -> Result<ScratchFile>
     where
         R: std::io::Read + std::io::Seek,
@@ -31,12 +37,26 @@ impl ScratchFile {
         use std::io::Read;
 
         let mut archive = zip::ZipArchive::new(file)?;
-        let project: Project = serde_json::from_reader(archive.by_name("project.json")?)?;
+        let json: Value = serde_json::from_reader(archive.by_name("project.json")?)?;
+        let (project, asset_names) = if json.get("targets").is_some() {
+            (serde_json::from_value(json)?, HashMap::default())
+        } else {
+            sb2::convert(&json)?
+        };
+        let asset_name = |name: &str| {
+            asset_names
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string())
+        };
 
         let mut image_names: Vec<String> = Vec::new();
+        let mut sound_names: Vec<String> = Vec::new();
         for name in archive.file_names() {
             if name.ends_with(".svg") | name.ends_with(".png") {
                 image_names.push(name.to_string());
+            } else if name.ends_with(".wav") | name.ends_with(".mp3") {
+                sound_names.push(name.to_string());
             }
         }
 
@@ -51,10 +71,60 @@ impl ScratchFile {
             } else {
                 return Err(Error::msg("unrecognized file extension"));
             };
-            images.insert(name.clone(), image);
+            images.insert(asset_name(name), image);
+        }
+
+        let mut sounds: HashMap<String, Audio> = HashMap::default();
+        for name in &sound_names {
+            let mut b: Vec<u8> = Vec::new();
+            archive.by_name(name).unwrap().read_to_end(&mut b)?;
+            let sound = if name.ends_with(".wav") {
+                Audio::WAV(b)
+            } else if name.ends_with(".mp3") {
+                Audio::MP3(b)
+            } else {
+                return Err(Error::msg("unrecognized file extension"));
+            };
+            sounds.insert(asset_name(name), sound);
+        }
+
+        Ok(Self {
+            project,
+            images,
+            sounds,
+        })
+    }
+
+    /// Writes the project and all of its assets as a Scratch 3.0 file.
+    pub fn write<W>(&self, file: W) -> Result<()>
+    where
+        W: std::io::Write + std::io::Seek,
+    {
+        use std::io::Write;
+
+        let mut archive = zip::ZipWriter::new(file);
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        archive.start_file("project.json", options)?;
+        serde_json::to_writer(&mut archive, &self.project)?;
+
+        for (name, image) in sorted_entries(&self.images) {
+            archive.start_file(name.as_str(), options)?;
+            match image {
+                Image::SVG(b) | Image::PNG(b) => archive.write_all(b)?,
+            }
+        }
+
+        for (name, sound) in sorted_entries(&self.sounds) {
+            archive.start_file(name.as_str(), options)?;
+            match sound {
+                Audio::WAV(b) | Audio::MP3(b) => archive.write_all(b)?,
+            }
         }
 
-        Ok(Self { project, images })
+        archive.finish()?;
+        Ok(())
     }
 }
 
@@ -65,6 +135,10 @@ pub struct Project {
     pub monitors: Vec<Monitor>,
     pub extensions: Vec<String>,
     pub meta: Meta,
+    /// Fields that are not used by the VM are kept so that the project can be written without
+    /// losing anything.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Represents a Sprite.
@@ -75,22 +149,35 @@ pub struct Target {
     pub is_stage: bool,
     pub name: String,
     pub variables: HashMap<String, Variable>,
+    #[serde(default)]
+    pub lists: HashMap<String, List>,
     pub blocks: HashMap<BlockID, Block>,
     pub costumes: Vec<Costume>,
+    #[serde(default)]
+    pub sounds: Vec<Sound>,
     /// Lowest number = back, highest number = front
     #[serde(default)]
     pub layer_order: usize,
-    /// This uses sprite coordinates.
+    /// This uses sprite coordinates. The stage does not have a position, size or visibility.
     /// Left = -240, right = +240
-    #[serde(default)]
-    pub x: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
     /// Top = +180, bottom = -180
-    #[serde(default)]
-    pub y: f64,
-    #[serde(default)]
-    pub size: f64,
-    #[serde(default)]
-    pub visible: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    /// 90 = right, 0 = up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<f64>,
+    /// "all around", "left-right" or "don't rotate"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_style: Option<String>,
+    /// Unrecognized fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for Target {
@@ -99,13 +186,18 @@ impl Default for Target {
             is_stage: false,
             name: String::new(),
             variables: HashMap::default(),
+            lists: HashMap::default(),
             blocks: HashMap::default(),
             costumes: Vec::new(),
+            sounds: Vec::new(),
             layer_order: 0,
-            x: 0.0,
-            y: 0.0,
-            size: 0.0,
-            visible: true,
+            x: None,
+            y: None,
+            size: None,
+            visible: None,
+            direction: None,
+            rotation_style: None,
+            extra: Map::new(),
         }
     }
 }
@@ -115,11 +207,18 @@ impl Hash for Target {
         self.is_stage.hash(state);
         self.name.hash(state);
         sorted_entries(&self.variables).hash(state);
+        sorted_entries(&self.lists).hash(state);
         sorted_entries(&self.blocks).hash(state);
         self.costumes.hash(state);
-        self.x.to_bits().hash(state);
-        self.y.to_bits().hash(state);
-        self.size.to_bits().hash(state);
+        self.sounds.hash(state);
+        self.layer_order.hash(state);
+        self.x.map(f64::to_bits).hash(state);
+        self.y.map(f64::to_bits).hash(state);
+        self.size.map(f64::to_bits).hash(state);
+        self.visible.hash(state);
+        self.direction.map(f64::to_bits).hash(state);
+        self.rotation_style.hash(state);
+        hash_extra(&self.extra, state);
     }
 }
 
@@ -129,7 +228,8 @@ impl PartialEq for Target {
     }
 }
 
-#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+/// A variable is stored as an array of name and value.
+#[derive(Clone, Default, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Variable {
     pub id: String,
@@ -138,6 +238,22 @@ pub struct Variable {
     pub i_dont_know_what_this_does: bool,
 }
 
+impl Serialize for Variable {
+    fn serialize<S>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        if self.i_dont_know_what_this_does {
+            (&self.id, &self.value, true).serialize(serializer)
+        } else {
+            (&self.id, &self.value).serialize(serializer)
+        }
+    }
+}
+
 impl Hash for Variable {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -152,6 +268,40 @@ impl PartialEq for Variable {
     }
 }
 
+/// A list is stored as an array of name and items.
+#[derive(Clone, Default, Debug, Deserialize)]
+pub struct List {
+    pub name: String,
+    pub values: Vec<Value>,
+}
+
+impl Serialize for List {
+    fn serialize<S>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        (&self.name, &self.values).serialize(serializer)
+    }
+}
+
+impl Hash for List {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        for value in &self.values {
+            hash_value(value, state);
+        }
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        equal_hash(&self, other)
+    }
+}
+
 fn equal_hash<A, B>(a: A, b: B) -> bool
 where
     A: Hash,
@@ -177,6 +327,12 @@ pub struct Block {
     pub fields: HashMap<String, Vec<Option<String>>>,
     /// Top most block in a stack of connected blocks
     pub top_level: bool,
+    /// Extra information for custom blocks and some other blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation: Option<Mutation>,
+    /// Unrecognized fields such as parent, shadow and the position of top level blocks
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Hash for Block {
@@ -192,6 +348,8 @@ impl Hash for Block {
         sorted_entries(&self.fields).hash(state);
 
         self.top_level.hash(state);
+        self.mutation.hash(state);
+        hash_extra(&self.extra, state);
     }
 }
 
@@ -201,6 +359,76 @@ impl PartialEq for Block {
     }
 }
 
+/// Custom blocks use the mutation to define the procedure and its arguments.
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mutation {
+    #[serde(default)]
+    pub tag_name: String,
+    #[serde(default)]
+    pub children: Vec<Value>,
+    /// Text of the custom block with argument placeholders such as "move %s steps"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub proccode: String,
+    /// JSON array of argument IDs
+    #[serde(
+        rename = "argumentids",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub argument_ids: String,
+    /// JSON array of argument names. Only the prototype has argument names.
+    #[serde(
+        rename = "argumentnames",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub argument_names: String,
+    /// "true" if the procedure should run without screen refresh
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub warp: Value,
+    /// Unrecognized fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Mutation {
+    pub fn argument_ids(&self) -> Result<Vec<String>> {
+        Mutation::parse_string_array(&self.argument_ids)
+    }
+
+    pub fn argument_names(&self) -> Result<Vec<String>> {
+        Mutation::parse_string_array(&self.argument_names)
+    }
+
+    pub fn warp(&self) -> bool {
+        match &self.warp {
+            Value::Bool(b) => *b,
+            Value::String(s) => s == "true",
+            _ => false,
+        }
+    }
+
+    fn parse_string_array(s: &str) -> Result<Vec<String>> {
+        if s.is_empty() {
+            Ok(Vec::new())
+        } else {
+            Ok(serde_json::from_str(s)?)
+        }
+    }
+}
+
+impl Hash for Mutation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tag_name.hash(state);
+        self.proccode.hash(state);
+        self.argument_ids.hash(state);
+        self.argument_names.hash(state);
+        hash_value(&self.warp, state);
+        hash_extra(&self.extra, state);
+    }
+}
+
 fn sorted_entries<K, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)>
 where
     K: std::cmp::Ord,
@@ -217,18 +445,38 @@ where
     value.to_string().hash(state)
 }
 
+fn hash_extra<H>(extra: &Map<String, Value>, state: &mut H)
+where
+    H: Hasher,
+{
+    for (key, value) in extra {
+        key.hash(state);
+        hash_value(value, state);
+    }
+}
+
+fn is_zero(n: &f64) -> bool {
+    *n == 0.0
+}
+
 /// Sprite costume
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Costume {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub md5ext: Option<String>,
     pub asset_id: String,
+    #[serde(default)]
+    pub data_format: String,
     /// Center of this costume with coordinates local to the image.
     pub rotation_center_x: f64,
     pub rotation_center_y: f64,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub bitmap_resolution: f64,
+    /// Unrecognized fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for Costume {
@@ -237,9 +485,11 @@ impl Default for Costume {
             name: String::new(),
             md5ext: None,
             asset_id: String::new(),
+            data_format: String::new(),
             rotation_center_x: 0.0,
             rotation_center_y: 0.0,
             bitmap_resolution: 1.0,
+            extra: Map::new(),
         }
     }
 }
@@ -248,8 +498,12 @@ impl Hash for Costume {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.name.hash(state);
         self.md5ext.hash(state);
+        self.asset_id.hash(state);
+        self.data_format.hash(state);
         self.rotation_center_x.to_bits().hash(state);
         self.rotation_center_y.to_bits().hash(state);
+        self.bitmap_resolution.to_bits().hash(state);
+        hash_extra(&self.extra, state);
     }
 }
 
@@ -259,6 +513,48 @@ impl PartialEq for Costume {
     }
 }
 
+/// Sprite sound
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sound {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub md5ext: Option<String>,
+    pub asset_id: String,
+    pub data_format: String,
+    /// Samples per second
+    #[serde(default)]
+    pub rate: u32,
+    #[serde(default)]
+    pub sample_count: u32,
+    /// Unrecognized fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Hash for Sound {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.md5ext.hash(state);
+        self.asset_id.hash(state);
+        self.data_format.hash(state);
+        self.rate.hash(state);
+        self.sample_count.hash(state);
+        hash_extra(&self.extra, state);
+    }
+}
+
+impl Sound {
+    /// Length of the sound.
+    pub fn duration(&self) -> std::time::Duration {
+        if self.rate == 0 {
+            std::time::Duration::default()
+        } else {
+            std::time::Duration::from_secs_f64(self.sample_count as f64 / self.rate as f64)
+        }
+    }
+}
+
 /// A monitor is the grey and orange rectangle that outputs the variable value.
 #[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -272,16 +568,34 @@ pub struct Monitor {
     pub x: f64,
     pub y: f64,
     pub visible: bool,
-    pub slider_min: f64,
-    pub slider_max: f64,
-    pub is_discrete: bool,
-}
-
+    /// Only list monitors have a size. Zero means default size.
+    #[serde(default)]
+    pub width: f64,
+    #[serde(default)]
+    pub height: f64,
+    /// List monitors do not have slider settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slider_min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slider_max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_discrete: Option<bool>,
+    /// Unrecognized fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Variable monitors have a VARIABLE param and list monitors have a LIST param.
 #[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MonitorParams {
-    #[serde(rename = "VARIABLE")]
+    #[serde(rename = "VARIABLE", default, skip_serializing_if = "String::is_empty")]
     pub variable: String,
+    #[serde(rename = "LIST", default, skip_serializing_if = "String::is_empty")]
+    pub list: String,
+    /// Params of other monitors such as NUMBER_NAME
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
@@ -290,6 +604,9 @@ pub struct Meta {
     pub semver: String,
     pub vm: String,
     pub agent: String,
+    /// Unrecognized fields
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Contains the raw bytes of the image format.
@@ -322,6 +639,36 @@ impl Debug for Image {
     }
 }
 
+/// Contains the raw bytes of the sound format.
+#[derive(PartialEq, Eq, Clone)]
+pub enum Audio {
+    WAV(Vec<u8>),
+    MP3(Vec<u8>),
+}
+
+impl Debug for Audio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let vec_len = match self {
+            Audio::WAV(v) => {
+                write!(f, "WAV(")?;
+                v.len()
+            }
+            Audio::MP3(v) => {
+                write!(f, "MP3(")?;
+                v.len()
+            }
+        };
+
+        if vec_len > 0 {
+            write!(f, "[...]")?;
+        } else {
+            write!(f, "[]")?;
+        }
+
+        write!(f, ")")
+    }
+}
+
 /// Unique ID for each block.
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Default, Hash)]
 pub struct BlockID {
@@ -380,7 +727,8 @@ impl Serialize for BlockID {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        // Display only shows part of the ID
+        serializer.serialize_str(std::str::from_utf8(&self.id).map_err(serde::ser::Error::custom)?)
     }
 }
 
@@ -422,6 +770,124 @@ mod tests {
         assert_eq!(target.name, "Sprite1");
     }
 
+    #[test]
+    fn scratch_file_parse_sounds() {
+        let file = std::fs::File::open("test_saves/say.sb3").unwrap();
+        let savefile = ScratchFile::parse(&file).unwrap();
+        let sound = &savefile.project.targets[1].sounds[0];
+        assert_eq!(sound.name, "Meow");
+        assert_eq!(sound.data_format, "wav");
+        assert_eq!(sound.duration().as_millis(), 847);
+        assert!(matches!(
+            savefile.sounds.get(sound.md5ext.as_ref().unwrap()),
+            Some(Audio::WAV(_))
+        ));
+    }
+
+    #[test]
+    fn scratch_file_write() {
+        let file = std::fs::File::open("test_saves/say.sb3").unwrap();
+        let savefile = ScratchFile::parse(&file).unwrap();
+
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        savefile.write(&mut buffer).unwrap();
+        buffer.set_position(0);
+        assert_eq!(ScratchFile::parse(buffer).unwrap(), savefile);
+    }
+
+    /// Numbers are compared as f64 because integers are written as floats.
+    fn normalize_numbers(value: &mut Value) {
+        match value {
+            Value::Number(n) => *value = Value::from(n.as_f64().unwrap()),
+            Value::Array(array) => array.iter_mut().for_each(normalize_numbers),
+            Value::Object(object) => object.values_mut().for_each(normalize_numbers),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn project_round_trip() {
+        let file = std::fs::File::open("test_saves/say.sb3").unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut expected: Value =
+            serde_json::from_reader(archive.by_name("project.json").unwrap()).unwrap();
+
+        let project: Project = serde_json::from_value(expected.clone()).unwrap();
+        let mut result = serde_json::to_value(&project).unwrap();
+
+        normalize_numbers(&mut expected);
+        normalize_numbers(&mut result);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn block_extra_fields() {
+        let json = r#"{"opcode":"event_whenflagclicked","next":null,"parent":null,"inputs":{},
+            "fields":{},"shadow":false,"topLevel":true,"x":48,"y":64}"#;
+        let block: Block = serde_json::from_str(json).unwrap();
+        assert_eq!(block.extra.get("shadow"), Some(&Value::Bool(false)));
+        assert_eq!(block.extra.get("x"), Some(&Value::from(48)));
+        assert_eq!(
+            serde_json::to_value(&block).unwrap(),
+            serde_json::from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn variable_serialize() {
+        let variable = Variable {
+            id: "a".to_string(),
+            value: Value::from(1),
+            i_dont_know_what_this_does: false,
+        };
+        assert_eq!(serde_json::to_string(&variable).unwrap(), r#"["a",1]"#);
+
+        let list = List {
+            name: "b".to_string(),
+            values: vec![Value::from("c")],
+        };
+        assert_eq!(serde_json::to_string(&list).unwrap(), r#"["b",["c"]]"#);
+    }
+
+    #[test]
+    fn list_deserialize() {
+        let list: List = serde_json::from_str(r#"["list", [1, "a"]]"#).unwrap();
+        assert_eq!(list.name, "list");
+        assert_eq!(list.values, vec![Value::from(1), Value::from("a")]);
+    }
+
+    #[test]
+    fn list_monitor_deserialize() {
+        let monitor: Monitor = serde_json::from_str(
+            r#"{"id":"id","mode":"list","opcode":"data_listcontents","params":{"LIST":"list"},
+            "spriteName":null,"value":[],"width":100,"height":200,"x":5,"y":5,"visible":true}"#,
+        )
+        .unwrap();
+        assert_eq!(monitor.params.list, "list");
+        assert_eq!(monitor.width, 100.0);
+        assert_eq!(monitor.height, 200.0);
+    }
+
+    #[test]
+    fn mutation_deserialize() {
+        let mutation: Mutation = serde_json::from_str(
+            r#"{"tagName":"mutation","children":[],"proccode":"block %s %b",
+            "argumentids":"[\"a\",\"b\"]","argumentnames":"[\"x\",\"y\"]",
+            "argumentdefaults":"[\"\",\"false\"]","warp":"true"}"#,
+        )
+        .unwrap();
+        assert_eq!(mutation.proccode, "block %s %b");
+        assert_eq!(mutation.argument_ids().unwrap(), vec!["a", "b"]);
+        assert_eq!(mutation.argument_names().unwrap(), vec!["x", "y"]);
+        assert!(mutation.warp());
+
+        let mutation: Mutation =
+            serde_json::from_str(r#"{"tagName":"mutation","children":[],"hasnext":"false"}"#)
+                .unwrap();
+        assert!(mutation.argument_ids().unwrap().is_empty());
+        assert!(!mutation.warp());
+    }
+
     #[test]
     fn block_id_from_str() {
         {
@@ -434,6 +900,10 @@ mod tests {
             let s = "G@pZX]3ynBGB)L`_LJk8";
             let id = BlockID::try_from(s).unwrap();
             assert_eq!(&id.to_string(), "G@pZX]3ynB");
+            assert_eq!(
+                serde_json::to_string(&id).unwrap(),
+                r#""G@pZX]3ynBGB)L`_LJk8""#
+            );
         }
     }
 }
@@ -0,0 +1,1371 @@
+//! Converts Scratch 2.0 (`.sb2`) projects to the Scratch 3.0 format.
+//!
+//! [.sb2 format documentation](https://en.scratch-wiki.info/wiki/Scratch_File_Format_(2.0))
+
+use super::*;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Converts the project.json of a Scratch 2.0 file. The returned map maps asset file names in the
+/// archive, such as "0.svg", to the md5ext names that the converted project refers to.
+pub fn convert(json: &Value) -> Result<(Project, HashMap<String, String>)> {
+    let stage = object(json)?;
+    let mut converter = Converter::default();
+
+    let stage_scope = converter.scope(stage, "Stage", &Scope::default());
+    let mut targets = vec![converter.target(stage, "Stage", &stage_scope, None)?];
+    let mut monitors = list_monitors(stage, &stage_scope, None);
+
+    let mut sprite_scopes: HashMap<String, Scope> = HashMap::default();
+    let children = array(stage.get("children"));
+    for child in children {
+        let child = object(child)?;
+        if child.contains_key("objName") {
+            let name = string(child.get("objName"));
+            let scope = converter.scope(child, &name, &stage_scope);
+            let layer_order = targets.len();
+            targets.push(converter.target(child, &name, &scope, Some(layer_order))?);
+            monitors.extend(list_monitors(child, &scope, Some(&name)));
+            sprite_scopes.insert(name, scope);
+        }
+    }
+
+    for child in children {
+        let child = object(child)?;
+        if child.get("cmd").and_then(Value::as_str) == Some("getVar:") {
+            let target = string(child.get("target"));
+            let (scope, sprite_name) = match sprite_scopes.get(&target) {
+                Some(scope) => (scope, Some(target)),
+                None => (&stage_scope, None),
+            };
+            monitors.extend(variable_monitor(child, scope, sprite_name));
+        }
+    }
+
+    let broadcasts: Map<String, Value> = converter
+        .broadcasts
+        .iter()
+        .map(|(name, id)| (id.clone(), Value::from(name.as_str())))
+        .collect();
+    targets[0]
+        .extra
+        .insert("broadcasts".to_string(), Value::Object(broadcasts));
+
+    let project = Project {
+        targets,
+        monitors,
+        extensions: converter.extensions.into_iter().collect(),
+        meta: Meta {
+            semver: "3.0.0".to_string(),
+            vm: "0.2.0".to_string(),
+            agent: String::new(),
+            extra: Map::new(),
+        },
+        extra: Map::new(),
+    };
+    Ok((project, converter.asset_names))
+}
+
+#[derive(Debug, Default)]
+struct Converter {
+    block_count: usize,
+    argument_count: usize,
+    /// Maps broadcast name to ID
+    broadcasts: BTreeMap<String, String>,
+    /// Maps asset file name to md5ext
+    asset_names: HashMap<String, String>,
+    /// Extensions that the converted blocks use, such as "pen"
+    extensions: BTreeSet<String>,
+}
+
+/// Names that blocks of a target can refer to.
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    /// Maps variable name to ID
+    variables: HashMap<String, String>,
+    /// Maps list name to ID
+    lists: HashMap<String, String>,
+    /// Maps the sb2 spec of a custom block to its argument IDs and warp setting
+    procedures: HashMap<String, (Vec<String>, bool)>,
+}
+
+impl Converter {
+    /// Local variables and lists hide the stage's variables and lists of the same name.
+    fn scope(&mut self, object: &Map<String, Value>, target_name: &str, stage: &Scope) -> Scope {
+        let mut scope = Scope {
+            variables: stage.variables.clone(),
+            lists: stage.lists.clone(),
+            procedures: HashMap::default(),
+        };
+
+        for variable in array(object.get("variables")) {
+            let name = string(variable.get("name"));
+            let id = format!("{}:variable:{}", target_name, name);
+            scope.variables.insert(name, id);
+        }
+
+        for list in array(object.get("lists")) {
+            let name = string(list.get("listName"));
+            let id = format!("{}:list:{}", target_name, name);
+            scope.lists.insert(name, id);
+        }
+
+        for script in array(object.get("scripts")) {
+            let definition = script
+                .get(2)
+                .and_then(|stack| stack.get(0))
+                .filter(|block| block.get(0).and_then(Value::as_str) == Some("procDef"));
+            if let Some(definition) = definition {
+                let spec = string(definition.get(1));
+                let argument_ids: Vec<String> = argument_types(&spec)
+                    .iter()
+                    .map(|_| {
+                        self.argument_count += 1;
+                        format!("argument{}", self.argument_count)
+                    })
+                    .collect();
+                let warp = definition.get(4).and_then(Value::as_bool).unwrap_or(false);
+                scope.procedures.insert(spec, (argument_ids, warp));
+            }
+        }
+        scope
+    }
+
+    /// layer_order is None for the stage.
+    fn target(
+        &mut self,
+        object: &Map<String, Value>,
+        name: &str,
+        scope: &Scope,
+        layer_order: Option<usize>,
+    ) -> Result<Target> {
+        let mut blocks: HashMap<BlockID, Block> = HashMap::default();
+        for script in array(object.get("scripts")) {
+            let script = script
+                .as_array()
+                .ok_or_else(|| Error::msg("script is not an array"))?;
+            let stack = array(script.get(2));
+            if let Some(id) = self.stack(&mut blocks, scope, stack, None)? {
+                let block = blocks.get_mut(&id).unwrap();
+                block.top_level = true;
+                block.extra.insert("x".to_string(), json!(script.first()));
+                block.extra.insert("y".to_string(), json!(script.get(1)));
+            }
+        }
+
+        let mut variables: HashMap<String, Variable> = HashMap::default();
+        for variable in array(object.get("variables")) {
+            let variable_name = string(variable.get("name"));
+            variables.insert(
+                scope.variables[&variable_name].clone(),
+                Variable {
+                    id: variable_name,
+                    value: variable.get("value").cloned().unwrap_or_else(|| json!(0)),
+                    i_dont_know_what_this_does: variable
+                        .get("isPersistent")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                },
+            );
+        }
+
+        let mut lists: HashMap<String, List> = HashMap::default();
+        for list in array(object.get("lists")) {
+            let list_name = string(list.get("listName"));
+            lists.insert(
+                scope.lists[&list_name].clone(),
+                List {
+                    name: list_name,
+                    values: array(list.get("contents")).to_vec(),
+                },
+            );
+        }
+
+        let costumes = array(object.get("costumes"))
+            .iter()
+            .map(|costume| self.costume(costume))
+            .collect();
+        let sounds = array(object.get("sounds"))
+            .iter()
+            .map(|sound| self.sound(sound))
+            .collect();
+
+        let mut extra = Map::new();
+        extra.insert(
+            "currentCostume".to_string(),
+            json!(object
+                .get("currentCostumeIndex")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as i64),
+        );
+        extra.insert("comments".to_string(), json!({}));
+        extra.insert("volume".to_string(), json!(100));
+        if layer_order.is_some() {
+            extra.insert(
+                "draggable".to_string(),
+                json!(object
+                    .get("isDraggable")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)),
+            );
+        } else {
+            extra.insert(
+                "tempo".to_string(),
+                json!(object
+                    .get("tempoBPM")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(60.0)),
+            );
+        }
+
+        let number = |key: &str| object.get(key).and_then(Value::as_f64);
+        Ok(Target {
+            is_stage: layer_order.is_none(),
+            name: name.to_string(),
+            variables,
+            lists,
+            blocks,
+            costumes,
+            sounds,
+            layer_order: layer_order.unwrap_or(0),
+            x: layer_order.map(|_| number("scratchX").unwrap_or(0.0)),
+            y: layer_order.map(|_| number("scratchY").unwrap_or(0.0)),
+            size: layer_order.map(|_| number("scale").unwrap_or(1.0) * 100.0),
+            visible: layer_order.map(|_| {
+                object
+                    .get("visible")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true)
+            }),
+            direction: layer_order.map(|_| number("direction").unwrap_or(90.0)),
+            rotation_style: layer_order.map(|_| {
+                match object.get("rotationStyle").and_then(Value::as_str) {
+                    Some("leftRight") => "left-right",
+                    Some("none") => "don't rotate",
+                    _ => "all around",
+                }
+                .to_string()
+            }),
+            extra,
+        })
+    }
+
+    fn costume(&mut self, costume: &Value) -> Costume {
+        let md5ext = string(costume.get("baseLayerMD5"));
+        let (asset_id, data_format) = split_md5ext(&md5ext);
+        if let Some(layer_id) = costume.get("baseLayerID").and_then(Value::as_i64) {
+            self.asset_names
+                .insert(format!("{}.{}", layer_id, data_format), md5ext.clone());
+        }
+
+        Costume {
+            name: string(costume.get("costumeName")),
+            md5ext: Some(md5ext.clone()),
+            asset_id: asset_id.to_string(),
+            data_format: data_format.to_string(),
+            rotation_center_x: costume
+                .get("rotationCenterX")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0),
+            rotation_center_y: costume
+                .get("rotationCenterY")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0),
+            bitmap_resolution: costume
+                .get("bitmapResolution")
+                .and_then(Value::as_f64)
+                .unwrap_or(1.0),
+            extra: Map::new(),
+        }
+    }
+
+    fn sound(&mut self, sound: &Value) -> Sound {
+        let md5ext = string(sound.get("md5"));
+        let (asset_id, data_format) = split_md5ext(&md5ext);
+        if let Some(sound_id) = sound.get("soundID").and_then(Value::as_i64) {
+            self.asset_names
+                .insert(format!("{}.{}", sound_id, data_format), md5ext.clone());
+        }
+
+        let mut extra = Map::new();
+        extra.insert("format".to_string(), json!(string(sound.get("format"))));
+        Sound {
+            name: string(sound.get("soundName")),
+            md5ext: Some(md5ext.clone()),
+            asset_id: asset_id.to_string(),
+            data_format: data_format.to_string(),
+            rate: sound.get("rate").and_then(Value::as_u64).unwrap_or(0) as u32,
+            sample_count: sound
+                .get("sampleCount")
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as u32,
+            extra,
+        }
+    }
+
+    fn new_id(&mut self) -> BlockID {
+        self.block_count += 1;
+        BlockID::try_from(format!("sb2block{:012}", self.block_count).as_str()).unwrap()
+    }
+
+    fn broadcast_id(&mut self, name: &str) -> String {
+        let id = format!("broadcast:{}", name);
+        self.broadcasts.insert(name.to_string(), id.clone());
+        id
+    }
+
+    /// Converts a stack of blocks and returns the ID of the first block.
+    fn stack(
+        &mut self,
+        blocks: &mut HashMap<BlockID, Block>,
+        scope: &Scope,
+        stack: &[Value],
+        parent: Option<BlockID>,
+    ) -> Result<Option<BlockID>> {
+        let mut first: Option<BlockID> = None;
+        let mut previous = parent;
+        for block in stack {
+            let id = self.block(blocks, scope, block, previous)?;
+            match first {
+                None => first = Some(id),
+                Some(_) => blocks.get_mut(&previous.unwrap()).unwrap().next = Some(id),
+            }
+            previous = Some(id);
+        }
+        Ok(first)
+    }
+
+    fn block(
+        &mut self,
+        blocks: &mut HashMap<BlockID, Block>,
+        scope: &Scope,
+        block: &Value,
+        parent: Option<BlockID>,
+    ) -> Result<BlockID> {
+        let block = block
+            .as_array()
+            .ok_or_else(|| Error::msg("block is not an array"))?;
+        let opcode = block
+            .first()
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::msg("block has no opcode"))?;
+        let args = &block[1..];
+
+        let id = self.new_id();
+        let mut result = new_block("", parent, false);
+        match opcode {
+            "procDef" => self.procedure_definition(blocks, scope, args, id, &mut result),
+            "call" => self.procedure_call(blocks, scope, args, id, &mut result)?,
+            "getParam" => {
+                result.opcode = if string(args.get(1)) == "b" {
+                    "argument_reporter_boolean"
+                } else {
+                    "argument_reporter_string_number"
+                }
+                .to_string();
+                result
+                    .fields
+                    .insert("VALUE".to_string(), vec![Some(string(args.first())), None]);
+            }
+            "readVariable" => {
+                result.opcode = "data_variable".to_string();
+                self.arguments(blocks, scope, id, &mut result, &[Arg::Variable], args)?;
+            }
+            "contentsOfList:" => {
+                result.opcode = "data_listcontents".to_string();
+                self.arguments(blocks, scope, id, &mut result, &[Arg::List], args)?;
+            }
+            "stopScripts" => {
+                result.opcode = "control_stop".to_string();
+                let option = match string(args.first()).as_str() {
+                    "other scripts in stage" => "other scripts in sprite".to_string(),
+                    s => s.to_string(),
+                };
+                let mut mutation = new_mutation();
+                mutation.extra.insert(
+                    "hasnext".to_string(),
+                    json!((option == "other scripts in sprite").to_string()),
+                );
+                result.mutation = Some(mutation);
+                result
+                    .fields
+                    .insert("STOP_OPTION".to_string(), vec![Some(option), None]);
+            }
+            _ => match spec(opcode) {
+                Some((new_opcode, spec)) => {
+                    let category = new_opcode.split('_').next().unwrap_or("");
+                    if matches!(category, "pen" | "music") {
+                        self.extensions.insert(category.to_string());
+                    }
+                    result.opcode = new_opcode.to_string();
+                    self.arguments(blocks, scope, id, &mut result, spec, args)?;
+                }
+                // Blocks without a Scratch 3.0 equivalent are kept in the script without their
+                // arguments, so that the rest of the project can be converted.
+                None => result.opcode = format!("sb2_{}", opcode),
+            },
+        }
+        blocks.insert(id, result);
+        Ok(id)
+    }
+
+    fn arguments(
+        &mut self,
+        blocks: &mut HashMap<BlockID, Block>,
+        scope: &Scope,
+        id: BlockID,
+        result: &mut Block,
+        spec: &[Arg],
+        args: &[Value],
+    ) -> Result<()> {
+        let mut args = args.iter();
+        for arg in spec {
+            if let Arg::ConstField(name, value) = arg {
+                result
+                    .fields
+                    .insert(name.to_string(), vec![Some(value.to_string()), None]);
+                continue;
+            }
+
+            let value = args.next().unwrap_or(&Value::Null);
+            match *arg {
+                Arg::Input(name, primitive) => {
+                    let input = self.input(blocks, scope, id, value, primitive)?;
+                    result.inputs.insert(name.to_string(), input);
+                }
+                Arg::Boolean(name) => {
+                    if is_block(value) {
+                        let reporter_id = self.block(blocks, scope, value, Some(id))?;
+                        result
+                            .inputs
+                            .insert(name.to_string(), json!([2, reporter_id]));
+                    }
+                }
+                Arg::Substack(name) => {
+                    if let Some(first) = self.stack(blocks, scope, array(Some(value)), Some(id))? {
+                        result.inputs.insert(name.to_string(), json!([2, first]));
+                    }
+                }
+                Arg::Field(name) => {
+                    result
+                        .fields
+                        .insert(name.to_string(), vec![Some(string(Some(value))), None]);
+                }
+                Arg::UpperField(name) => {
+                    let field = string(Some(value)).to_uppercase().replace(' ', "");
+                    result
+                        .fields
+                        .insert(name.to_string(), vec![Some(field), None]);
+                }
+                Arg::Variable => {
+                    let name = string(Some(value));
+                    let variable_id = scope.variables.get(&name).cloned();
+                    result
+                        .fields
+                        .insert("VARIABLE".to_string(), vec![Some(name), variable_id]);
+                }
+                Arg::List => {
+                    let name = string(Some(value));
+                    let list_id = scope.lists.get(&name).cloned();
+                    result
+                        .fields
+                        .insert("LIST".to_string(), vec![Some(name), list_id]);
+                }
+                Arg::Menu(name, menu_opcode, field) => {
+                    let menu_id = self.new_id();
+                    let mut menu = new_block(menu_opcode, Some(id), true);
+                    let input = if is_block(value) {
+                        menu.fields
+                            .insert(field.to_string(), vec![Some(String::new()), None]);
+                        let reporter_id = self.block(blocks, scope, value, Some(id))?;
+                        json!([3, reporter_id, menu_id])
+                    } else {
+                        menu.fields
+                            .insert(field.to_string(), vec![Some(string(Some(value))), None]);
+                        json!([1, menu_id])
+                    };
+                    blocks.insert(menu_id, menu);
+                    result.inputs.insert(name.to_string(), input);
+                }
+                Arg::Broadcast => {
+                    let input = if is_block(value) {
+                        let reporter_id = self.block(blocks, scope, value, Some(id))?;
+                        json!([3, reporter_id, [11, "", ""]])
+                    } else {
+                        let name = string(Some(value));
+                        json!([1, [11, name, self.broadcast_id(&name)]])
+                    };
+                    result.inputs.insert("BROADCAST_INPUT".to_string(), input);
+                }
+                Arg::BroadcastField => {
+                    let name = string(Some(value));
+                    let broadcast_id = self.broadcast_id(&name);
+                    result.fields.insert(
+                        "BROADCAST_OPTION".to_string(),
+                        vec![Some(name), Some(broadcast_id)],
+                    );
+                }
+                Arg::ConstField(..) => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a literal or a reporter block to an input with the primitive type.
+    fn input(
+        &mut self,
+        blocks: &mut HashMap<BlockID, Block>,
+        scope: &Scope,
+        parent: BlockID,
+        value: &Value,
+        primitive: i64,
+    ) -> Result<Value> {
+        let shadow = json!([primitive, ""]);
+        if is_block(value) {
+            let name = string(value.get(1));
+            return Ok(match value.get(0).and_then(Value::as_str) {
+                Some("readVariable") => {
+                    json!([3, [12, name, scope.variables.get(&name)], shadow])
+                }
+                Some("contentsOfList:") => json!([3, [13, name, scope.lists.get(&name)], shadow]),
+                _ => {
+                    let reporter_id = self.block(blocks, scope, value, Some(parent))?;
+                    json!([3, reporter_id, shadow])
+                }
+            });
+        }
+
+        let literal = match value {
+            Value::Number(n) if primitive == COLOR => {
+                json!(format!(
+                    "#{:06x}",
+                    n.as_f64().unwrap_or(0.0) as i64 & 0xff_ffff
+                ))
+            }
+            Value::Null => json!(""),
+            _ => value.clone(),
+        };
+        Ok(json!([1, [primitive, literal]]))
+    }
+
+    fn procedure_definition(
+        &mut self,
+        blocks: &mut HashMap<BlockID, Block>,
+        scope: &Scope,
+        args: &[Value],
+        id: BlockID,
+        result: &mut Block,
+    ) {
+        let spec = string(args.first());
+        let (argument_ids, warp) = scope.procedures.get(&spec).cloned().unwrap_or_default();
+        let argument_names: Vec<String> = array(args.get(1))
+            .iter()
+            .map(|name| string(Some(name)))
+            .collect();
+
+        let prototype_id = self.new_id();
+        let mut prototype = new_block("procedures_prototype", Some(id), true);
+        let arguments = argument_ids
+            .iter()
+            .zip(&argument_names)
+            .zip(argument_types(&spec));
+        for ((argument_id, name), argument_type) in arguments {
+            let opcode = if argument_type == 'b' {
+                "argument_reporter_boolean"
+            } else {
+                "argument_reporter_string_number"
+            };
+            let reporter_id = self.new_id();
+            let mut reporter = new_block(opcode, Some(prototype_id), true);
+            reporter
+                .fields
+                .insert("VALUE".to_string(), vec![Some(name.clone()), None]);
+            blocks.insert(reporter_id, reporter);
+            prototype
+                .inputs
+                .insert(argument_id.clone(), json!([1, reporter_id]));
+        }
+
+        let mut mutation = new_mutation();
+        mutation.proccode = proccode(&spec);
+        mutation.argument_ids = json!(argument_ids).to_string();
+        mutation.argument_names = json!(argument_names).to_string();
+        mutation.warp = json!(warp.to_string());
+        mutation.extra.insert(
+            "argumentdefaults".to_string(),
+            json!(json!(args.get(2).cloned().unwrap_or_else(|| json!([]))).to_string()),
+        );
+        prototype.mutation = Some(mutation);
+        blocks.insert(prototype_id, prototype);
+
+        result.opcode = "procedures_definition".to_string();
+        result
+            .inputs
+            .insert("custom_block".to_string(), json!([1, prototype_id]));
+    }
+
+    fn procedure_call(
+        &mut self,
+        blocks: &mut HashMap<BlockID, Block>,
+        scope: &Scope,
+        args: &[Value],
+        id: BlockID,
+        result: &mut Block,
+    ) -> Result<()> {
+        let spec = string(args.first());
+        let (argument_ids, warp) = scope
+            .procedures
+            .get(&spec)
+            .cloned()
+            .ok_or_else(|| Error::msg(format!("custom block does not exist: {}", spec)))?;
+
+        let arguments = argument_ids
+            .iter()
+            .zip(argument_types(&spec))
+            .zip(args.iter().skip(1));
+        for ((argument_id, argument_type), value) in arguments {
+            if argument_type == 'b' {
+                if is_block(value) {
+                    let reporter_id = self.block(blocks, scope, value, Some(id))?;
+                    result
+                        .inputs
+                        .insert(argument_id.clone(), json!([2, reporter_id]));
+                }
+            } else {
+                let input = self.input(blocks, scope, id, value, TEXT)?;
+                result.inputs.insert(argument_id.clone(), input);
+            }
+        }
+
+        let mut mutation = new_mutation();
+        mutation.proccode = proccode(&spec);
+        mutation.argument_ids = json!(argument_ids).to_string();
+        mutation.warp = json!(warp.to_string());
+        result.mutation = Some(mutation);
+        result.opcode = "procedures_call".to_string();
+        Ok(())
+    }
+}
+
+fn new_block(opcode: &str, parent: Option<BlockID>, shadow: bool) -> Block {
+    let mut extra = Map::new();
+    extra.insert("parent".to_string(), json!(parent));
+    extra.insert("shadow".to_string(), json!(shadow));
+    Block {
+        opcode: opcode.to_string(),
+        next: None,
+        inputs: HashMap::default(),
+        fields: HashMap::default(),
+        top_level: false,
+        mutation: None,
+        extra,
+    }
+}
+
+fn new_mutation() -> Mutation {
+    Mutation {
+        tag_name: "mutation".to_string(),
+        ..Default::default()
+    }
+}
+
+fn list_monitors(
+    object: &Map<String, Value>,
+    scope: &Scope,
+    sprite_name: Option<&str>,
+) -> Vec<Monitor> {
+    array(object.get("lists"))
+        .iter()
+        .map(|list| {
+            let name = string(list.get("listName"));
+            let number = |key: &str| list.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+            Monitor {
+                id: scope.lists[&name].clone(),
+                mode: "list".to_string(),
+                opcode: "data_listcontents".to_string(),
+                params: MonitorParams {
+                    list: name,
+                    ..Default::default()
+                },
+                sprite_name: sprite_name.map(str::to_string),
+                value: json!(array(list.get("contents"))),
+                x: number("x"),
+                y: number("y"),
+                visible: list
+                    .get("visible")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+                width: number("width"),
+                height: number("height"),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn variable_monitor(
+    watcher: &Map<String, Value>,
+    scope: &Scope,
+    sprite_name: Option<String>,
+) -> Option<Monitor> {
+    let name = string(watcher.get("param"));
+    let id = scope.variables.get(&name)?;
+    let number = |key: &str| watcher.get(key).and_then(Value::as_f64);
+    let mode = match watcher.get("mode").and_then(Value::as_i64) {
+        Some(2) => "large",
+        Some(3) => "slider",
+        _ => "default",
+    };
+    Some(Monitor {
+        id: id.clone(),
+        mode: mode.to_string(),
+        opcode: "data_variable".to_string(),
+        params: MonitorParams {
+            variable: name,
+            ..Default::default()
+        },
+        sprite_name,
+        value: json!(0),
+        x: number("x").unwrap_or(0.0),
+        y: number("y").unwrap_or(0.0),
+        visible: watcher
+            .get("visible")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        slider_min: Some(number("sliderMin").unwrap_or(0.0)),
+        slider_max: Some(number("sliderMax").unwrap_or(100.0)),
+        is_discrete: Some(
+            watcher
+                .get("isDiscrete")
+                .and_then(Value::as_bool)
+                .unwrap_or(true),
+        ),
+        ..Default::default()
+    })
+}
+
+fn object(value: &Value) -> Result<&Map<String, Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| Error::msg("expected an object"))
+}
+
+/// Returns an empty slice if value is not an array.
+fn array(value: Option<&Value>) -> &[Value] {
+    value
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Blocks are arrays that start with the opcode.
+fn is_block(value: &Value) -> bool {
+    matches!(value.get(0), Some(Value::String(_)))
+}
+
+fn split_md5ext(md5ext: &str) -> (&str, &str) {
+    let mut parts = md5ext.splitn(2, '.');
+    (parts.next().unwrap_or(""), parts.next().unwrap_or(""))
+}
+
+/// Returns the argument types of a custom block spec, such as ['n', 'b'] for "jump %n %b".
+fn argument_types(spec: &str) -> Vec<char> {
+    spec.split('%')
+        .skip(1)
+        .filter_map(|s| s.chars().next())
+        .filter(|c| matches!(c, 'n' | 's' | 'b'))
+        .collect()
+}
+
+/// Scratch 3.0 uses %s for number arguments.
+fn proccode(spec: &str) -> String {
+    spec.replace("%n", "%s")
+}
+
+const NUMBER: i64 = 4;
+const POSITIVE_NUMBER: i64 = 5;
+const WHOLE_NUMBER: i64 = 6;
+const INTEGER: i64 = 7;
+const ANGLE: i64 = 8;
+const COLOR: i64 = 9;
+const TEXT: i64 = 10;
+
+/// Describes how an argument of a Scratch 2.0 block is stored in Scratch 3.0.
+#[derive(Debug, Copy, Clone)]
+enum Arg {
+    /// Input with the primitive type of the literal
+    Input(&'static str, i64),
+    Boolean(&'static str),
+    Substack(&'static str),
+    Field(&'static str),
+    /// Field that is uppercase without spaces in Scratch 3.0
+    UpperField(&'static str),
+    /// Field that does not exist in Scratch 2.0. It does not use an argument.
+    ConstField(&'static str, &'static str),
+    Variable,
+    List,
+    /// Input with a shadow menu block. (input name, menu opcode, menu field name)
+    Menu(&'static str, &'static str, &'static str),
+    Broadcast,
+    BroadcastField,
+}
+
+/// Returns the Scratch 3.0 opcode and the arguments of a Scratch 2.0 opcode.
+fn spec(opcode: &str) -> Option<(&'static str, &'static [Arg])> {
+    use Arg::*;
+
+    Some(match opcode {
+        // Motion
+        "forward:" => ("motion_movesteps", &[Input("STEPS", NUMBER)]),
+        "turnRight:" => ("motion_turnright", &[Input("DEGREES", NUMBER)]),
+        "turnLeft:" => ("motion_turnleft", &[Input("DEGREES", NUMBER)]),
+        "heading:" => ("motion_pointindirection", &[Input("DIRECTION", ANGLE)]),
+        "pointTowards:" => (
+            "motion_pointtowards",
+            &[Menu("TOWARDS", "motion_pointtowards_menu", "TOWARDS")],
+        ),
+        "gotoX:y:" => ("motion_gotoxy", &[Input("X", NUMBER), Input("Y", NUMBER)]),
+        "gotoSpriteOrMouse:" => ("motion_goto", &[Menu("TO", "motion_goto_menu", "TO")]),
+        "glideSecs:toX:y:elapsed:from:" => (
+            "motion_glidesecstoxy",
+            &[
+                Input("SECS", NUMBER),
+                Input("X", NUMBER),
+                Input("Y", NUMBER),
+            ],
+        ),
+        "changeXposBy:" => ("motion_changexby", &[Input("DX", NUMBER)]),
+        "xpos:" => ("motion_setx", &[Input("X", NUMBER)]),
+        "changeYposBy:" => ("motion_changeyby", &[Input("DY", NUMBER)]),
+        "ypos:" => ("motion_sety", &[Input("Y", NUMBER)]),
+        "bounceOffEdge" => ("motion_ifonedgebounce", &[]),
+        "setRotationStyle" => ("motion_setrotationstyle", &[Field("STYLE")]),
+        "xpos" => ("motion_xposition", &[]),
+        "ypos" => ("motion_yposition", &[]),
+        "heading" => ("motion_direction", &[]),
+
+        // Looks
+        "say:duration:elapsed:from:" => (
+            "looks_sayforsecs",
+            &[Input("MESSAGE", TEXT), Input("SECS", NUMBER)],
+        ),
+        "say:" => ("looks_say", &[Input("MESSAGE", TEXT)]),
+        "think:duration:elapsed:from:" => (
+            "looks_thinkforsecs",
+            &[Input("MESSAGE", TEXT), Input("SECS", NUMBER)],
+        ),
+        "think:" => ("looks_think", &[Input("MESSAGE", TEXT)]),
+        "show" => ("looks_show", &[]),
+        "hide" => ("looks_hide", &[]),
+        "lookLike:" => (
+            "looks_switchcostumeto",
+            &[Menu("COSTUME", "looks_costume", "COSTUME")],
+        ),
+        "nextCostume" => ("looks_nextcostume", &[]),
+        "startScene" => (
+            "looks_switchbackdropto",
+            &[Menu("BACKDROP", "looks_backdrops", "BACKDROP")],
+        ),
+        "nextScene" => ("looks_nextbackdrop", &[]),
+        "changeGraphicEffect:by:" => (
+            "looks_changeeffectby",
+            &[UpperField("EFFECT"), Input("CHANGE", NUMBER)],
+        ),
+        "setGraphicEffect:to:" => (
+            "looks_seteffectto",
+            &[UpperField("EFFECT"), Input("VALUE", NUMBER)],
+        ),
+        "filterReset" => ("looks_cleargraphiceffects", &[]),
+        "changeSizeBy:" => ("looks_changesizeby", &[Input("CHANGE", NUMBER)]),
+        "setSizeTo:" => ("looks_setsizeto", &[Input("SIZE", NUMBER)]),
+        "comeToFront" => ("looks_gotofrontback", &[ConstField("FRONT_BACK", "front")]),
+        "goBackByLayers:" => (
+            "looks_goforwardbackwardlayers",
+            &[
+                ConstField("FORWARD_BACKWARD", "backward"),
+                Input("NUM", INTEGER),
+            ],
+        ),
+        "costumeIndex" => (
+            "looks_costumenumbername",
+            &[ConstField("NUMBER_NAME", "number")],
+        ),
+        "sceneName" => (
+            "looks_backdropnumbername",
+            &[ConstField("NUMBER_NAME", "name")],
+        ),
+        "backgroundIndex" => (
+            "looks_backdropnumbername",
+            &[ConstField("NUMBER_NAME", "number")],
+        ),
+        "startSceneAndWait" => (
+            "looks_switchbackdroptoandwait",
+            &[Menu("BACKDROP", "looks_backdrops", "BACKDROP")],
+        ),
+        "scale" => ("looks_size", &[]),
+
+        // Sound
+        "playSound:" => (
+            "sound_play",
+            &[Menu("SOUND_MENU", "sound_sounds_menu", "SOUND_MENU")],
+        ),
+        "doPlaySoundAndWait" => (
+            "sound_playuntildone",
+            &[Menu("SOUND_MENU", "sound_sounds_menu", "SOUND_MENU")],
+        ),
+        "stopAllSounds" => ("sound_stopallsounds", &[]),
+        "changeVolumeBy:" => ("sound_changevolumeby", &[Input("VOLUME", NUMBER)]),
+        "setVolumeTo:" => ("sound_setvolumeto", &[Input("VOLUME", NUMBER)]),
+        "volume" => ("sound_volume", &[]),
+
+        // Pen
+        "clearPenTrails" => ("pen_clear", &[]),
+        "stampCostume" => ("pen_stamp", &[]),
+        "putPenDown" => ("pen_penDown", &[]),
+        "putPenUp" => ("pen_penUp", &[]),
+        "penColor:" => ("pen_setPenColorToColor", &[Input("COLOR", COLOR)]),
+        "changePenSizeBy:" => ("pen_changePenSizeBy", &[Input("SIZE", NUMBER)]),
+        "penSize:" => ("pen_setPenSizeTo", &[Input("SIZE", NUMBER)]),
+        "setPenHueTo:" => ("pen_setPenHueToNumber", &[Input("HUE", NUMBER)]),
+        "changePenHueBy:" => ("pen_changePenHueBy", &[Input("HUE", NUMBER)]),
+        "setPenShadeTo:" => ("pen_setPenShadeToNumber", &[Input("SHADE", NUMBER)]),
+        "changePenShadeBy:" => ("pen_changePenShadeBy", &[Input("SHADE", NUMBER)]),
+
+        // Music
+        "playDrum" | "drum:duration:elapsed:from:" => (
+            "music_playDrumForBeats",
+            &[
+                Menu("DRUM", "music_menu_DRUM", "DRUM"),
+                Input("BEATS", NUMBER),
+            ],
+        ),
+        "rest:elapsed:from:" => ("music_restForBeats", &[Input("BEATS", NUMBER)]),
+        "noteOn:duration:elapsed:from:" => (
+            "music_playNoteForBeats",
+            &[Menu("NOTE", "note", "NOTE"), Input("BEATS", NUMBER)],
+        ),
+        "instrument:" | "midiInstrument:" => (
+            "music_setInstrument",
+            &[Menu("INSTRUMENT", "music_menu_INSTRUMENT", "INSTRUMENT")],
+        ),
+        "changeTempoBy:" => ("music_changeTempo", &[Input("TEMPO", NUMBER)]),
+        "setTempoTo:" => ("music_setTempo", &[Input("TEMPO", NUMBER)]),
+        "tempo" => ("music_getTempo", &[]),
+
+        // Events
+        "whenGreenFlag" => ("event_whenflagclicked", &[]),
+        "whenKeyPressed" => ("event_whenkeypressed", &[Field("KEY_OPTION")]),
+        "whenClicked" => ("event_whenthisspriteclicked", &[]),
+        "whenSceneStarts" => ("event_whenbackdropswitchesto", &[Field("BACKDROP")]),
+        "whenSensorGreaterThan" => (
+            "event_whengreaterthan",
+            &[UpperField("WHENGREATERTHANMENU"), Input("VALUE", NUMBER)],
+        ),
+        "whenIReceive" => ("event_whenbroadcastreceived", &[BroadcastField]),
+        "broadcast:" => ("event_broadcast", &[Broadcast]),
+        "doBroadcastAndWait" => ("event_broadcastandwait", &[Broadcast]),
+
+        // Control
+        "wait:elapsed:from:" => ("control_wait", &[Input("DURATION", POSITIVE_NUMBER)]),
+        "doRepeat" => (
+            "control_repeat",
+            &[Input("TIMES", WHOLE_NUMBER), Substack("SUBSTACK")],
+        ),
+        "doForever" => ("control_forever", &[Substack("SUBSTACK")]),
+        "doIf" => ("control_if", &[Boolean("CONDITION"), Substack("SUBSTACK")]),
+        "doIfElse" => (
+            "control_if_else",
+            &[
+                Boolean("CONDITION"),
+                Substack("SUBSTACK"),
+                Substack("SUBSTACK2"),
+            ],
+        ),
+        "doWaitUntil" => ("control_wait_until", &[Boolean("CONDITION")]),
+        "doUntil" => (
+            "control_repeat_until",
+            &[Boolean("CONDITION"), Substack("SUBSTACK")],
+        ),
+        "whenCloned" => ("control_start_as_clone", &[]),
+        "createCloneOf" => (
+            "control_create_clone_of",
+            &[Menu(
+                "CLONE_OPTION",
+                "control_create_clone_of_menu",
+                "CLONE_OPTION",
+            )],
+        ),
+        "deleteClone" => ("control_delete_this_clone", &[]),
+
+        // Sensing
+        "touching:" => (
+            "sensing_touchingobject",
+            &[Menu(
+                "TOUCHINGOBJECTMENU",
+                "sensing_touchingobjectmenu",
+                "TOUCHINGOBJECTMENU",
+            )],
+        ),
+        "touchingColor:" => ("sensing_touchingcolor", &[Input("COLOR", COLOR)]),
+        "color:sees:" => (
+            "sensing_coloristouchingcolor",
+            &[Input("COLOR", COLOR), Input("COLOR2", COLOR)],
+        ),
+        "distanceTo:" => (
+            "sensing_distanceto",
+            &[Menu(
+                "DISTANCETOMENU",
+                "sensing_distancetomenu",
+                "DISTANCETOMENU",
+            )],
+        ),
+        "getAttribute:of:" => (
+            "sensing_of",
+            &[
+                Field("PROPERTY"),
+                Menu("OBJECT", "sensing_of_object_menu", "OBJECT"),
+            ],
+        ),
+        "doAsk" => ("sensing_askandwait", &[Input("QUESTION", TEXT)]),
+        "answer" => ("sensing_answer", &[]),
+        "keyPressed:" => (
+            "sensing_keypressed",
+            &[Menu("KEY_OPTION", "sensing_keyoptions", "KEY_OPTION")],
+        ),
+        "mousePressed" => ("sensing_mousedown", &[]),
+        "mouseX" => ("sensing_mousex", &[]),
+        "mouseY" => ("sensing_mousey", &[]),
+        "soundLevel" => ("sensing_loudness", &[]),
+        "timer" => ("sensing_timer", &[]),
+        "timerReset" => ("sensing_resettimer", &[]),
+        "timeAndDate" => ("sensing_current", &[UpperField("CURRENTMENU")]),
+        "timestamp" => ("sensing_dayssince2000", &[]),
+        "getUserName" => ("sensing_username", &[]),
+        "dragMode" => ("sensing_setdragmode", &[Field("DRAG_MODE")]),
+
+        // Operators
+        "+" => (
+            "operator_add",
+            &[Input("NUM1", NUMBER), Input("NUM2", NUMBER)],
+        ),
+        "-" => (
+            "operator_subtract",
+            &[Input("NUM1", NUMBER), Input("NUM2", NUMBER)],
+        ),
+        "*" => (
+            "operator_multiply",
+            &[Input("NUM1", NUMBER), Input("NUM2", NUMBER)],
+        ),
+        "/" => (
+            "operator_divide",
+            &[Input("NUM1", NUMBER), Input("NUM2", NUMBER)],
+        ),
+        "%" => (
+            "operator_mod",
+            &[Input("NUM1", NUMBER), Input("NUM2", NUMBER)],
+        ),
+        "randomFrom:to:" => (
+            "operator_random",
+            &[Input("FROM", NUMBER), Input("TO", NUMBER)],
+        ),
+        "<" => (
+            "operator_lt",
+            &[Input("OPERAND1", TEXT), Input("OPERAND2", TEXT)],
+        ),
+        ">" => (
+            "operator_gt",
+            &[Input("OPERAND1", TEXT), Input("OPERAND2", TEXT)],
+        ),
+        "=" => (
+            "operator_equals",
+            &[Input("OPERAND1", TEXT), Input("OPERAND2", TEXT)],
+        ),
+        "&" => ("operator_and", &[Boolean("OPERAND1"), Boolean("OPERAND2")]),
+        "|" => ("operator_or", &[Boolean("OPERAND1"), Boolean("OPERAND2")]),
+        "not" => ("operator_not", &[Boolean("OPERAND")]),
+        "concatenate:with:" => (
+            "operator_join",
+            &[Input("STRING1", TEXT), Input("STRING2", TEXT)],
+        ),
+        "letter:of:" => (
+            "operator_letter_of",
+            &[Input("LETTER", WHOLE_NUMBER), Input("STRING", TEXT)],
+        ),
+        "stringLength:" => ("operator_length", &[Input("STRING", TEXT)]),
+        "rounded" => ("operator_round", &[Input("NUM", NUMBER)]),
+        "computeFunction:of:" => (
+            "operator_mathop",
+            &[Field("OPERATOR"), Input("NUM", NUMBER)],
+        ),
+
+        // Data
+        "setVar:to:" => ("data_setvariableto", &[Variable, Input("VALUE", TEXT)]),
+        "changeVar:by:" => ("data_changevariableby", &[Variable, Input("VALUE", NUMBER)]),
+        "showVariable:" => ("data_showvariable", &[Variable]),
+        "hideVariable:" => ("data_hidevariable", &[Variable]),
+        "append:toList:" => ("data_addtolist", &[Input("ITEM", TEXT), List]),
+        "deleteLine:ofList:" => ("data_deleteoflist", &[Input("INDEX", INTEGER), List]),
+        "insert:at:ofList:" => (
+            "data_insertatlist",
+            &[Input("ITEM", TEXT), Input("INDEX", INTEGER), List],
+        ),
+        "setLine:ofList:to:" => (
+            "data_replaceitemoflist",
+            &[Input("INDEX", INTEGER), List, Input("ITEM", TEXT)],
+        ),
+        "getLine:ofList:" => ("data_itemoflist", &[Input("INDEX", INTEGER), List]),
+        "lineCountOfList:" => ("data_lengthoflist", &[List]),
+        "list:contains:" => ("data_listcontainsitem", &[List, Input("ITEM", TEXT)]),
+        "showList:" => ("data_showlist", &[List]),
+        "hideList:" => ("data_hidelist", &[List]),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> Value {
+        json!({
+            "objName": "Stage",
+            "variables": [{"name": "score", "value": 0, "isPersistent": false}],
+            "scripts": [],
+            "costumes": [{
+                "costumeName": "backdrop1",
+                "baseLayerID": 0,
+                "baseLayerMD5": "aaaa.svg",
+                "bitmapResolution": 1,
+                "rotationCenterX": 240,
+                "rotationCenterY": 180
+            }],
+            "sounds": [],
+            "currentCostumeIndex": 0,
+            "children": [
+                {
+                    "objName": "Sprite1",
+                    "variables": [],
+                    "lists": [{"listName": "items", "contents": ["a"], "isPersistent": false,
+                        "x": 5, "y": 5, "width": 100, "height": 200, "visible": true}],
+                    "scripts": [
+                        [10, 20, [
+                            ["whenGreenFlag"],
+                            ["setVar:to:", "score", 0],
+                            ["doRepeat", 10, [
+                                ["changeVar:by:", "score", ["+", ["readVariable", "score"], 1]],
+                                ["call", "jump %n", 5]
+                            ]],
+                            ["append:toList:", "b", "items"]
+                        ]],
+                        [10, 200, [
+                            ["procDef", "jump %n", ["height"], [1], true],
+                            ["changeYposBy:", ["getParam", "height", "r"]]
+                        ]]
+                    ],
+                    "costumes": [{
+                        "costumeName": "costume1",
+                        "baseLayerID": 1,
+                        "baseLayerMD5": "bbbb.png",
+                        "bitmapResolution": 2,
+                        "rotationCenterX": 10,
+                        "rotationCenterY": 10
+                    }],
+                    "sounds": [{"soundName": "pop", "soundID": 0, "md5": "cccc.wav",
+                        "sampleCount": 100, "rate": 1000, "format": ""}],
+                    "scratchX": 30,
+                    "scratchY": -40,
+                    "scale": 0.5,
+                    "direction": 90,
+                    "rotationStyle": "leftRight",
+                    "isDraggable": false,
+                    "visible": true
+                },
+                {"target": "Stage", "cmd": "getVar:", "param": "score", "mode": 1,
+                    "sliderMin": 0, "sliderMax": 100, "isDiscrete": true, "x": 5, "y": 5,
+                    "visible": true}
+            ]
+        })
+    }
+
+    fn find<'a>(target: &'a Target, opcode: &str) -> (&'a BlockID, &'a Block) {
+        target
+            .blocks
+            .iter()
+            .find(|(_, block)| block.opcode == opcode)
+            .unwrap()
+    }
+
+    #[test]
+    fn convert_targets() {
+        let (project, asset_names) = convert(&project()).unwrap();
+        assert_eq!(project.targets.len(), 2);
+
+        let stage = &project.targets[0];
+        assert!(stage.is_stage);
+        assert_eq!(stage.variables["Stage:variable:score"].id, "score");
+        assert_eq!(stage.costumes[0].md5ext.as_deref(), Some("aaaa.svg"));
+
+        let sprite = &project.targets[1];
+        assert_eq!(sprite.name, "Sprite1");
+        assert_eq!(sprite.layer_order, 1);
+        assert_eq!(sprite.x, Some(30.0));
+        assert_eq!(sprite.size, Some(50.0));
+        assert_eq!(sprite.direction, Some(90.0));
+        assert_eq!(sprite.rotation_style.as_deref(), Some("left-right"));
+        assert_eq!(sprite.lists["Sprite1:list:items"].values, vec![json!("a")]);
+        assert_eq!(sprite.sounds[0].duration().as_millis(), 100);
+
+        assert_eq!(asset_names["0.svg"], "aaaa.svg");
+        assert_eq!(asset_names["1.png"], "bbbb.png");
+        assert_eq!(asset_names["0.wav"], "cccc.wav");
+
+        assert_eq!(project.monitors.len(), 2);
+        assert!(project
+            .monitors
+            .iter()
+            .any(|m| m.id == "Stage:variable:score" && m.opcode == "data_variable"));
+        assert!(project
+            .monitors
+            .iter()
+            .any(|m| m.id == "Sprite1:list:items" && m.width == 100.0));
+    }
+
+    #[test]
+    fn convert_blocks() {
+        let (project, _) = convert(&project()).unwrap();
+        let sprite = &project.targets[1];
+
+        let (hat_id, hat) = find(sprite, "event_whenflagclicked");
+        assert!(hat.top_level);
+        assert_eq!(hat.extra["x"], json!(10));
+
+        let (set_id, set) = find(sprite, "data_setvariableto");
+        assert_eq!(hat.next, Some(*set_id));
+        assert_eq!(set.extra["parent"], json!(hat_id));
+        assert_eq!(
+            set.fields["VARIABLE"],
+            vec![
+                Some("score".to_string()),
+                Some("Stage:variable:score".to_string())
+            ]
+        );
+        assert_eq!(set.inputs["VALUE"], json!([1, [10, 0]]));
+
+        let (repeat_id, repeat) = find(sprite, "control_repeat");
+        assert_eq!(repeat.inputs["TIMES"], json!([1, [6, 10]]));
+        let (change_id, change) = find(sprite, "data_changevariableby");
+        assert_eq!(repeat.inputs["SUBSTACK"], json!([2, change_id]));
+        assert_eq!(change.extra["parent"], json!(repeat_id));
+
+        let (add_id, add) = find(sprite, "operator_add");
+        assert_eq!(change.inputs["VALUE"], json!([3, add_id, [4, ""]]));
+        assert_eq!(
+            add.inputs["NUM1"],
+            json!([3, [12, "score", "Stage:variable:score"], [4, ""]])
+        );
+
+        let (_, append) = find(sprite, "data_addtolist");
+        assert_eq!(
+            append.fields["LIST"],
+            vec![
+                Some("items".to_string()),
+                Some("Sprite1:list:items".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn convert_procedures() {
+        let (project, _) = convert(&project()).unwrap();
+        let sprite = &project.targets[1];
+
+        let (prototype_id, prototype) = find(sprite, "procedures_prototype");
+        let prototype_mutation = prototype.mutation.as_ref().unwrap();
+        assert_eq!(prototype_mutation.proccode, "jump %s");
+        assert_eq!(prototype_mutation.argument_names().unwrap(), vec!["height"]);
+        assert!(prototype_mutation.warp());
+
+        let (_, definition) = find(sprite, "procedures_definition");
+        assert_eq!(definition.inputs["custom_block"], json!([1, prototype_id]));
+
+        let (_, call) = find(sprite, "procedures_call");
+        let call_mutation = call.mutation.as_ref().unwrap();
+        assert_eq!(call_mutation.proccode, "jump %s");
+        let argument_ids = call_mutation.argument_ids().unwrap();
+        assert_eq!(argument_ids, prototype_mutation.argument_ids().unwrap());
+        assert_eq!(call.inputs[&argument_ids[0]], json!([1, [10, 5]]));
+
+        let reporters: Vec<&Block> = sprite
+            .blocks
+            .values()
+            .filter(|b| b.opcode == "argument_reporter_string_number")
+            .collect();
+        assert_eq!(reporters.len(), 2);
+        assert!(reporters
+            .iter()
+            .all(|b| b.fields["VALUE"] == vec![Some("height".to_string()), None]));
+    }
+
+    #[test]
+    fn unknown_block() {
+        let mut project = project();
+        project["scripts"] = json!([[0, 0, [["whenGreenFlag"], ["noSuchBlock", 1], ["show"]]]]);
+        let (project, _) = convert(&project).unwrap();
+        let stage = &project.targets[0];
+
+        let (unknown_id, unknown) = find(stage, "sb2_noSuchBlock");
+        assert!(unknown.inputs.is_empty());
+        assert_eq!(
+            find(stage, "event_whenflagclicked").1.next,
+            Some(*unknown_id)
+        );
+        assert_eq!(unknown.next, Some(*find(stage, "looks_show").0));
+    }
+
+    #[test]
+    fn convert_extension_blocks() {
+        let mut project = project();
+        project["scripts"] = json!([[
+            0,
+            0,
+            [
+                ["whenGreenFlag"],
+                [
+                    "setPenHueTo:",
+                    ["getAttribute:of:", "x position", "Sprite1"]
+                ],
+                ["changeTempoBy:", ["soundLevel"]]
+            ]
+        ]]);
+        let (project, _) = convert(&project).unwrap();
+        assert_eq!(project.extensions, vec!["music", "pen"]);
+        let stage = &project.targets[0];
+
+        let (of_id, of) = find(stage, "sensing_of");
+        assert_eq!(
+            of.fields["PROPERTY"],
+            vec![Some("x position".to_string()), None]
+        );
+        let (_, hue) = find(stage, "pen_setPenHueToNumber");
+        assert_eq!(hue.inputs["HUE"], json!([3, of_id, [4, ""]]));
+
+        let (loudness_id, _) = find(stage, "sensing_loudness");
+        let (_, tempo) = find(stage, "music_changeTempo");
+        assert_eq!(tempo.inputs["TEMPO"], json!([3, loudness_id, [4, ""]]));
+    }
+
+    #[test]
+    fn test_argument_types() {
+        assert_eq!(argument_types("jump %n"), vec!['n']);
+        assert_eq!(argument_types("a %s b %b 100%"), vec!['s', 'b']);
+        assert!(argument_types("no arguments").is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use super::*;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use scratch_file::Audio;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Plays sounds.
+pub trait Mixer: Debug + Send + Sync {
+    /// Starts playing the sound and returns without waiting for it to end.
+    fn play(&self, sound: &Sound) -> Result<()>;
+
+    fn stop_all(&self);
+}
+
+#[derive(Debug, Clone)]
+pub struct Sound {
+    name: String,
+    audio: Arc<Audio>,
+    duration: Duration,
+}
+
+impl Sound {
+    pub fn new(sound: &scratch_file::Sound, sounds: &HashMap<String, Audio>) -> Result<Self> {
+        let md5ext = sound
+            .md5ext
+            .as_ref()
+            .ok_or_else(|| Error::msg(format!("sound {} has no file", sound.name)))?;
+        let audio = sounds
+            .get(md5ext)
+            .ok_or_else(|| Error::msg(format!("sound not found: {}", md5ext)))?;
+        Ok(Self {
+            name: sound.name.clone(),
+            audio: Arc::new(audio.clone()),
+            duration: sound.duration(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn data(&self) -> &[u8] {
+        match self.audio.as_ref() {
+            Audio::WAV(b) | Audio::MP3(b) => b,
+        }
+    }
+}
+
+/// Plays sounds on the default output device.
+pub struct RodioMixer {
+    handle: OutputStreamHandle,
+    sinks: Mutex<Vec<Sink>>,
+}
+
+impl RodioMixer {
+    pub fn new() -> Result<Self> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // OutputStream cannot be sent between threads and the output stops when it is dropped,
+        // so it is kept in its own thread.
+        std::thread::spawn(move || match OutputStream::try_default() {
+            Ok((_stream, handle)) => {
+                if sender.send(Ok(handle)).is_ok() {
+                    loop {
+                        std::thread::park();
+                    }
+                }
+            }
+            Err(e) => {
+                sender.send(Err(Error::msg(e.to_string()))).ok();
+            }
+        });
+
+        Ok(Self {
+            handle: receiver.recv()??,
+            sinks: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+impl Mixer for RodioMixer {
+    fn play(&self, sound: &Sound) -> Result<()> {
+        let sink = Sink::try_new(&self.handle)?;
+        sink.append(Decoder::new(Cursor::new(sound.data().to_vec()))?);
+
+        let mut sinks = self.sinks.lock().map_err(|_| Error::msg("lock poisoned"))?;
+        sinks.retain(|sink| !sink.empty());
+        sinks.push(sink);
+        Ok(())
+    }
+
+    fn stop_all(&self) {
+        if let Ok(mut sinks) = self.sinks.lock() {
+            for sink in sinks.drain(..) {
+                sink.stop();
+            }
+        }
+    }
+}
+
+impl Debug for RodioMixer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RodioMixer").finish()
+    }
+}
+
+/// Does not output audio. Used when there is no output device.
+#[derive(Debug, Default)]
+pub struct NullMixer;
+
+impl Mixer for NullMixer {
+    fn play(&self, _: &Sound) -> Result<()> {
+        Ok(())
+    }
+
+    fn stop_all(&self) {}
+}
+
+/// Does not output audio. Calls are recorded so that tests can check which sounds were played.
+#[derive(Debug, Default)]
+pub struct RecordingMixer {
+    events: Mutex<Vec<(MixerEvent, Instant)>>,
+}
+
+impl RecordingMixer {
+    pub fn events(&self) -> Vec<(MixerEvent, Instant)> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl Mixer for RecordingMixer {
+    fn play(&self, sound: &Sound) -> Result<()> {
+        self.events
+            .lock()
+            .map_err(|_| Error::msg("lock poisoned"))?
+            .push((MixerEvent::Play(sound.name.clone()), Instant::now()));
+        Ok(())
+    }
+
+    fn stop_all(&self) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push((MixerEvent::StopAll, Instant::now()));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MixerEvent {
+    /// Contains the name of the sound
+    Play(String),
+    StopAll,
+}
@@ -4,14 +4,13 @@ use crate::broadcaster::BroadcastMsg;
 use crate::vm::ThreadID;
 use std::str::FromStr;
 use strum::EnumString;
-use tokio::time::interval;
 
 pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
         "if" => Box::new(If::new(id)),
         "forever" => Box::new(Forever::new(id)),
         "repeat" => Box::new(Repeat::new(id)),
-        "wait" => Box::new(Wait::new(id)),
+        "wait" => Box::new(Wait::new(id, runtime)),
         "repeat_until" => Box::new(RepeatUntil::new(id)),
         "if_else" => Box::new(IfElse::new(id)),
         "wait_until" => Box::new(WaitUntil::new(id)),
@@ -30,7 +29,6 @@ pub struct If {
     condition: Box<dyn Block>,
     next: Option<BlockID>,
     substack: Option<BlockID>,
-    done: bool,
 }
 
 impl If {
@@ -40,7 +38,6 @@ impl If {
             condition: Box::new(EmptyFalse {}),
             next: None,
             substack: None,
-            done: false,
         }
     }
 }
@@ -78,17 +75,14 @@ impl Block for If {
     }
 
     async fn execute(&mut self) -> Result<Next> {
-        if self.done {
-            self.done = false;
-            return Next::continue_(self.next);
-        }
-
-        self.done = true;
-
-        if self.condition.value().await?.try_into()? {
-            return Next::loop_(self.substack);
+        let condition: bool = self.condition.value().await?.try_into()?;
+        match self.substack {
+            Some(substack) if condition => Ok(Next::Branch(substack)),
+            _ => Next::continue_(self.next),
         }
+    }
 
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
         Next::continue_(self.next)
     }
 }
@@ -96,16 +90,20 @@ impl Block for If {
 #[derive(Debug)]
 pub struct Wait {
     id: BlockID,
+    runtime: Runtime,
     next: Option<BlockID>,
     duration: Box<dyn Block>,
+    end: Option<Duration>,
 }
 
 impl Wait {
-    pub fn new(id: BlockID) -> Self {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
         Self {
             id,
+            runtime,
             next: None,
             duration: Box::new(EmptyInput {}),
+            end: None,
         }
     }
 }
@@ -142,9 +140,21 @@ impl Block for Wait {
 
     async fn execute(&mut self) -> Result<Next> {
         let duration: f64 = self.duration.value().await?.try_into()?;
-        sleep(Duration::from_secs_f64(duration)).await;
+        self.end = Some(wait_end(&self.runtime, duration));
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if waiting(&self.runtime, self.end) {
+            return Ok(Next::Loop(self.id));
+        }
+        self.end = None;
         Next::continue_(self.next)
     }
+
+    async fn reset(&mut self) {
+        self.end = None;
+    }
 }
 
 #[derive(Debug)]
@@ -184,10 +194,8 @@ impl Block for Forever {
     }
 
     async fn execute(&mut self) -> Result<Next> {
-        Ok(match &self.substack {
-            Some(b) => Next::Loop(*b),
-            None => Next::None,
-        })
+        // An empty loop loops to itself, so that it yields once per frame
+        Ok(Next::Loop(self.substack.unwrap_or(self.id)))
     }
 }
 
@@ -197,7 +205,9 @@ pub struct Repeat {
     times: Box<dyn Block>,
     next: Option<BlockID>,
     substack: Option<BlockID>,
-    count: usize,
+    /// Number of iterations, which is read when the loop starts. A recursive procedure call
+    /// starts the loop again before the outer loop ends, so the innermost loop is last.
+    counts: Vec<usize>,
 }
 
 impl Repeat {
@@ -207,7 +217,7 @@ impl Repeat {
             times: Box::new(EmptyInput {}),
             next: None,
             substack: None,
-            count: 0,
+            counts: Vec::new(),
         }
     }
 }
@@ -246,15 +256,22 @@ impl Block for Repeat {
 
     async fn execute(&mut self) -> Result<Next> {
         let times: f64 = self.times.value().await?.try_into()?;
-        if self.count < times as usize {
-            // Loop until count equals times
-            self.count += 1;
-            return Next::loop_(self.substack);
+        self.counts.push(times.round() as usize);
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, iterations: usize) -> Result<Next> {
+        if iterations < self.counts.last().copied().unwrap_or(0) {
+            return Ok(Next::Loop(self.substack.unwrap_or(self.id)));
         }
 
-        self.count = 0;
+        self.counts.pop();
         Next::continue_(self.next)
     }
+
+    async fn reset(&mut self) {
+        self.counts.clear();
+    }
 }
 
 #[derive(Debug)]
@@ -315,7 +332,7 @@ impl Block for RepeatUntil {
             return Next::continue_(self.next);
         }
 
-        Next::loop_(self.substack)
+        Ok(Next::Loop(self.substack.unwrap_or(self.id)))
     }
 }
 
@@ -326,7 +343,6 @@ pub struct IfElse {
     condition: Box<dyn Block>,
     substack_true: Option<BlockID>,
     substack_false: Option<BlockID>,
-    done: bool,
 }
 
 impl IfElse {
@@ -337,7 +353,6 @@ impl IfElse {
             condition: Box::new(EmptyFalse {}),
             substack_true: None,
             substack_false: None,
-            done: false,
         }
     }
 }
@@ -380,18 +395,20 @@ impl Block for IfElse {
     }
 
     async fn execute(&mut self) -> Result<Next> {
-        if self.done {
-            self.done = false;
-            return Next::continue_(self.next);
-        }
-
-        self.done = true;
-
-        if self.condition.value().await?.try_into()? {
-            return Next::loop_(self.substack_true);
+        let condition: bool = self.condition.value().await?.try_into()?;
+        let substack = if condition {
+            self.substack_true
+        } else {
+            self.substack_false
+        };
+        match substack {
+            Some(substack) => Ok(Next::Branch(substack)),
+            None => Next::continue_(self.next),
         }
+    }
 
-        Next::loop_(self.substack_false)
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        Next::continue_(self.next)
     }
 }
 
@@ -443,13 +460,11 @@ impl Block for WaitUntil {
     }
 
     async fn execute(&mut self) -> Result<Next> {
-        let mut interval = interval(Duration::from_millis(10));
-        loop {
-            if self.condition.value().await?.try_into()? {
-                return Next::continue_(self.next);
-            }
-            interval.tick().await;
+        if self.condition.value().await?.try_into()? {
+            return Next::continue_(self.next);
         }
+        // The condition is checked again in the next frame
+        Ok(Next::Loop(self.id))
     }
 }
 
@@ -494,6 +509,10 @@ impl Block for StartAsClone {
         }
     }
 
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::StartAsClone)
+    }
+
     async fn execute(&mut self) -> Result<Next> {
         if self.runtime.sprite.read().await.is_a_clone() {
             Next::continue_(self.next)
@@ -716,11 +735,11 @@ mod tests {
     use super::*;
     use crate::blocks::test::{BlockStub, BlockStubMsg};
     use crate::blocks::value::{ValueBool, ValueNumber};
+    use crate::clock::VirtualClock;
     use crate::runtime::Global;
     use crate::sprite::SpriteID;
     use crate::sprite_runtime::SpriteRuntime;
     use crate::thread::{StepStatus, Thread};
-    use futures::FutureExt;
 
     #[tokio::test]
     async fn if_block() {
@@ -792,6 +811,50 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn if_body_does_not_yield() {
+        let runtime = Runtime::default();
+        let mut receiver = runtime.global.broadcaster.subscribe();
+
+        let mut gen = BlockIDGenerator::new();
+        let repeat_id = gen.get_id();
+        let if_id = gen.get_id();
+        let branch_id = gen.get_id();
+
+        let mut repeat = Repeat::new(repeat_id);
+        repeat.set_input("TIMES", Box::new(ValueNumber::new(2.0)));
+        repeat.set_substack("SUBSTACK", if_id);
+
+        let mut if_block = If::new(if_id);
+        if_block.set_substack("SUBSTACK", branch_id);
+        if_block.set_input("CONDITION", Box::new(ValueBool::new(true)));
+
+        let blocks = block_map(vec![
+            (repeat_id, Box::new(repeat)),
+            (if_id, Box::new(if_block)),
+            (
+                branch_id,
+                Box::new(BlockStub::new(branch_id, runtime.clone())),
+            ),
+        ]);
+
+        let mut thread = Thread::new(repeat_id, blocks);
+        for _ in 0..2 {
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Continue); // Repeat
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Continue); // If
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Continue); // Branch
+
+            // The thread only yields at the end of the loop iteration
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Yield); // If
+            assert_eq!(
+                receiver.try_recv().unwrap(),
+                BroadcastMsg::BlockStub(branch_id, BlockStubMsg::Executed)
+            );
+        }
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+        assert!(receiver.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn forever() {
         let runtime = Runtime::default();
@@ -815,7 +878,7 @@ mod tests {
         let mut thread = Thread::new(forever_id, blocks);
         for _ in 0..2 {
             assert!(matches!(thread.step().await.unwrap(), StepStatus::Continue));
-            assert!(matches!(thread.step().await.unwrap(), StepStatus::Continue));
+            assert!(matches!(thread.step().await.unwrap(), StepStatus::Yield));
             assert_eq!(
                 receiver.try_recv().unwrap(),
                 BroadcastMsg::BlockStub(substack_id, BlockStubMsg::Executed)
@@ -895,16 +958,73 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn wait() {
+    async fn repeat_count() {
         let runtime = Runtime::default();
         let mut receiver = runtime.global.broadcaster.subscribe();
 
+        let mut gen = BlockIDGenerator::new();
+        let substack_id = gen.get_id();
+        let repeat_id = gen.get_id();
+        let times = Arc::new(RwLock::new(Value::Number(2.5)));
+
+        let mut repeat = Repeat::new(repeat_id);
+        repeat.set_input(
+            "TIMES",
+            Box::new(BlockStub::with_behavior(
+                gen.get_id(),
+                runtime.clone(),
+                Some(times.clone()),
+                Arc::new(RwLock::new(Next::None)),
+            )),
+        );
+        repeat.set_substack("SUBSTACK", substack_id);
+
+        let blocks = block_map(vec![
+            (
+                substack_id,
+                Box::new(BlockStub::new(substack_id, runtime.clone())),
+            ),
+            (repeat_id, Box::new(repeat)),
+        ]);
+
+        let mut thread = Thread::new(repeat_id, blocks);
+        let mut steps = 0;
+        while thread.step().await.unwrap() != StepStatus::Done {
+            // The count is read once when the loop starts
+            *times.write().await = Value::Number(0.0);
+            steps += 1;
+            assert!(steps < 100);
+        }
+
+        // 2.5 is rounded to 3
+        for _ in 0..3 {
+            assert_eq!(
+                receiver.try_recv().unwrap(),
+                BroadcastMsg::BlockStub(substack_id, BlockStubMsg::Executed)
+            );
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn wait() {
+        let clock = Arc::new(VirtualClock::default());
+        let runtime = Runtime::new(
+            Arc::default(),
+            Arc::new(Global {
+                clock: clock.clone(),
+                ..Default::default()
+            }),
+            ThreadID::default(),
+        );
+        let mut receiver = runtime.global.broadcaster.subscribe();
+
         let mut gen = BlockIDGenerator::new();
         let next_id = gen.get_id();
         let wait_id = gen.get_id();
 
-        let mut wait = Wait::new(wait_id);
-        wait.set_input("DURATION", Box::new(ValueNumber::new(0.0)));
+        let mut wait = Wait::new(wait_id, runtime.clone());
+        wait.set_input("DURATION", Box::new(ValueNumber::new(1.0)));
         wait.set_substack("next", next_id);
 
         let blocks = block_map(vec![
@@ -913,7 +1033,13 @@ mod tests {
         ]);
 
         let mut thread = Thread::new(wait_id, blocks);
-        thread.step().await.unwrap();
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        assert!(receiver.try_recv().is_err());
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
         assert!(matches!(thread.step().await.unwrap(), StepStatus::Done));
 
         assert_eq!(
@@ -983,6 +1109,94 @@ mod tests {
         assert!(receiver.try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn empty_forever() {
+        let mut gen = BlockIDGenerator::new();
+        let forever_id = gen.get_id();
+
+        let blocks = block_map(vec![(forever_id, Box::new(Forever::new(forever_id)))]);
+
+        let mut thread = Thread::new(forever_id, blocks);
+        for _ in 0..3 {
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_repeat() {
+        let runtime = Runtime::default();
+        let mut receiver = runtime.global.broadcaster.subscribe();
+
+        let mut gen = BlockIDGenerator::new();
+        let next_id = gen.get_id();
+        let repeat_id = gen.get_id();
+
+        let mut repeat = Repeat::new(repeat_id);
+        repeat.set_input("TIMES", Box::new(ValueNumber::new(2.0)));
+        repeat.set_substack("next", next_id);
+
+        let blocks = block_map(vec![
+            (next_id, Box::new(BlockStub::new(next_id, runtime.clone()))),
+            (repeat_id, Box::new(repeat)),
+        ]);
+
+        let mut thread = Thread::new(repeat_id, blocks);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        assert!(receiver.try_recv().is_err());
+
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::BlockStub(next_id, BlockStubMsg::Executed)
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn empty_repeat_until() {
+        let runtime = Runtime::default();
+        let mut receiver = runtime.global.broadcaster.subscribe();
+
+        let mut gen = BlockIDGenerator::new();
+        let next_id = gen.get_id();
+        let repeat_until_id = gen.get_id();
+
+        let condition = Arc::new(RwLock::new(Value::Bool(false)));
+
+        let mut repeat_until = RepeatUntil::new(repeat_until_id);
+        repeat_until.set_input(
+            "CONDITION",
+            Box::new(BlockStub::with_behavior(
+                gen.get_id(),
+                runtime.clone(),
+                Some(condition.clone()),
+                Arc::new(RwLock::new(Next::None)),
+            )),
+        );
+        repeat_until.set_substack("next", next_id);
+
+        let blocks = block_map(vec![
+            (next_id, Box::new(BlockStub::new(next_id, runtime.clone()))),
+            (repeat_until_id, Box::new(repeat_until)),
+        ]);
+
+        let mut thread = Thread::new(repeat_until_id, blocks);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        assert!(receiver.try_recv().is_err());
+
+        *condition.write().await = Value::Bool(true);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::BlockStub(next_id, BlockStubMsg::Executed)
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn if_else() {
         let runtime = Runtime::default();
@@ -1091,12 +1305,12 @@ mod tests {
         ]);
 
         let mut thread = Thread::new(wait_until_id, blocks);
-        let mut step_future = thread.step().boxed_local();
-        assert!((&mut step_future).now_or_never().is_none());
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
 
         *condition.write().await = Value::Bool(true);
 
-        step_future.await.unwrap();
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
         assert!(matches!(thread.step().await.unwrap(), StepStatus::Done));
 
         assert_eq!(
@@ -1131,7 +1345,9 @@ mod tests {
         }
         {
             let runtime = Runtime::new(
-                Arc::new(RwLock::new(SpriteRuntime::default().clone_sprite_runtime())),
+                Arc::new(RwLock::new(
+                    SpriteRuntime::default().clone_sprite_runtime().await,
+                )),
                 Arc::new(Global::default()),
                 ThreadID::default(),
             );
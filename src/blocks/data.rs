@@ -1,4 +1,7 @@
 use super::*;
+use crate::blocks::value::cast;
+use rand::{thread_rng, Rng};
+use std::cmp::Ordering;
 
 pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
@@ -6,6 +9,17 @@ pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Bl
         "changevariableby" => Box::new(ChangeVariable::new(id, runtime)),
         "hidevariable" => Box::new(HideVariable::new(id, runtime)),
         "showvariable" => Box::new(ShowVariable::new(id, runtime)),
+        "addtolist" => Box::new(AddToList::new(id, runtime)),
+        "deleteoflist" => Box::new(DeleteOfList::new(id, runtime)),
+        "deletealloflist" => Box::new(DeleteAllOfList::new(id, runtime)),
+        "insertatlist" => Box::new(InsertAtList::new(id, runtime)),
+        "replaceitemoflist" => Box::new(ReplaceItemOfList::new(id, runtime)),
+        "itemoflist" => Box::new(ItemOfList::new(id, runtime)),
+        "itemnumoflist" => Box::new(ItemNumOfList::new(id, runtime)),
+        "lengthoflist" => Box::new(LengthOfList::new(id, runtime)),
+        "listcontainsitem" => Box::new(ListContainsItem::new(id, runtime)),
+        "showlist" => Box::new(ShowList::new(id, runtime)),
+        "hidelist" => Box::new(HideList::new(id, runtime)),
         _ => return Err(Error::msg(format!("{} does not exist", name))),
     })
 }
@@ -70,41 +84,640 @@ impl Block for SetVariable {
 
     async fn execute(&mut self) -> Result<Next> {
         let value = self.value.value().await?;
+        self.runtime.set_variable(&self.variable_id, value).await;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct ChangeVariable {
+    id: BlockID,
+    runtime: Runtime,
+    variable_id: String,
+    value: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl ChangeVariable {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            variable_id: String::new(),
+            value: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ChangeVariable {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ChangeVariable",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("VARIABLE", self.variable_id.clone())],
+            vec![("VALUE", self.value.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "VALUE" {
+            self.value = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "VARIABLE" {
+            self.variable_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let value: f64 = self.value.value().await?.try_into()?;
+        self.runtime
+            .set_variable_with(&self.variable_id, |v| {
+                let previous_float: f64 = v.try_into().unwrap_or(0.0);
+                (previous_float + value).into()
+            })
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct HideVariable {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    variable_id: String,
+}
+
+impl HideVariable {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            variable_id: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for HideVariable {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "HideVariable",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("VARIABLE", self.variable_id.clone())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "VARIABLE" {
+            self.variable_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime
+            .set_variable_monitored(&self.variable_id, false)
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct ShowVariable {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    variable_id: String,
+}
+
+impl ShowVariable {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            variable_id: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ShowVariable {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ShowVariable",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("VARIABLE", self.variable_id.clone())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "VARIABLE" {
+            self.variable_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime
+            .set_variable_monitored(&self.variable_id, true)
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct AddToList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    item: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl AddToList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            item: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for AddToList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "AddToList",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![("ITEM", self.item.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "ITEM" => self.item = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let item = self.item.value().await?;
+        self.runtime
+            .list_mut(&self.list_id, |list| list.push(item))
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteOfList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    index: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl DeleteOfList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            index: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for DeleteOfList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "DeleteOfList",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![("INDEX", self.index.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "INDEX" => self.index = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let index = self.index.value().await?;
+        self.runtime
+            .list_mut(&self.list_id, |list| {
+                match list_index(&index, list.len(), true) {
+                    ListIndex::Index(i) => {
+                        list.remove(i);
+                    }
+                    ListIndex::All => list.clear(),
+                    ListIndex::Invalid => {}
+                }
+            })
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteAllOfList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    next: Option<BlockID>,
+}
+
+impl DeleteAllOfList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for DeleteAllOfList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "DeleteAllOfList",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime
+            .list_mut(&self.list_id, |list| list.clear())
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct InsertAtList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    item: Box<dyn Block>,
+    index: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl InsertAtList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            item: Box::new(EmptyInput {}),
+            index: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for InsertAtList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "InsertAtList",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![("ITEM", self.item.as_ref()), ("INDEX", self.index.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "ITEM" => self.item = block,
+            "INDEX" => self.index = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let item = self.item.value().await?;
+        let index = self.index.value().await?;
+        self.runtime
+            .list_mut(&self.list_id, |list| {
+                // An item can be inserted after the last item
+                if let ListIndex::Index(i) = list_index(&index, list.len() + 1, false) {
+                    list.insert(i, item);
+                }
+            })
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplaceItemOfList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    index: Box<dyn Block>,
+    item: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl ReplaceItemOfList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            index: Box::new(EmptyInput {}),
+            item: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ReplaceItemOfList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ReplaceItemOfList",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![("INDEX", self.index.as_ref()), ("ITEM", self.item.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "INDEX" => self.index = block,
+            "ITEM" => self.item = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let index = self.index.value().await?;
+        let item = self.item.value().await?;
+        self.runtime
+            .list_mut(&self.list_id, |list| {
+                if let ListIndex::Index(i) = list_index(&index, list.len(), false) {
+                    list[i] = item;
+                }
+            })
+            .await?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct ItemOfList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    index: Box<dyn Block>,
+}
+
+impl ItemOfList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            index: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ItemOfList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ItemOfList",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![("INDEX", self.index.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "INDEX" => self.index = block,
+            _ => {}
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let index = self.index.value().await?;
         self.runtime
-            .global
-            .variables
-            .set(&self.variable_id, value)
-            .await;
-        Next::continue_(self.next)
+            .list(&self.list_id, |list| {
+                match list_index(&index, list.len(), false) {
+                    ListIndex::Index(i) => list[i].clone(),
+                    _ => Value::String(String::new()),
+                }
+            })
+            .await
     }
 }
 
 #[derive(Debug)]
-pub struct ChangeVariable {
+pub struct ItemNumOfList {
     id: BlockID,
     runtime: Runtime,
-    variable_id: String,
-    value: Box<dyn Block>,
-    next: Option<BlockID>,
+    list_id: String,
+    item: Box<dyn Block>,
 }
 
-impl ChangeVariable {
+impl ItemNumOfList {
     pub fn new(id: BlockID, runtime: Runtime) -> Self {
         Self {
             id,
             runtime,
-            variable_id: String::new(),
-            value: Box::new(EmptyInput {}),
-            next: None,
+            list_id: String::new(),
+            item: Box::new(EmptyInput {}),
         }
     }
 }
 
 #[async_trait]
-impl Block for ChangeVariable {
+impl Block for ItemNumOfList {
     fn block_info(&self) -> BlockInfo {
         BlockInfo {
-            name: "ChangeVariable",
+            name: "ItemNumOfList",
             id: self.id,
         }
     }
@@ -112,69 +725,171 @@ impl Block for ChangeVariable {
     fn block_inputs(&self) -> BlockInputsPartial {
         BlockInputsPartial::new(
             self.block_info(),
-            vec![("VARIABLE", self.variable_id.clone())],
-            vec![("VALUE", self.value.as_ref())],
-            vec![("next", &self.next)],
+            vec![("LIST", self.list_id.clone())],
+            vec![("ITEM", self.item.as_ref())],
+            vec![],
         )
     }
 
     fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
-        if key == "VALUE" {
-            self.value = block;
+        match key {
+            "ITEM" => self.item = block,
+            _ => {}
         }
     }
 
-    fn set_substack(&mut self, key: &str, block: BlockID) {
-        if key == "next" {
-            self.next = Some(block);
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let item = self.item.value().await?;
+        self.runtime
+            .list(&self.list_id, |list| {
+                let position = list.iter().position(|v| item_equals(v, &item));
+                Value::Number(position.map_or(0.0, |i| (i + 1) as f64))
+            })
+            .await
+    }
+}
+
+#[derive(Debug)]
+pub struct LengthOfList {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+}
+
+impl LengthOfList {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for LengthOfList {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "LengthOfList",
+            id: self.id,
         }
     }
 
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![],
+            vec![],
+        )
+    }
+
     fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
-        if key == "VARIABLE" {
-            self.variable_id = get_field_value(field, 1)?.to_string();
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
         }
         Ok(())
     }
 
-    async fn execute(&mut self) -> Result<Next> {
-        let value: f64 = self.value.value().await?.try_into()?;
+    async fn value(&mut self) -> Result<Value> {
         self.runtime
-            .global
-            .variables
-            .set_with(&self.variable_id, |v| {
-                let previous_float: f64 = v.try_into().unwrap_or(0.0);
-                (previous_float + value).into()
+            .list(&self.list_id, |list| Value::Number(list.len() as f64))
+            .await
+    }
+}
+
+#[derive(Debug)]
+pub struct ListContainsItem {
+    id: BlockID,
+    runtime: Runtime,
+    list_id: String,
+    item: Box<dyn Block>,
+}
+
+impl ListContainsItem {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            list_id: String::new(),
+            item: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ListContainsItem {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ListContainsItem",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("LIST", self.list_id.clone())],
+            vec![("ITEM", self.item.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "ITEM" => self.item = block,
+            _ => {}
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let item = self.item.value().await?;
+        self.runtime
+            .list(&self.list_id, |list| {
+                Value::Bool(list.iter().any(|v| item_equals(v, &item)))
             })
-            .await?;
-        Next::continue_(self.next)
+            .await
     }
 }
 
 #[derive(Debug)]
-pub struct HideVariable {
+pub struct ShowList {
     id: BlockID,
     runtime: Runtime,
+    list_id: String,
     next: Option<BlockID>,
-    variable_id: String,
 }
 
-impl HideVariable {
+impl ShowList {
     pub fn new(id: BlockID, runtime: Runtime) -> Self {
         Self {
             id,
             runtime,
+            list_id: String::new(),
             next: None,
-            variable_id: String::new(),
         }
     }
 }
 
 #[async_trait]
-impl Block for HideVariable {
+impl Block for ShowList {
     fn block_info(&self) -> BlockInfo {
         BlockInfo {
-            name: "HideVariable",
+            name: "ShowList",
             id: self.id,
         }
     }
@@ -182,7 +897,7 @@ impl Block for HideVariable {
     fn block_inputs(&self) -> BlockInputsPartial {
         BlockInputsPartial::new(
             self.block_info(),
-            vec![("VARIABLE", self.variable_id.clone())],
+            vec![("LIST", self.list_id.clone())],
             vec![],
             vec![("next", &self.next)],
         )
@@ -195,46 +910,42 @@ impl Block for HideVariable {
     }
 
     fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
-        if key == "VARIABLE" {
-            self.variable_id = get_field_value(field, 1)?.to_string();
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
         }
         Ok(())
     }
 
     async fn execute(&mut self) -> Result<Next> {
-        self.runtime
-            .global
-            .variables
-            .set_monitored(&self.variable_id, false)
-            .await?;
+        self.runtime.set_list_monitored(&self.list_id, true).await?;
         Next::continue_(self.next)
     }
 }
 
 #[derive(Debug)]
-pub struct ShowVariable {
+pub struct HideList {
     id: BlockID,
     runtime: Runtime,
+    list_id: String,
     next: Option<BlockID>,
-    variable_id: String,
 }
 
-impl ShowVariable {
+impl HideList {
     pub fn new(id: BlockID, runtime: Runtime) -> Self {
         Self {
             id,
             runtime,
+            list_id: String::new(),
             next: None,
-            variable_id: String::new(),
         }
     }
 }
 
 #[async_trait]
-impl Block for ShowVariable {
+impl Block for HideList {
     fn block_info(&self) -> BlockInfo {
         BlockInfo {
-            name: "ShowVariable",
+            name: "HideList",
             id: self.id,
         }
     }
@@ -242,7 +953,7 @@ impl Block for ShowVariable {
     fn block_inputs(&self) -> BlockInputsPartial {
         BlockInputsPartial::new(
             self.block_info(),
-            vec![("VARIABLE", self.variable_id.clone())],
+            vec![("LIST", self.list_id.clone())],
             vec![],
             vec![("next", &self.next)],
         )
@@ -255,26 +966,60 @@ impl Block for ShowVariable {
     }
 
     fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
-        if key == "VARIABLE" {
-            self.variable_id = get_field_value(field, 1)?.to_string();
+        if key == "LIST" {
+            self.list_id = get_field_value(field, 1)?.to_string();
         }
         Ok(())
     }
 
     async fn execute(&mut self) -> Result<Next> {
         self.runtime
-            .global
-            .variables
-            .set_monitored(&self.variable_id, true)
+            .set_list_monitored(&self.list_id, false)
             .await?;
         Next::continue_(self.next)
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ListIndex {
+    /// Zero-based index
+    Index(usize),
+    All,
+    Invalid,
+}
+
+/// Converts a one-based index input to a zero-based index. The index can also be "last", "random"
+/// or "any". "all" is only accepted if accept_all is true.
+fn list_index(index: &Value, length: usize, accept_all: bool) -> ListIndex {
+    if let Value::String(s) = index {
+        match s.as_str() {
+            "all" if accept_all => return ListIndex::All,
+            "last" if length > 0 => return ListIndex::Index(length - 1),
+            "random" | "any" if length > 0 => {
+                return ListIndex::Index(thread_rng().gen_range(0..length))
+            }
+            _ => {}
+        }
+    }
+
+    let n = cast::to_number(index).floor();
+    if n < 1.0 || n > length as f64 {
+        ListIndex::Invalid
+    } else {
+        ListIndex::Index(n as usize - 1)
+    }
+}
+
+/// Items are equal if they are equal numbers or case-insensitively equal strings.
+fn item_equals(a: &Value, b: &Value) -> bool {
+    cast::compare(a, b) == Ordering::Equal
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blocks::value::ValueNumber;
+    use crate::blocks::value::{ValueNumber, ValueString};
+    use rstest::rstest;
 
     const KEY: &str = "key";
 
@@ -332,6 +1077,38 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn set_local_variable() {
+        let runtime = Runtime::default();
+        runtime.global.variables.set(KEY, Value::Number(0.0)).await;
+        runtime
+            .sprite
+            .read()
+            .await
+            .variables()
+            .set(KEY, Value::Number(0.0))
+            .await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut set_variable = SetVariable::new(gen.get_id(), runtime.clone());
+        set_variable.set_input("VALUE", Box::new(ValueNumber::new(1.0)));
+        set_variable
+            .set_field("VARIABLE", &[None, Some(KEY.to_string())])
+            .unwrap();
+
+        set_variable.execute().await.unwrap();
+
+        assert_eq!(runtime.variable(KEY).await.unwrap(), Value::Number(1.0));
+        assert_eq!(
+            runtime.global.variables.get(KEY).await.unwrap(),
+            Value::Number(0.0)
+        );
+
+        let cloned = runtime.sprite.read().await.clone_sprite_runtime().await;
+        cloned.variables().set(KEY, Value::Number(2.0)).await;
+        assert_eq!(runtime.variable(KEY).await.unwrap(), Value::Number(1.0));
+    }
+
     #[tokio::test]
     async fn hide_variable_and_show_variable() {
         let runtime = Runtime::default();
@@ -356,4 +1133,185 @@ mod tests {
 
         assert!(runtime.global.variables.monitored(KEY).await);
     }
+
+    #[tokio::test]
+    async fn add_to_list() {
+        let runtime = Runtime::default();
+        runtime.global.lists.set(KEY, vec![]).await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut add_to_list = AddToList::new(gen.get_id(), runtime.clone());
+        add_to_list.set_input("ITEM", Box::new(ValueString::new("a".to_string())));
+        add_to_list
+            .set_field("LIST", &[None, Some(KEY.to_string())])
+            .unwrap();
+
+        add_to_list.execute().await.unwrap();
+        add_to_list.execute().await.unwrap();
+
+        assert_eq!(
+            runtime.list(KEY, |list| list.to_vec()).await.unwrap(),
+            vec![Value::from("a"), Value::from("a")]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_of_list() {
+        let runtime = Runtime::default();
+        runtime
+            .global
+            .lists
+            .set(
+                KEY,
+                vec![Value::from("a"), Value::from("b"), Value::from("c")],
+            )
+            .await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut delete_of_list = DeleteOfList::new(gen.get_id(), runtime.clone());
+        delete_of_list
+            .set_field("LIST", &[None, Some(KEY.to_string())])
+            .unwrap();
+
+        delete_of_list.set_input("INDEX", Box::new(ValueNumber::new(1.0)));
+        delete_of_list.execute().await.unwrap();
+        assert_eq!(
+            runtime.list(KEY, |list| list.to_vec()).await.unwrap(),
+            vec![Value::from("b"), Value::from("c")]
+        );
+
+        delete_of_list.set_input("INDEX", Box::new(ValueString::new("last".to_string())));
+        delete_of_list.execute().await.unwrap();
+        assert_eq!(
+            runtime.list(KEY, |list| list.to_vec()).await.unwrap(),
+            vec![Value::from("b")]
+        );
+
+        delete_of_list.set_input("INDEX", Box::new(ValueNumber::new(5.0)));
+        delete_of_list.execute().await.unwrap();
+        assert_eq!(runtime.list(KEY, |list| list.len()).await.unwrap(), 1);
+
+        delete_of_list.set_input("INDEX", Box::new(ValueString::new("all".to_string())));
+        delete_of_list.execute().await.unwrap();
+        assert_eq!(runtime.list(KEY, |list| list.len()).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn insert_at_list_and_replace_item_of_list() {
+        let runtime = Runtime::default();
+        runtime.global.lists.set(KEY, vec![Value::from("a")]).await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut insert_at_list = InsertAtList::new(gen.get_id(), runtime.clone());
+        insert_at_list
+            .set_field("LIST", &[None, Some(KEY.to_string())])
+            .unwrap();
+        insert_at_list.set_input("ITEM", Box::new(ValueString::new("b".to_string())));
+        insert_at_list.set_input("INDEX", Box::new(ValueNumber::new(2.0)));
+        insert_at_list.execute().await.unwrap();
+        assert_eq!(
+            runtime.list(KEY, |list| list.to_vec()).await.unwrap(),
+            vec![Value::from("a"), Value::from("b")]
+        );
+
+        let mut replace_item_of_list = ReplaceItemOfList::new(gen.get_id(), runtime.clone());
+        replace_item_of_list
+            .set_field("LIST", &[None, Some(KEY.to_string())])
+            .unwrap();
+        replace_item_of_list.set_input("ITEM", Box::new(ValueString::new("c".to_string())));
+        replace_item_of_list.set_input("INDEX", Box::new(ValueNumber::new(1.0)));
+        replace_item_of_list.execute().await.unwrap();
+        assert_eq!(
+            runtime.list(KEY, |list| list.to_vec()).await.unwrap(),
+            vec![Value::from("c"), Value::from("b")]
+        );
+    }
+
+    #[tokio::test]
+    async fn list_reporters() {
+        let runtime = Runtime::default();
+        runtime
+            .global
+            .lists
+            .set(KEY, vec![Value::from("a"), Value::Number(1.0)])
+            .await;
+
+        let mut gen = BlockIDGenerator::new();
+        let field = [None, Some(KEY.to_string())];
+
+        let mut item_of_list = ItemOfList::new(gen.get_id(), runtime.clone());
+        item_of_list.set_field("LIST", &field).unwrap();
+        item_of_list.set_input("INDEX", Box::new(ValueNumber::new(2.0)));
+        assert_eq!(item_of_list.value().await.unwrap(), Value::Number(1.0));
+        item_of_list.set_input("INDEX", Box::new(ValueNumber::new(3.0)));
+        assert_eq!(item_of_list.value().await.unwrap(), Value::from(""));
+
+        let mut item_num_of_list = ItemNumOfList::new(gen.get_id(), runtime.clone());
+        item_num_of_list.set_field("LIST", &field).unwrap();
+        item_num_of_list.set_input("ITEM", Box::new(ValueString::new("A".to_string())));
+        assert_eq!(item_num_of_list.value().await.unwrap(), Value::Number(1.0));
+
+        let mut length_of_list = LengthOfList::new(gen.get_id(), runtime.clone());
+        length_of_list.set_field("LIST", &field).unwrap();
+        assert_eq!(length_of_list.value().await.unwrap(), Value::Number(2.0));
+
+        let mut list_contains_item = ListContainsItem::new(gen.get_id(), runtime.clone());
+        list_contains_item.set_field("LIST", &field).unwrap();
+        list_contains_item.set_input("ITEM", Box::new(ValueString::new("1.0".to_string())));
+        assert_eq!(list_contains_item.value().await.unwrap(), Value::Bool(true));
+        list_contains_item.set_input("ITEM", Box::new(ValueString::new("b".to_string())));
+        assert_eq!(
+            list_contains_item.value().await.unwrap(),
+            Value::Bool(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn hide_list_and_show_list() {
+        let runtime = Runtime::default();
+        runtime.global.lists.set(KEY, vec![]).await;
+
+        let mut gen = BlockIDGenerator::new();
+        let field = [None, Some(KEY.to_string())];
+
+        let mut show_list = ShowList::new(gen.get_id(), runtime.clone());
+        show_list.set_field("LIST", &field).unwrap();
+        show_list.execute().await.unwrap();
+        assert!(runtime.global.lists.monitored(KEY).await);
+
+        let mut hide_list = HideList::new(gen.get_id(), runtime.clone());
+        hide_list.set_field("LIST", &field).unwrap();
+        hide_list.execute().await.unwrap();
+        assert!(!runtime.global.lists.monitored(KEY).await);
+    }
+
+    #[rstest]
+    #[case(Value::Number(1.0), 2, false, ListIndex::Index(0))]
+    #[case(Value::Number(2.9), 2, false, ListIndex::Index(1))]
+    #[case(Value::Number(0.0), 2, false, ListIndex::Invalid)]
+    #[case(Value::Number(3.0), 2, false, ListIndex::Invalid)]
+    #[case(Value::from("2"), 2, false, ListIndex::Index(1))]
+    #[case(Value::from("last"), 2, false, ListIndex::Index(1))]
+    #[case(Value::from("last"), 0, false, ListIndex::Invalid)]
+    #[case(Value::from("all"), 2, true, ListIndex::All)]
+    #[case(Value::from("all"), 2, false, ListIndex::Invalid)]
+    #[case(Value::from("a"), 2, false, ListIndex::Invalid)]
+    fn test_list_index(
+        #[case] index: Value,
+        #[case] length: usize,
+        #[case] accept_all: bool,
+        #[case] expected: ListIndex,
+    ) {
+        assert_eq!(list_index(&index, length, accept_all), expected);
+    }
+
+    #[rstest]
+    #[case(Value::Number(1.0), Value::from("1"), true)]
+    #[case(Value::Number(1.0), Value::from("1.0"), true)]
+    #[case(Value::from("a"), Value::from("A"), true)]
+    #[case(Value::from("a"), Value::from("b"), false)]
+    #[case(Value::Bool(true), Value::from("true"), true)]
+    fn test_item_equals(#[case] a: Value, #[case] b: Value, #[case] expected: bool) {
+        assert_eq!(item_equals(&a, &b), expected);
+    }
 }
@@ -1,14 +1,23 @@
 use crate::broadcaster::BroadcastMsg;
 
+use super::sensing::KeyOption;
 use super::*;
+use std::str::FromStr;
+use strum::EnumString;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::broadcast::Receiver;
 
 pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
         "whenflagclicked" => Box::new(WhenFlagClicked::new(id, runtime)),
-        "whenbroadcastreceived" => Box::new(WhenBroadcastReceived::new(id, runtime)),
+        "whenbroadcastreceived" => Box::new(WhenBroadcastReceived::new(id)),
         "broadcast" => Box::new(Broadcast::new(id, runtime)),
         "broadcastandwait" => Box::new(BroadcastAndWait::new(id, runtime)),
-        "whenthisspriteclicked" => Box::new(WhenThisSpriteClicked::new(id, runtime)),
+        "whenthisspriteclicked" => Box::new(WhenThisSpriteClicked::new(id)),
+        "whenkeypressed" => Box::new(WhenKeyPressed::new(id)),
+        "whenbackdropswitchesto" => Box::new(WhenBackdropSwitchesTo::new(id)),
+        "whengreaterthan" => Box::new(WhenGreaterThan::new(id, runtime)),
+        "whenstageclicked" => Box::new(WhenStageClicked::new(id)),
         _ => return Err(Error::msg(format!("{} does not exist", name))),
     })
 }
@@ -54,6 +63,10 @@ impl Block for WhenFlagClicked {
         }
     }
 
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::GreenFlag)
+    }
+
     async fn execute(&mut self) -> Result<Next> {
         if self.runtime.sprite.read().await.is_a_clone() {
             Ok(Next::None)
@@ -63,23 +76,20 @@ impl Block for WhenFlagClicked {
     }
 }
 
+/// The VM sends BroadcastMsg::Finished when the script ends.
 #[derive(Debug)]
 pub struct WhenBroadcastReceived {
     id: BlockID,
-    runtime: Runtime,
     next: Option<BlockID>,
     broadcast_id: String,
-    started: bool,
 }
 
 impl WhenBroadcastReceived {
-    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+    pub fn new(id: BlockID) -> Self {
         Self {
             id,
-            runtime,
             next: None,
             broadcast_id: String::new(),
-            started: false,
         }
     }
 }
@@ -115,25 +125,12 @@ impl Block for WhenBroadcastReceived {
         Ok(())
     }
 
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::BroadcastReceived(self.broadcast_id.clone()))
+    }
+
     async fn execute(&mut self) -> Result<Next> {
-        if self.started {
-            self.runtime
-                .global
-                .broadcaster
-                .send(BroadcastMsg::Finished(self.broadcast_id.clone()))?;
-            self.started = false;
-            return Ok(Next::None);
-        }
-
-        let mut recv = self.runtime.global.broadcaster.subscribe();
-        loop {
-            if let BroadcastMsg::Start(s) = recv.recv().await? {
-                if s == self.broadcast_id {
-                    self.started = true;
-                    return Next::loop_(self.next);
-                }
-            }
-        }
+        Next::continue_(self.next)
     }
 }
 
@@ -202,6 +199,8 @@ pub struct BroadcastAndWait {
     runtime: Runtime,
     next: Option<BlockID>,
     message: Box<dyn Block>,
+    /// Message that was sent, and the receiver of the end of the scripts that it started
+    waiting_for: Option<(String, Receiver<BroadcastMsg>)>,
 }
 
 impl BroadcastAndWait {
@@ -211,6 +210,7 @@ impl BroadcastAndWait {
             runtime,
             next: None,
             message: Box::new(EmptyInput {}),
+            waiting_for: None,
         }
     }
 }
@@ -247,43 +247,303 @@ impl Block for BroadcastAndWait {
 
     async fn execute(&mut self) -> Result<Next> {
         let msg = self.message.value().await?.to_string();
+        let receiver = self.runtime.global.broadcaster.subscribe();
         self.runtime
             .global
             .broadcaster
             .send(BroadcastMsg::Start(msg.clone()))?;
-        let mut recv = self.runtime.global.broadcaster.subscribe();
-        loop {
-            if let BroadcastMsg::Finished(s) = recv.recv().await? {
-                if s == msg {
-                    return Next::continue_(self.next);
+        self.waiting_for = Some((msg, receiver));
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if let Some((msg, receiver)) = &mut self.waiting_for {
+            loop {
+                match receiver.try_recv() {
+                    Ok(BroadcastMsg::Finished(s)) if s == *msg => break,
+                    Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                    // Messages are checked again in the next frame
+                    Err(TryRecvError::Empty) => return Ok(Next::Loop(self.id)),
+                    Err(e) => return Err(e.into()),
                 }
             }
         }
+        self.waiting_for = None;
+        Next::continue_(self.next)
+    }
+
+    async fn reset(&mut self) {
+        self.waiting_for = None;
     }
 }
 
 #[derive(Debug)]
 pub struct WhenThisSpriteClicked {
     id: BlockID,
-    runtime: Runtime,
     next: Option<BlockID>,
 }
 
 impl WhenThisSpriteClicked {
+    pub fn new(id: BlockID) -> Self {
+        Self { id, next: None }
+    }
+}
+
+#[async_trait]
+impl Block for WhenThisSpriteClicked {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "WhenThisSpriteClicked",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::SpriteClicked)
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        Next::continue_(self.next)
+    }
+}
+
+/// Runs the script each time the key is pressed. Holding the key down repeats the press. Presses
+/// while the script is running are ignored.
+#[derive(Debug)]
+pub struct WhenKeyPressed {
+    id: BlockID,
+    next: Option<BlockID>,
+    key: KeyOption,
+}
+
+impl WhenKeyPressed {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            next: None,
+            key: KeyOption::Any,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for WhenKeyPressed {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "WhenKeyPressed",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("KEY_OPTION", self.key.to_string())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "KEY_OPTION" {
+            self.key = KeyOption::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::KeyPressed(self.key))
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        Next::continue_(self.next)
+    }
+}
+
+/// Runs the script each time the stage switches to the backdrop.
+#[derive(Debug)]
+pub struct WhenBackdropSwitchesTo {
+    id: BlockID,
+    next: Option<BlockID>,
+    backdrop: String,
+}
+
+impl WhenBackdropSwitchesTo {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            next: None,
+            backdrop: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for WhenBackdropSwitchesTo {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "WhenBackdropSwitchesTo",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("BACKDROP", self.backdrop.clone())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "BACKDROP" {
+            self.backdrop = get_field_value(field, 0)?.to_string();
+        }
+        Ok(())
+    }
+
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::BackdropSwitchesTo(self.backdrop.clone()))
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, EnumString, strum::Display)]
+pub enum GreaterThanOption {
+    #[strum(serialize = "LOUDNESS")]
+    Loudness,
+    #[strum(serialize = "TIMER")]
+    Timer,
+}
+
+/// Runs the script when the loudness or the timer becomes greater than the value. The condition
+/// is evaluated by the VM.
+#[derive(Debug)]
+pub struct WhenGreaterThan {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    option: GreaterThanOption,
+    value: Box<dyn Block>,
+}
+
+impl WhenGreaterThan {
     pub fn new(id: BlockID, runtime: Runtime) -> Self {
         Self {
             id,
             runtime,
             next: None,
+            option: GreaterThanOption::Timer,
+            value: Box::new(EmptyInput {}),
         }
     }
 }
 
 #[async_trait]
-impl Block for WhenThisSpriteClicked {
+impl Block for WhenGreaterThan {
     fn block_info(&self) -> BlockInfo {
         BlockInfo {
-            name: "WhenThisSpriteClicked",
+            name: "WhenGreaterThan",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("WHENGREATERTHANMENU", self.option.to_string())],
+            vec![("VALUE", self.value.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "VALUE" {
+            self.value = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "WHENGREATERTHANMENU" {
+            self.option = GreaterThanOption::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::EdgeTriggered)
+    }
+
+    async fn hat_condition(&mut self) -> Result<bool> {
+        let value: f64 = self.value.value().await?.try_into()?;
+        let measured = match self.option {
+            // Scratch reports -1 without a microphone
+            GreaterThanOption::Loudness => -1.0,
+            GreaterThanOption::Timer => self.runtime.global.timer.seconds(),
+        };
+        Ok(measured > value)
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        Next::continue_(self.next)
+    }
+}
+
+/// Runs the script each time the stage is clicked where there is no sprite.
+#[derive(Debug)]
+pub struct WhenStageClicked {
+    id: BlockID,
+    next: Option<BlockID>,
+}
+
+impl WhenStageClicked {
+    pub fn new(id: BlockID) -> Self {
+        Self { id, next: None }
+    }
+}
+
+#[async_trait]
+impl Block for WhenStageClicked {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "WhenStageClicked",
             id: self.id,
         }
     }
@@ -303,33 +563,26 @@ impl Block for WhenThisSpriteClicked {
         }
     }
 
+    fn hat(&self) -> Option<Hat> {
+        Some(Hat::StageClicked)
+    }
+
     async fn execute(&mut self) -> Result<Next> {
-        let mut channel = self.runtime.global.broadcaster.subscribe();
-        loop {
-            if let BroadcastMsg::MouseClick(c) = channel.recv().await? {
-                if self
-                    .runtime
-                    .sprite
-                    .read()
-                    .await
-                    .rectangle()
-                    .contains(&c.into())
-                {
-                    return Next::continue_(self.next);
-                }
-            }
-        }
+        Next::continue_(self.next)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::blocks::test::{BlockStub, BlockStubMsg};
-    use crate::blocks::value::ValueString;
-    use crate::coordinate::SpriteCoordinate;
+    use crate::blocks::test::BlockStub;
+    use crate::blocks::value::{ValueNumber, ValueString};
+    use crate::clock::{Timer, VirtualClock};
+    use crate::runtime::Global;
+    use crate::sprite_runtime::SpriteRuntime;
     use crate::thread::{StepStatus, Thread};
-    use futures::future::FutureExt;
+    use crate::vm::ThreadID;
+    use input::Key;
 
     #[tokio::test]
     async fn when_flag_clicked() {
@@ -346,71 +599,13 @@ mod test {
 
         {
             let mut runtime = Runtime::default();
-            let new_runtime = runtime.sprite.read().await.clone_sprite_runtime();
+            let new_runtime = runtime.sprite.read().await.clone_sprite_runtime().await;
             runtime.sprite = Arc::new(RwLock::new(new_runtime));
             let mut when_flag_clicked = WhenFlagClicked::new(gen.get_id(), runtime.clone());
             assert_eq!(when_flag_clicked.execute().await.unwrap(), Next::None);
         }
     }
 
-    #[tokio::test]
-    async fn when_broadcast_received() {
-        let runtime = Runtime::default();
-        let mut receiver = runtime.global.broadcaster.subscribe();
-
-        let mut gen = BlockIDGenerator::new();
-        let when_broadcast_received_id = gen.get_id();
-        let next_id = gen.get_id();
-
-        let mut when_broadcast_received =
-            WhenBroadcastReceived::new(when_broadcast_received_id, runtime.clone());
-        const BROADCAST_ID: &str = "broadcast_id";
-        when_broadcast_received
-            .set_field("BROADCAST_OPTION", &[Some(BROADCAST_ID.to_string())])
-            .unwrap();
-        when_broadcast_received.set_substack("next", next_id);
-
-        let blocks = block_map(vec![
-            (
-                when_broadcast_received_id,
-                Box::new(when_broadcast_received),
-            ),
-            (next_id, Box::new(BlockStub::new(next_id, runtime.clone()))),
-        ]);
-
-        let mut thread = Thread::new(when_broadcast_received_id, blocks);
-
-        // WhenBroadcastReceived
-        let mut step_future = thread.step().boxed_local();
-        assert!((&mut step_future).now_or_never().is_none());
-
-        runtime
-            .global
-            .broadcaster
-            .send(BroadcastMsg::Start(BROADCAST_ID.to_string()))
-            .unwrap();
-        step_future.await.unwrap();
-
-        // BlockStub
-        thread.step().await.unwrap();
-
-        assert_eq!(
-            receiver.try_recv().unwrap(),
-            BroadcastMsg::Start(BROADCAST_ID.to_string())
-        );
-        assert_eq!(
-            receiver.try_recv().unwrap(),
-            BroadcastMsg::BlockStub(next_id, BlockStubMsg::Executed)
-        );
-
-        // WhenBroadcastReceived
-        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
-        assert_eq!(
-            receiver.try_recv().unwrap(),
-            BroadcastMsg::Finished(BROADCAST_ID.to_string())
-        );
-    }
-
     #[tokio::test]
     async fn broadcast() {
         let runtime = Runtime::default();
@@ -439,47 +634,163 @@ mod test {
 
         let mut gen = BlockIDGenerator::new();
 
-        let mut broadcast_and_wait = BroadcastAndWait::new(gen.get_id(), runtime.clone());
+        let id = gen.get_id();
+        let next_id = gen.get_id();
+        let mut broadcast_and_wait = BroadcastAndWait::new(id, runtime.clone());
         const MESSAGE: &str = "message";
         broadcast_and_wait.set_input(
             "BROADCAST_INPUT",
             Box::new(ValueString::new(MESSAGE.to_string())),
         );
-        let mut execute_future = broadcast_and_wait.execute().boxed_local();
-        assert!((&mut execute_future).now_or_never().is_none());
+        broadcast_and_wait.set_substack("next", next_id);
+        assert_eq!(broadcast_and_wait.execute().await.unwrap(), Next::Loop(id));
 
         assert_eq!(
             receiver.try_recv().unwrap(),
             BroadcastMsg::Start(MESSAGE.to_string())
         );
 
-        runtime
-            .global
-            .broadcaster
-            .send(BroadcastMsg::Finished(MESSAGE.to_string()))
+        let broadcaster = &runtime.global.broadcaster;
+        broadcaster
+            .send(BroadcastMsg::Finished("other".to_string()))
             .unwrap();
+        assert_eq!(
+            broadcast_and_wait.loop_return(1).await.unwrap(),
+            Next::Loop(id)
+        );
 
-        execute_future.await.unwrap();
+        broadcaster
+            .send(BroadcastMsg::Finished(MESSAGE.to_string()))
+            .unwrap();
+        assert_eq!(
+            broadcast_and_wait.loop_return(2).await.unwrap(),
+            Next::Continue(next_id)
+        );
     }
 
     #[tokio::test]
-    async fn when_this_sprite_clicked() {
-        let runtime = Runtime::default();
+    async fn when_greater_than() {
+        let clock = Arc::new(VirtualClock::default());
+        let runtime = Runtime::new(
+            Arc::new(RwLock::new(SpriteRuntime::default())),
+            Arc::new(Global {
+                timer: Timer::new(clock.clone()),
+                clock: clock.clone(),
+                ..Default::default()
+            }),
+            ThreadID::default(),
+        );
+        runtime.global.timer.resume();
 
         let mut gen = BlockIDGenerator::new();
-        let mut when_this_sprite_clicked =
-            WhenThisSpriteClicked::new(gen.get_id(), runtime.clone());
-        let mut execute_future = when_this_sprite_clicked.execute().boxed_local();
-        assert!((&mut execute_future).now_or_never().is_none());
+        let hat_id = gen.get_id();
+        let next_id = gen.get_id();
+        let mut when_greater_than = WhenGreaterThan::new(hat_id, runtime.clone());
+        when_greater_than
+            .set_field("WHENGREATERTHANMENU", &[Some("TIMER".to_string())])
+            .unwrap();
+        when_greater_than.set_input("VALUE", Box::new(ValueNumber::new(1.0)));
+        when_greater_than.set_substack("next", next_id);
 
-        runtime
-            .global
-            .broadcaster
-            .send(BroadcastMsg::MouseClick(
-                SpriteCoordinate { x: 0.0, y: 0.0 }.into(),
-            ))
+        let blocks = block_map(vec![
+            (hat_id, Box::new(when_greater_than)),
+            (next_id, Box::new(BlockStub::new(next_id, runtime.clone()))),
+        ]);
+        let mut thread = Thread::new(hat_id, blocks);
+        assert_eq!(thread.hat(), Some(Hat::EdgeTriggered));
+        assert!(!thread.hat_triggered().await.unwrap());
+
+        clock.advance(Duration::from_secs(2));
+        assert!(thread.hat_triggered().await.unwrap());
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+
+        // The condition has to become false before the script runs again
+        assert!(!thread.hat_triggered().await.unwrap());
+        runtime.global.timer.reset();
+        assert!(!thread.hat_triggered().await.unwrap());
+        clock.advance(Duration::from_secs(2));
+        assert!(thread.hat_triggered().await.unwrap());
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+    }
+
+    #[rstest]
+    #[case(Hat::GreenFlag, HatEvent::GreenFlag, true)]
+    #[case(Hat::GreenFlag, HatEvent::StageClicked, false)]
+    #[case(Hat::BroadcastReceived("a".to_string()), HatEvent::Broadcast("a".to_string()), true)]
+    #[case(Hat::BroadcastReceived("a".to_string()), HatEvent::Broadcast("b".to_string()), false)]
+    #[case(Hat::KeyPressed(KeyOption::Any), HatEvent::KeyPressed(Key::A), true)]
+    #[case(
+        Hat::KeyPressed(KeyOption::Key(Key::A)),
+        HatEvent::KeyPressed(Key::A),
+        true
+    )]
+    #[case(
+        Hat::KeyPressed(KeyOption::Key(Key::A)),
+        HatEvent::KeyPressed(Key::B),
+        false
+    )]
+    #[case(Hat::SpriteClicked, HatEvent::SpriteClicked, true)]
+    #[case(Hat::SpriteClicked, HatEvent::StageClicked, false)]
+    #[case(Hat::BackdropSwitchesTo("a".to_string()), HatEvent::BackdropSwitched("a".to_string()), true)]
+    #[case(Hat::StartAsClone, HatEvent::StartAsClone, true)]
+    #[case(Hat::EdgeTriggered, HatEvent::GreenFlag, false)]
+    fn hat_matches(#[case] hat: Hat, #[case] event: HatEvent, #[case] expected: bool) {
+        assert_eq!(hat.matches(&event), expected);
+    }
+
+    #[test]
+    fn hats() {
+        let mut gen = BlockIDGenerator::new();
+
+        let mut when_broadcast_received = WhenBroadcastReceived::new(gen.get_id());
+        when_broadcast_received
+            .set_field("BROADCAST_OPTION", &[Some("message".to_string())])
             .unwrap();
+        assert_eq!(
+            when_broadcast_received.hat(),
+            Some(Hat::BroadcastReceived("message".to_string()))
+        );
+
+        let mut when_key_pressed = WhenKeyPressed::new(gen.get_id());
+        when_key_pressed
+            .set_field("KEY_OPTION", &[Some("a".to_string())])
+            .unwrap();
+        assert_eq!(
+            when_key_pressed.hat(),
+            Some(Hat::KeyPressed(KeyOption::Key(Key::A)))
+        );
+        assert!(!when_key_pressed.hat().unwrap().restarts_running_thread());
 
-        execute_future.await.unwrap();
+        let mut when_backdrop_switches_to = WhenBackdropSwitchesTo::new(gen.get_id());
+        when_backdrop_switches_to
+            .set_field("BACKDROP", &[Some("night".to_string())])
+            .unwrap();
+        assert_eq!(
+            when_backdrop_switches_to.hat(),
+            Some(Hat::BackdropSwitchesTo("night".to_string()))
+        );
+
+        assert_eq!(
+            WhenThisSpriteClicked::new(gen.get_id()).hat(),
+            Some(Hat::SpriteClicked)
+        );
+        assert_eq!(
+            WhenStageClicked::new(gen.get_id()).hat(),
+            Some(Hat::StageClicked)
+        );
+        assert!(Hat::StageClicked.restarts_running_thread());
+    }
+
+    #[tokio::test]
+    async fn when_broadcast_received() {
+        let mut gen = BlockIDGenerator::new();
+        let next_id = gen.get_id();
+        let mut when_broadcast_received = WhenBroadcastReceived::new(gen.get_id());
+        when_broadcast_received.set_substack("next", next_id);
+        assert_eq!(
+            when_broadcast_received.execute().await.unwrap(),
+            Next::Continue(next_id)
+        );
     }
 }
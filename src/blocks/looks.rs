@@ -1,25 +1,33 @@
 use super::*;
 use crate::broadcaster::{BroadcastMsg, LayerChange};
-use crate::coordinate::Scale;
-use crate::sprite_runtime::{HideStatus, Text};
-use std::fmt::{Display, Formatter};
+use crate::graphic_effects::GraphicEffect;
+use crate::sprite_runtime::{Bubble, HideStatus, SpriteRuntime, Text};
 use std::str::FromStr;
 
 pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
         "say" => Box::new(Say::new(id, runtime)),
         "sayforsecs" => Box::new(SayForSecs::new(id, runtime)),
+        "think" => Box::new(Think::new(id, runtime)),
+        "thinkforsecs" => Box::new(ThinkForSecs::new(id, runtime)),
         "gotofrontback" => Box::new(GoToFrontBack::new(id, runtime)),
+        "goforwardbackwardlayers" => Box::new(GoForwardBackwardLayers::new(id, runtime)),
         "hide" => Box::new(Hide::new(id, runtime)),
         "show" => Box::new(Show::new(id, runtime)),
         "seteffectto" => Box::new(SetEffectTo::new(id, runtime)),
         "nextcostume" => Box::new(NextCostume::new(id, runtime)),
         "changeeffectby" => Box::new(ChangeEffectBy::new(id, runtime)),
+        "cleargraphiceffects" => Box::new(ClearGraphicEffects::new(id, runtime)),
         "setsizeto" => Box::new(SetSizeTo::new(id, runtime)),
+        "changesizeby" => Box::new(ChangeSizeBy::new(id, runtime)),
+        "size" => Box::new(Size::new(id, runtime)),
         "switchcostumeto" => Box::new(SwitchCostumeTo::new(id, runtime)),
         "costume" => Box::new(Costume::new(id, runtime)),
+        "costumenumbername" => Box::new(CostumeNumberName::new(id, runtime)),
         "switchbackdropto" => Box::new(SwitchBackdropTo::new(id, runtime)),
+        "nextbackdrop" => Box::new(NextBackdrop::new(id, runtime)),
         "backdrops" => Box::new(Backdrops::new(id, runtime)),
+        "backdropnumbername" => Box::new(BackdropNumberName::new(id, runtime)),
         _ => return Err(Error::msg(format!("{} does not exist", name))),
     })
 }
@@ -78,6 +86,7 @@ impl Block for Say {
         self.runtime.sprite.write().await.say(Text {
             id: self.id,
             text: Some(message),
+            bubble: Bubble::Say,
         });
         Next::continue_(self.next)
     }
@@ -90,6 +99,7 @@ pub struct SayForSecs {
     message: Box<dyn Block>,
     secs: Box<dyn Block>,
     next: Option<BlockID>,
+    end: Option<Duration>,
 }
 
 impl SayForSecs {
@@ -100,6 +110,7 @@ impl SayForSecs {
             message: Box::new(EmptyInput {}),
             secs: Box::new(EmptyInput {}),
             next: None,
+            end: None,
         }
     }
 }
@@ -146,14 +157,177 @@ impl Block for SayForSecs {
         self.runtime.sprite.write().await.say(Text {
             id: self.id,
             text: Some(message),
+            bubble: Bubble::Say,
         });
-        sleep(Duration::from_secs_f64(seconds)).await;
+        self.end = Some(wait_end(&self.runtime, seconds));
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if waiting(&self.runtime, self.end) {
+            return Ok(Next::Loop(self.id));
+        }
+        self.end = None;
         self.runtime.sprite.write().await.say(Text {
             id: self.id,
             text: None,
+            bubble: Bubble::Say,
         });
         Next::continue_(self.next)
     }
+
+    async fn reset(&mut self) {
+        self.end = None;
+    }
+}
+
+#[derive(Debug)]
+pub struct Think {
+    id: BlockID,
+    runtime: Runtime,
+    message: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl Think {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            message: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Think {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Think",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("MESSAGE", self.message.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "MESSAGE" {
+            self.message = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let message = self.message.value().await?.to_string();
+        self.runtime.sprite.write().await.say(Text {
+            id: self.id,
+            text: Some(message),
+            bubble: Bubble::Think,
+        });
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct ThinkForSecs {
+    id: BlockID,
+    runtime: Runtime,
+    message: Box<dyn Block>,
+    secs: Box<dyn Block>,
+    next: Option<BlockID>,
+    end: Option<Duration>,
+}
+
+impl ThinkForSecs {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            message: Box::new(EmptyInput {}),
+            secs: Box::new(EmptyInput {}),
+            next: None,
+            end: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ThinkForSecs {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ThinkForSecs",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![
+                ("MESSAGE", self.message.as_ref()),
+                ("SECS", self.secs.as_ref()),
+            ],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "MESSAGE" => self.message = block,
+            "SECS" => self.secs = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let message = self.message.value().await?.to_string();
+        let seconds: f64 = self.secs.value().await?.try_into()?;
+
+        self.runtime.sprite.write().await.say(Text {
+            id: self.id,
+            text: Some(message),
+            bubble: Bubble::Think,
+        });
+        self.end = Some(wait_end(&self.runtime, seconds));
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if waiting(&self.runtime, self.end) {
+            return Ok(Next::Loop(self.id));
+        }
+        self.end = None;
+        self.runtime.sprite.write().await.say(Text {
+            id: self.id,
+            text: None,
+            bubble: Bubble::Think,
+        });
+        Next::continue_(self.next)
+    }
+
+    async fn reset(&mut self) {
+        self.end = None;
+    }
 }
 
 #[derive(Debug)]
@@ -240,6 +414,99 @@ impl FromStr for FrontBack {
     }
 }
 
+#[derive(Debug)]
+pub struct GoForwardBackwardLayers {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    forward_or_backward: ForwardBackward,
+    num: Box<dyn Block>,
+}
+
+impl GoForwardBackwardLayers {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            forward_or_backward: ForwardBackward::Forward,
+            num: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for GoForwardBackwardLayers {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "GoForwardBackwardLayers",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("NUM", self.num.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "NUM" {
+            self.num = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "FORWARD_BACKWARD" {
+            self.forward_or_backward = ForwardBackward::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let num: f64 = self.num.value().await?.try_into()?;
+        let layers = match self.forward_or_backward {
+            ForwardBackward::Forward => num as i64,
+            ForwardBackward::Backward => -(num as i64),
+        };
+        self.runtime
+            .global
+            .broadcaster
+            .send(BroadcastMsg::ChangeLayer {
+                sprite: self.runtime.thread_id().sprite_id,
+                action: LayerChange::Forward(layers),
+            })?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+enum ForwardBackward {
+    Forward,
+    Backward,
+}
+
+impl FromStr for ForwardBackward {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "forward" => Self::Forward,
+            "backward" => Self::Backward,
+            _ => return Err(Error::msg(format!("s is invalid: {}", s))),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Hide {
     id: BlockID,
@@ -339,7 +606,7 @@ pub struct SetEffectTo {
     id: BlockID,
     runtime: Runtime,
     next: Option<BlockID>,
-    effect: Effect,
+    effect: GraphicEffect,
     value: Box<dyn Block>,
 }
 
@@ -349,7 +616,7 @@ impl SetEffectTo {
             id,
             runtime,
             next: None,
-            effect: Effect::Color,
+            effect: GraphicEffect::Color,
             value: Box::new(EmptyInput {}),
         }
     }
@@ -387,66 +654,22 @@ impl Block for SetEffectTo {
 
     fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
         if key == "EFFECT" {
-            self.effect = Effect::from_str(get_field_value(field, 0)?)?;
+            self.effect = GraphicEffect::from_str(get_field_value(field, 0)?)?;
         }
         Ok(())
     }
 
     async fn execute(&mut self) -> Result<Next> {
         let value: f64 = self.value.value().await?.try_into()?;
-        let mut runtime = self.runtime.sprite.write().await;
-        match self.effect {
-            Effect::Ghost => runtime.set_transparency((100.0 - value) / 100.0),
-            _ => unimplemented!(),
-        }
-
+        self.runtime
+            .sprite
+            .write()
+            .await
+            .set_graphic_effect(self.effect, value);
         Next::continue_(self.next)
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Effect {
-    Color,
-    Fisheye,
-    Whirl,
-    Pixelate,
-    Mosaic,
-    Brightness,
-    Ghost,
-}
-
-impl FromStr for Effect {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        Ok(match s.to_ascii_lowercase().as_str() {
-            "color" => Self::Color,
-            "fisheye" => Self::Fisheye,
-            "whirl" => Self::Whirl,
-            "pixelate" => Self::Pixelate,
-            "mosaic" => Self::Mosaic,
-            "brightness" => Self::Brightness,
-            "ghost" => Self::Ghost,
-            _ => return Err(Error::msg(format!("s is invalid: {}", s))),
-        })
-    }
-}
-
-impl Display for Effect {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            Effect::Color => "color",
-            Effect::Fisheye => "fisheye",
-            Effect::Whirl => "whirl",
-            Effect::Pixelate => "pixelate",
-            Effect::Mosaic => "mosaic",
-            Effect::Brightness => "brightness",
-            Effect::Ghost => "ghost",
-        };
-        f.write_str(s)
-    }
-}
-
 #[derive(Debug)]
 pub struct NextCostume {
     id: BlockID,
@@ -499,7 +722,7 @@ pub struct ChangeEffectBy {
     id: BlockID,
     runtime: Runtime,
     next: Option<BlockID>,
-    effect: Effect,
+    effect: GraphicEffect,
     change: Box<dyn Block>,
 }
 
@@ -509,7 +732,7 @@ impl ChangeEffectBy {
             id,
             runtime,
             next: None,
-            effect: Effect::Color,
+            effect: GraphicEffect::Color,
             change: Box::new(EmptyInput {}),
         }
     }
@@ -547,7 +770,7 @@ impl Block for ChangeEffectBy {
 
     fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
         if key == "EFFECT" {
-            self.effect = Effect::from_str(get_field_value(field, 0)?)?;
+            self.effect = GraphicEffect::from_str(get_field_value(field, 0)?)?;
         }
         Ok(())
     }
@@ -555,14 +778,55 @@ impl Block for ChangeEffectBy {
     async fn execute(&mut self) -> Result<Next> {
         let value: f64 = self.change.value().await?.try_into()?;
         let mut runtime = self.runtime.sprite.write().await;
-        match self.effect {
-            Effect::Ghost => {
-                let current_transparency = runtime.transparency();
-                runtime.set_transparency(current_transparency - value / 100.0);
-            }
-            _ => unimplemented!(),
+        let current = runtime.graphic_effects().get(self.effect);
+        runtime.set_graphic_effect(self.effect, current + value);
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct ClearGraphicEffects {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+}
+
+impl ClearGraphicEffects {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
         }
+    }
+}
+
+#[async_trait]
+impl Block for ClearGraphicEffects {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ClearGraphicEffects",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
 
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime.sprite.write().await.clear_graphic_effects();
         Next::continue_(self.next)
     }
 }
@@ -618,18 +882,99 @@ impl Block for SetSizeTo {
 
     async fn execute(&mut self) -> Result<Next> {
         let size: f64 = self.size.value().await?.try_into()?;
-        let scale = size / 100.0;
+        self.runtime.sprite.write().await.set_size(size);
+        Next::continue_(self.next)
+    }
+}
 
-        self.runtime
-            .sprite
-            .write()
-            .await
-            .set_scale(Scale { x: scale, y: scale });
+#[derive(Debug)]
+pub struct ChangeSizeBy {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    change: Box<dyn Block>,
+}
+
+impl ChangeSizeBy {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            change: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ChangeSizeBy {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ChangeSizeBy",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("CHANGE", self.change.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "CHANGE" {
+            self.change = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
 
+    async fn execute(&mut self) -> Result<Next> {
+        let change: f64 = self.change.value().await?.try_into()?;
+        let mut runtime = self.runtime.sprite.write().await;
+        let size = runtime.size();
+        runtime.set_size(size + change);
         Next::continue_(self.next)
     }
 }
 
+#[derive(Debug)]
+pub struct Size {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl Size {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for Size {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Size",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self.runtime.sprite.read().await.size().round().into())
+    }
+}
+
 #[derive(Debug)]
 pub struct SwitchCostumeTo {
     id: BlockID,
@@ -749,6 +1094,50 @@ impl Block for Costume {
     }
 }
 
+#[derive(Debug)]
+pub struct CostumeNumberName {
+    id: BlockID,
+    runtime: Runtime,
+    number_or_name: NumberName,
+}
+
+impl CostumeNumberName {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            number_or_name: NumberName::Number,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for CostumeNumberName {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "CostumeNumberName",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "NUMBER_NAME" {
+            self.number_or_name = NumberName::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self
+            .number_or_name
+            .value(&*self.runtime.sprite.read().await))
+    }
+}
+
 #[derive(Debug)]
 pub struct SwitchBackdropTo {
     id: BlockID,
@@ -805,7 +1194,66 @@ impl Block for SwitchBackdropTo {
             .write()
             .await
             .costumes()
-            .set_current_costume_by_name(backdrop)?;
+            .set_current_costume_by_name(backdrop.clone())?;
+        self.runtime
+            .global
+            .broadcaster
+            .send(BroadcastMsg::BackdropSwitched(backdrop))?;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct NextBackdrop {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+}
+
+impl NextBackdrop {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for NextBackdrop {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "NextBackdrop",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let backdrop = {
+            let mut runtime = self.runtime.sprite.write().await;
+            runtime.costumes().next_costume();
+            runtime.costume_name().unwrap_or_default().to_string()
+        };
+        self.runtime
+            .global
+            .broadcaster
+            .send(BroadcastMsg::BackdropSwitched(backdrop))?;
         Next::continue_(self.next)
     }
 }
@@ -854,3 +1302,201 @@ impl Block for Backdrops {
         Ok(self.backdrop.clone().into())
     }
 }
+
+#[derive(Debug)]
+pub struct BackdropNumberName {
+    id: BlockID,
+    runtime: Runtime,
+    number_or_name: NumberName,
+}
+
+impl BackdropNumberName {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            number_or_name: NumberName::Number,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for BackdropNumberName {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "BackdropNumberName",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "NUMBER_NAME" {
+            self.number_or_name = NumberName::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self
+            .number_or_name
+            .value(&*self.runtime.sprite.read().await))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum NumberName {
+    Number,
+    Name,
+}
+
+impl NumberName {
+    fn value(self, sprite: &SpriteRuntime) -> Value {
+        match self {
+            Self::Number => (sprite.costume_number() as f64).into(),
+            Self::Name => sprite.costume_name().unwrap_or_default().into(),
+        }
+    }
+}
+
+impl FromStr for NumberName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "number" => Self::Number,
+            "name" => Self::Name,
+            _ => return Err(Error::msg(format!("s is invalid: {}", s))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::value::ValueNumber;
+    use crate::sprite_runtime::Costumes;
+
+    /// Costumes with blank 2x2 images
+    async fn runtime(costume_names: &[&str]) -> Runtime {
+        let costumes: Vec<scratch_file::Costume> = costume_names
+            .iter()
+            .map(|name| scratch_file::Costume {
+                name: name.to_string(),
+                md5ext: None,
+                asset_id: String::new(),
+                data_format: String::new(),
+                rotation_center_x: 0.0,
+                rotation_center_y: 0.0,
+                bitmap_resolution: 1.0,
+                extra: Default::default(),
+            })
+            .collect();
+        let runtime = Runtime::default();
+        runtime.sprite.write().await.set_costumes(
+            Costumes::new(None, &costumes, &HashMap::default())
+                .await
+                .unwrap(),
+        );
+        runtime
+    }
+
+    #[tokio::test]
+    async fn size() {
+        let runtime = runtime(&["a"]).await;
+        let mut gen = BlockIDGenerator::new();
+        let mut size = Size::new(gen.get_id(), runtime.clone());
+        assert_eq!(size.value().await.unwrap(), Value::Number(100.0));
+
+        let mut change_size_by = ChangeSizeBy::new(gen.get_id(), runtime.clone());
+        change_size_by.set_input("CHANGE", Box::new(ValueNumber::new(50.5)));
+        change_size_by.execute().await.unwrap();
+        assert_eq!(size.value().await.unwrap(), Value::Number(151.0));
+
+        // A 2x2 costume can't be smaller than its original size or larger than 1.5 * 360 / 2
+        let mut set_size_to = SetSizeTo::new(gen.get_id(), runtime.clone());
+        set_size_to.set_input("SIZE", Box::new(ValueNumber::new(10.0)));
+        set_size_to.execute().await.unwrap();
+        assert_eq!(size.value().await.unwrap(), Value::Number(100.0));
+
+        set_size_to.set_input("SIZE", Box::new(ValueNumber::new(100_000.0)));
+        set_size_to.execute().await.unwrap();
+        assert_eq!(size.value().await.unwrap(), Value::Number(27_000.0));
+    }
+
+    #[tokio::test]
+    async fn next_backdrop() {
+        let runtime = runtime(&["a", "b"]).await;
+        let mut receiver = runtime.global.broadcaster.subscribe();
+        let mut gen = BlockIDGenerator::new();
+
+        let mut number = BackdropNumberName::new(gen.get_id(), runtime.clone());
+        number
+            .set_field("NUMBER_NAME", &[Some("number".to_string())])
+            .unwrap();
+        let mut name = BackdropNumberName::new(gen.get_id(), runtime.clone());
+        name.set_field("NUMBER_NAME", &[Some("name".to_string())])
+            .unwrap();
+        assert_eq!(number.value().await.unwrap(), Value::Number(1.0));
+        assert_eq!(name.value().await.unwrap(), Value::from("a"));
+
+        let mut next_backdrop = NextBackdrop::new(gen.get_id(), runtime.clone());
+        next_backdrop.execute().await.unwrap();
+        assert_eq!(number.value().await.unwrap(), Value::Number(2.0));
+        assert_eq!(name.value().await.unwrap(), Value::from("b"));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::BackdropSwitched("b".to_string())
+        );
+
+        next_backdrop.execute().await.unwrap();
+        assert_eq!(number.value().await.unwrap(), Value::Number(1.0));
+    }
+
+    #[tokio::test]
+    async fn go_forward_backward_layers() {
+        let runtime = Runtime::default();
+        let mut receiver = runtime.global.broadcaster.subscribe();
+        let mut gen = BlockIDGenerator::new();
+
+        let mut block = GoForwardBackwardLayers::new(gen.get_id(), runtime.clone());
+        block
+            .set_field("FORWARD_BACKWARD", &[Some("backward".to_string())])
+            .unwrap();
+        block.set_input("NUM", Box::new(ValueNumber::new(2.0)));
+        block.execute().await.unwrap();
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::ChangeLayer {
+                sprite: runtime.thread_id().sprite_id,
+                action: LayerChange::Forward(-2),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn clear_graphic_effects() {
+        let runtime = Runtime::default();
+        runtime
+            .sprite
+            .write()
+            .await
+            .set_graphic_effect(GraphicEffect::Ghost, 50.0);
+
+        let mut gen = BlockIDGenerator::new();
+        let mut block = ClearGraphicEffects::new(gen.get_id(), runtime.clone());
+        block.execute().await.unwrap();
+        assert_eq!(
+            runtime
+                .sprite
+                .read()
+                .await
+                .graphic_effects()
+                .get(GraphicEffect::Ghost),
+            0.0
+        );
+    }
+}
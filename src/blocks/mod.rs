@@ -5,6 +5,7 @@ mod looks;
 mod motion;
 mod operator;
 mod pen;
+mod procedures;
 mod sensing;
 mod sound;
 pub mod test;
@@ -14,12 +15,18 @@ use super::*;
 use crate::blocks::value::value_block_from_input_arr;
 use crate::runtime::Runtime;
 use async_trait::async_trait;
+use input::Key;
+use sensing::KeyOption;
 use std::convert::TryInto;
 use std::time::Duration;
-use tokio::time::sleep;
 use value::Value;
 
-fn get_block(id: BlockID, runtime: Runtime, info: &scratch_file::Block) -> Result<Box<dyn Block>> {
+fn get_block(
+    id: BlockID,
+    runtime: Runtime,
+    info: &scratch_file::Block,
+    infos: &HashMap<BlockID, scratch_file::Block>,
+) -> Result<Box<dyn Block>> {
     let (category, name) = info.opcode.split_once('_').ok_or_else(|| {
         Error::msg(format!(
             "block \"{}\": opcode {} does not exist",
@@ -45,6 +52,10 @@ fn get_block(id: BlockID, runtime: Runtime, info: &scratch_file::Block) -> Resul
         "pen" => {
             pen::get_block(name, id_clone, runtime).map_err(|e| add_error_context(id, "pen", e))
         }
+        "procedures" => procedures::get_block(name, id_clone, runtime, info, infos)
+            .map_err(|e| add_error_context(id, "procedures", e)),
+        "argument" => procedures::get_argument_block(name, id_clone, runtime)
+            .map_err(|e| add_error_context(id, "argument", e)),
         "sensing" => sensing::get_block(name, id_clone, runtime)
             .map_err(|e| add_error_context(id, "sensing", e)),
         "sound" => {
@@ -90,6 +101,33 @@ pub trait Block: std::fmt::Debug + Sync + Send {
     async fn execute(&mut self) -> Result<Next> {
         Err(Error::msg("this block cannot be executed"))
     }
+
+    /// Called instead of execute() when the substack that this block looped into has finished.
+    /// iterations is the number of times the substack has run since the block started looping.
+    #[allow(unused_variables)]
+    async fn loop_return(&mut self, iterations: usize) -> Result<Next> {
+        self.execute().await
+    }
+
+    /// Called when the procedure that this block called has finished.
+    async fn procedure_return(&mut self) -> Result<Next> {
+        Err(Error::msg("this block cannot call a procedure"))
+    }
+
+    /// Called when the thread is restarted from the hat. Blocks that keep state between
+    /// executions clear it here.
+    async fn reset(&mut self) {}
+
+    /// Returns what starts the script if this block is a hat.
+    fn hat(&self) -> Option<Hat> {
+        None
+    }
+
+    /// Condition of a Hat::EdgeTriggered hat. The VM evaluates it once per frame while the script
+    /// is not running.
+    async fn hat_condition(&mut self) -> Result<bool> {
+        Err(Error::msg("this block is not an edge-triggered hat"))
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -97,8 +135,21 @@ pub enum Next {
     None,
     Continue(BlockID),
 
-    /// Continues to BlockID and adds current block to loop stack.
+    /// Continues to BlockID and adds current block to loop stack. If BlockID is the current block,
+    /// the thread yields and loop_return() of the block is called in the next step, so that blocks
+    /// that take time run once per frame.
     Loop(BlockID),
+
+    /// Continues to BlockID and calls loop_return() of the current block when the substack ends,
+    /// without yielding.
+    Branch(BlockID),
+
+    /// Runs the procedure that starts at the definition block. procedure_return() of the current
+    /// block is called when the procedure ends.
+    Call {
+        procedure: BlockID,
+        warp: bool,
+    },
 }
 
 impl Next {
@@ -108,15 +159,58 @@ impl Next {
             None => Next::None,
         })
     }
+}
 
-    pub fn loop_(block: Option<BlockID>) -> Result<Next> {
-        Ok(match block {
-            Some(b) => Next::Loop(b),
-            None => Next::None,
-        })
+/// What starts the script of a hat block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hat {
+    GreenFlag,
+    BroadcastReceived(String),
+    KeyPressed(KeyOption),
+    SpriteClicked,
+    StageClicked,
+    BackdropSwitchesTo(String),
+    StartAsClone,
+    /// Started when hat_condition() changes from false to true
+    EdgeTriggered,
+}
+
+impl Hat {
+    pub fn matches(&self, event: &HatEvent) -> bool {
+        match (self, event) {
+            (Hat::GreenFlag, HatEvent::GreenFlag)
+            | (Hat::SpriteClicked, HatEvent::SpriteClicked)
+            | (Hat::StageClicked, HatEvent::StageClicked)
+            | (Hat::StartAsClone, HatEvent::StartAsClone)
+            | (Hat::KeyPressed(KeyOption::Any), HatEvent::KeyPressed(_)) => true,
+            (Hat::BroadcastReceived(a), HatEvent::Broadcast(b))
+            | (Hat::BackdropSwitchesTo(a), HatEvent::BackdropSwitched(b)) => a == b,
+            (Hat::KeyPressed(KeyOption::Key(a)), HatEvent::KeyPressed(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the script is restarted when the hat is triggered while the script is
+    /// running. Otherwise the trigger is ignored.
+    pub fn restarts_running_thread(&self) -> bool {
+        !matches!(self, Hat::KeyPressed(_) | Hat::EdgeTriggered)
     }
 }
 
+/// Events that start scripts. The VM creates them from broadcast messages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HatEvent {
+    GreenFlag,
+    Broadcast(String),
+    KeyPressed(Key),
+    /// Sent only to the sprite that was clicked
+    SpriteClicked,
+    StageClicked,
+    BackdropSwitched(String),
+    /// Sent only to the new clone
+    StartAsClone,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct BlockInfo {
     pub name: &'static str,
@@ -169,7 +263,7 @@ pub fn block_tree(
     };
 
     let mut block_map: HashMap<BlockID, Box<dyn Block>> = HashMap::default();
-    let mut block = get_block(top_block_id, runtime.clone(), &info)?;
+    let mut block = get_block(top_block_id, runtime.clone(), &info, infos)?;
 
     if let Some(next_id) = info.next {
         let input_blocks = block_tree(next_id, runtime.clone(), infos)?;
@@ -215,15 +309,20 @@ pub fn block_tree(
                 let value = match input_type {
                     // Value
                     1 => value_block_from_input_arr(arr).map_err(wrap_err)?,
-                    // Variable
+                    // Variable or list
                     2 | 3 => {
                         let id = arr
                             .get(2)
                             .ok_or_else(input_err)?
                             .as_str()
                             .ok_or_else(input_err)?;
-                        Box::new(value::Variable::new(id.to_string(), runtime.clone()))
-                            as Box<dyn Block>
+                        if arr.get(0).and_then(|v| v.as_i64()) == Some(13) {
+                            Box::new(value::ListContents::new(id.to_string(), runtime.clone()))
+                                as Box<dyn Block>
+                        } else {
+                            Box::new(value::Variable::new(id.to_string(), runtime.clone()))
+                                as Box<dyn Block>
+                        }
                     }
                     _ => return Err(input_err()),
                 };
@@ -311,6 +410,24 @@ pub fn get_field_value(field: &[Option<String>], index: usize) -> Result<&str> {
     }
 }
 
+/// Returns the time of the runtime's clock after seconds from a block input. Negative seconds end
+/// now.
+fn wait_end(runtime: &Runtime, seconds: f64) -> Duration {
+    // Duration::from_secs_f64() panics if the duration does not fit
+    let seconds = if seconds > 0.0 {
+        seconds.min(1e16)
+    } else {
+        0.0
+    };
+    runtime.global.clock.elapsed() + Duration::from_secs_f64(seconds)
+}
+
+/// Returns true until the runtime's clock reaches end. Blocks that wait return Next::Loop to
+/// themselves while this is true.
+fn waiting(runtime: &Runtime, end: Option<Duration>) -> bool {
+    matches!(end, Some(end) if runtime.global.clock.elapsed() < end)
+}
+
 #[cfg(test)]
 pub fn block_map(mut blocks: Vec<(BlockID, Box<dyn Block>)>) -> HashMap<BlockID, Box<dyn Block>> {
     blocks.drain(..).collect()
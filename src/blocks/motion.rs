@@ -1,7 +1,7 @@
 use super::*;
-use crate::broadcaster::BroadcastMsg;
 use crate::coordinate::{canvas_const, SpriteCoordinate};
 use crate::sprite::SpriteID;
+use crate::sprite_runtime::RotationStyle;
 use rand::distributions::{DistIter, Uniform};
 use rand::prelude::*;
 use std::fmt::{Display, Formatter};
@@ -21,6 +21,15 @@ pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Bl
         "pointindirection" => Box::new(PointInDirection::new(id, runtime)),
         "goto" => Box::new(GoTo::new(id, runtime)),
         "goto_menu" => Box::new(GoToMenu::new(id, runtime)),
+        "turnright" => Box::new(TurnRight::new(id, runtime)),
+        "turnleft" => Box::new(TurnLeft::new(id, runtime)),
+        "glidesecstoxy" => Box::new(GlideSecsToXY::new(id, runtime)),
+        "glideto" => Box::new(GlideTo::new(id, runtime)),
+        "glideto_menu" => Box::new(GoToMenu::new(id, runtime)),
+        "pointtowards" => Box::new(PointTowards::new(id, runtime)),
+        "pointtowards_menu" => Box::new(PointTowardsMenu::new(id)),
+        "ifonedgebounce" => Box::new(IfOnEdgeBounce::new(id, runtime)),
+        "setrotationstyle" => Box::new(SetRotationStyle::new(id, runtime)),
         _ => return Err(Error::msg(format!("{} does not exist", name))),
     })
 }
@@ -589,33 +598,26 @@ impl Block for GoTo {
 
     async fn execute(&mut self) -> Result<Next> {
         let option: GoToOption = self.option.value().await?.try_into()?;
-        let new_coordinate = match option {
-            GoToOption::RandomPosition => self.rng.next().unwrap(),
-            GoToOption::MousePointer => self.runtime.global.inputs.mouse_position().await.into(),
-            GoToOption::Sprite(id) => {
-                self.runtime
-                    .global
-                    .broadcaster
-                    .send(BroadcastMsg::RequestSpriteRectangle(id))?;
-
-                let mut channel = self.runtime.global.broadcaster.subscribe();
-                loop {
-                    if let BroadcastMsg::SpriteRectangle { sprite, rectangle } =
-                        channel.recv().await?
-                    {
-                        if sprite == id {
-                            break rectangle.center;
-                        }
-                    }
-                }
-            }
-        };
+        let new_coordinate = option_position(&self.runtime, option, &mut self.rng).await?;
         self.runtime.sprite.write().await.set_center(new_coordinate);
 
         Next::continue_(self.next)
     }
 }
 
+/// Returns the position that the menu option refers to.
+async fn option_position(
+    runtime: &Runtime,
+    option: GoToOption,
+    rng: &mut RandomCoordinateGenerator,
+) -> Result<SpriteCoordinate> {
+    Ok(match option {
+        GoToOption::RandomPosition => rng.next().unwrap(),
+        GoToOption::MousePointer => runtime.global.inputs.mouse_position().await.into(),
+        GoToOption::Sprite(id) => runtime.global.stage.sprite_rectangle(id).await?.center,
+    })
+}
+
 #[derive(Debug)]
 struct RandomCoordinateGenerator {
     x_iter: DistIter<Uniform<f64>, SmallRng, f64>,
@@ -723,11 +725,554 @@ impl Display for GoToOption {
 
 impl_try_from_value!(GoToOption);
 
+#[derive(Debug)]
+pub struct TurnRight {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    degrees: Box<dyn Block>,
+}
+
+impl TurnRight {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            degrees: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for TurnRight {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "TurnRight",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("DEGREES", self.degrees.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "DEGREES" {
+            self.degrees = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let degrees: f64 = self.degrees.value().await?.try_into()?;
+        let mut runtime = self.runtime.sprite.write().await;
+        let direction = runtime.direction() + degrees;
+        runtime.set_direction(direction);
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct TurnLeft {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    degrees: Box<dyn Block>,
+}
+
+impl TurnLeft {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            degrees: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for TurnLeft {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "TurnLeft",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("DEGREES", self.degrees.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "DEGREES" {
+            self.degrees = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let degrees: f64 = self.degrees.value().await?.try_into()?;
+        let mut runtime = self.runtime.sprite.write().await;
+        let direction = runtime.direction() - degrees;
+        runtime.set_direction(direction);
+        Next::continue_(self.next)
+    }
+}
+
+/// Moves the sprite a little every frame so that it reaches the destination after the given
+/// number of seconds of the runtime's clock.
+#[derive(Debug)]
+struct Glide {
+    start: SpriteCoordinate,
+    destination: SpriteCoordinate,
+    start_time: Duration,
+    seconds: f64,
+}
+
+impl Glide {
+    async fn new(runtime: &Runtime, destination: SpriteCoordinate, seconds: f64) -> Self {
+        Self {
+            start: runtime.sprite.read().await.center(),
+            destination,
+            start_time: runtime.global.clock.elapsed(),
+            seconds,
+        }
+    }
+
+    /// Moves the sprite to where it is at this time. Returns true if the sprite has reached the
+    /// destination.
+    async fn step(&self, runtime: &Runtime) -> bool {
+        let elapsed = runtime.global.clock.elapsed() - self.start_time;
+        let fraction = elapsed.as_secs_f64() / self.seconds;
+        if fraction >= 1.0 || self.seconds <= 0.0 {
+            runtime.sprite.write().await.set_center(self.destination);
+            return true;
+        }
+
+        runtime.sprite.write().await.set_center(SpriteCoordinate {
+            x: self.start.x + (self.destination.x - self.start.x) * fraction,
+            y: self.start.y + (self.destination.y - self.start.y) * fraction,
+        });
+        false
+    }
+}
+
+#[derive(Debug)]
+pub struct GlideSecsToXY {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    secs: Box<dyn Block>,
+    x: Box<dyn Block>,
+    y: Box<dyn Block>,
+    glide: Option<Glide>,
+}
+
+impl GlideSecsToXY {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            secs: Box::new(EmptyInput {}),
+            x: Box::new(EmptyInput {}),
+            y: Box::new(EmptyInput {}),
+            glide: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for GlideSecsToXY {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "GlideSecsToXY",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![
+                ("SECS", self.secs.as_ref()),
+                ("X", self.x.as_ref()),
+                ("Y", self.y.as_ref()),
+            ],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "SECS" => self.secs = block,
+            "X" => self.x = block,
+            "Y" => self.y = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let secs: f64 = self.secs.value().await?.try_into()?;
+        let x: f64 = self.x.value().await?.try_into()?;
+        let y: f64 = self.y.value().await?.try_into()?;
+        self.glide = Some(Glide::new(&self.runtime, SpriteCoordinate { x, y }, secs).await);
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if let Some(glide) = &self.glide {
+            if !glide.step(&self.runtime).await {
+                return Ok(Next::Loop(self.id));
+            }
+        }
+        self.glide = None;
+        Next::continue_(self.next)
+    }
+
+    async fn reset(&mut self) {
+        self.glide = None;
+    }
+}
+
+#[derive(Debug)]
+pub struct GlideTo {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    secs: Box<dyn Block>,
+    option: Box<dyn Block>,
+    rng: RandomCoordinateGenerator,
+    glide: Option<Glide>,
+}
+
+impl GlideTo {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            secs: Box::new(EmptyInput {}),
+            option: Box::new(EmptyInput {}),
+            rng: RandomCoordinateGenerator::new(),
+            glide: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for GlideTo {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "GlideTo",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("SECS", self.secs.as_ref()), ("TO", self.option.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "SECS" => self.secs = block,
+            "TO" => self.option = block,
+            _ => {}
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let secs: f64 = self.secs.value().await?.try_into()?;
+        let option: GoToOption = self.option.value().await?.try_into()?;
+        let destination = option_position(&self.runtime, option, &mut self.rng).await?;
+        self.glide = Some(Glide::new(&self.runtime, destination, secs).await);
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if let Some(glide) = &self.glide {
+            if !glide.step(&self.runtime).await {
+                return Ok(Next::Loop(self.id));
+            }
+        }
+        self.glide = None;
+        Next::continue_(self.next)
+    }
+
+    async fn reset(&mut self) {
+        self.glide = None;
+    }
+}
+
+#[derive(Debug)]
+pub struct PointTowards {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    towards: Box<dyn Block>,
+}
+
+impl PointTowards {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            towards: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for PointTowards {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "PointTowards",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("TOWARDS", self.towards.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "TOWARDS" {
+            self.towards = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let option: GoToOption = self.towards.value().await?.try_into()?;
+        let direction = if let GoToOption::RandomPosition = option {
+            thread_rng().gen_range(-180..=180) as f64
+        } else {
+            let target =
+                option_position(&self.runtime, option, &mut RandomCoordinateGenerator::new())
+                    .await?;
+            let center = self.runtime.sprite.read().await.center();
+            90.0 - (target.y - center.y)
+                .atan2(target.x - center.x)
+                .to_degrees()
+        };
+        self.runtime.sprite.write().await.set_direction(direction);
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct PointTowardsMenu {
+    id: BlockID,
+    option: GoToOption,
+}
+
+impl PointTowardsMenu {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            option: GoToOption::MousePointer,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for PointTowardsMenu {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "PointTowardsMenu",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("TOWARDS", format!("{}", self.option))],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "TOWARDS" {
+            self.option = GoToOption::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(Value::GoToOption(self.option))
+    }
+}
+
+#[derive(Debug)]
+pub struct IfOnEdgeBounce {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+}
+
+impl IfOnEdgeBounce {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for IfOnEdgeBounce {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "IfOnEdgeBounce",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime.sprite.write().await.if_on_edge_bounce();
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct SetRotationStyle {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+    style: RotationStyle,
+}
+
+impl SetRotationStyle {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            style: RotationStyle::AllAround,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for SetRotationStyle {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "SetRotationStyle",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("STYLE", self.style.to_string())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "STYLE" {
+            self.style = RotationStyle::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime
+            .sprite
+            .write()
+            .await
+            .set_rotation_style(self.style);
+        Next::continue_(self.next)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::blocks::value::ValueNumber;
+    use crate::clock::VirtualClock;
     use crate::coordinate::{CanvasCoordinate, Size, SpriteRectangle};
+    use crate::runtime::Global;
+    use crate::sprite_runtime::SpriteRuntime;
+    use crate::stage::{StageQuery, TestStage};
+    use crate::vm::ThreadID;
 
     #[tokio::test]
     async fn move_steps() {
@@ -888,40 +1433,145 @@ mod test {
             let mut go_to = GoTo::new(gen.get_id(), runtime.clone());
             go_to.set_input("TO", Box::new(menu));
 
-            let mut receiver = runtime.global.broadcaster.subscribe();
-            let task = spawn(async move { go_to.execute().await.unwrap() });
-
-            assert_eq!(
-                receiver.recv().await.unwrap(),
-                BroadcastMsg::RequestSpriteRectangle(SpriteID::from_sprite_name(SPRITE_NAME))
-            );
-
-            // Sprite rectangle of different sprite
-            runtime
-                .global
-                .broadcaster
-                .send(BroadcastMsg::SpriteRectangle {
-                    sprite: SpriteID::from_sprite_name("wrong_id"),
-                    rectangle: SpriteRectangle::default(),
-                })
-                .unwrap();
-
             let rectangle = SpriteRectangle {
                 center: SpriteCoordinate { x: 1.0, y: 2.0 },
                 size: Size::default(),
             };
-            runtime
-                .global
-                .broadcaster
-                .send(BroadcastMsg::SpriteRectangle {
-                    sprite: SpriteID::from_sprite_name(SPRITE_NAME),
-                    rectangle,
-                })
-                .unwrap();
+            let mut stage = TestStage::default();
+            stage
+                .rectangles
+                .insert(SpriteID::from_sprite_name(SPRITE_NAME), rectangle);
+            let stage: Arc<dyn StageQuery> = Arc::new(stage);
+            runtime.global.stage.set_query(Arc::downgrade(&stage)).await;
 
-            task.await.unwrap();
+            go_to.execute().await.unwrap();
 
             assert_eq!(runtime.sprite.read().await.center(), rectangle.center);
         }
     }
+
+    #[tokio::test]
+    async fn turn_right_and_turn_left() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+
+        let mut turn_right = TurnRight::new(gen.get_id(), runtime.clone());
+        turn_right.set_input("DEGREES", Box::new(ValueNumber::new(180.0)));
+        turn_right.execute().await.unwrap();
+        assert_eq!(runtime.sprite.read().await.direction(), -90.0);
+
+        let mut turn_left = TurnLeft::new(gen.get_id(), runtime.clone());
+        turn_left.set_input("DEGREES", Box::new(ValueNumber::new(45.0)));
+        turn_left.execute().await.unwrap();
+        assert_eq!(runtime.sprite.read().await.direction(), -135.0);
+    }
+
+    #[tokio::test]
+    async fn go_to_xy_outside_stage() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let mut go_to_xy = GoToXY::new(gen.get_id(), runtime.clone());
+        go_to_xy.set_input("X", Box::new(ValueNumber::new(1000.0)));
+        go_to_xy.set_input("Y", Box::new(ValueNumber::new(0.0)));
+        go_to_xy.execute().await.unwrap();
+
+        assert_eq!(
+            runtime.sprite.read().await.center(),
+            SpriteCoordinate { x: 240.0, y: 0.0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn glide_secs_to_xy() {
+        let clock = Arc::new(VirtualClock::default());
+        let runtime = Runtime::new(
+            Arc::new(RwLock::new(SpriteRuntime::default())),
+            Arc::new(Global {
+                clock: clock.clone(),
+                ..Default::default()
+            }),
+            ThreadID::default(),
+        );
+        let mut gen = BlockIDGenerator::new();
+        let glide_id = gen.get_id();
+        let next_id = gen.get_id();
+        let mut glide = GlideSecsToXY::new(glide_id, runtime.clone());
+        glide.set_input("SECS", Box::new(ValueNumber::new(0.1)));
+        glide.set_input("X", Box::new(ValueNumber::new(10.0)));
+        glide.set_input("Y", Box::new(ValueNumber::new(20.0)));
+        glide.set_substack("next", next_id);
+
+        // The sprite moves once per frame until the glide has taken 0.1 seconds
+        assert_eq!(glide.execute().await.unwrap(), Next::Loop(glide_id));
+        assert_eq!(
+            runtime.sprite.read().await.center(),
+            SpriteCoordinate { x: 0.0, y: 0.0 }
+        );
+
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(glide.loop_return(1).await.unwrap(), Next::Loop(glide_id));
+        assert_eq!(
+            runtime.sprite.read().await.center(),
+            SpriteCoordinate { x: 5.0, y: 10.0 }
+        );
+
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(glide.loop_return(2).await.unwrap(), Next::Continue(next_id));
+        assert_eq!(
+            runtime.sprite.read().await.center(),
+            SpriteCoordinate { x: 10.0, y: 20.0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn point_towards() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        runtime
+            .global
+            .inputs
+            .set_mouse_position(CanvasCoordinate { x: 240.0, y: 80.0 })
+            .await;
+
+        let mut menu = PointTowardsMenu::new(gen.get_id());
+        menu.set_field("TOWARDS", &[Some("_mouse_".to_string())])
+            .unwrap();
+        let mut point_towards = PointTowards::new(gen.get_id(), runtime.clone());
+        point_towards.set_input("TOWARDS", Box::new(menu));
+        point_towards.execute().await.unwrap();
+
+        assert_eq!(runtime.sprite.read().await.direction(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn if_on_edge_bounce() {
+        let runtime = Runtime::default();
+        runtime
+            .sprite
+            .write()
+            .await
+            .set_center(SpriteCoordinate { x: 240.0, y: 0.0 });
+
+        let mut gen = BlockIDGenerator::new();
+        let mut bounce = IfOnEdgeBounce::new(gen.get_id(), runtime.clone());
+        bounce.execute().await.unwrap();
+
+        assert_eq!(runtime.sprite.read().await.direction(), -90.0);
+    }
+
+    #[tokio::test]
+    async fn set_rotation_style() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let mut set_rotation_style = SetRotationStyle::new(gen.get_id(), runtime.clone());
+        set_rotation_style
+            .set_field("STYLE", &[Some("left-right".to_string())])
+            .unwrap();
+        set_rotation_style.execute().await.unwrap();
+
+        assert_eq!(
+            runtime.sprite.read().await.rotation_style(),
+            RotationStyle::LeftRight
+        );
+    }
 }
@@ -1,7 +1,11 @@
 use super::*;
+use crate::blocks::value::cast;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
 use std::mem::swap;
+use std::str::FromStr;
+use strum::EnumString;
 
 pub fn get_block(name: &str, id: BlockID, _runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
@@ -17,6 +21,12 @@ pub fn get_block(name: &str, id: BlockID, _runtime: Runtime) -> Result<Box<dyn B
         "gt" => Box::new(GreaterThan::new(id)),
         "random" => Box::new(Random::new(id)),
         "join" => Box::new(Join::new(id)),
+        "mod" => Box::new(Mod::new(id)),
+        "round" => Box::new(Round::new(id)),
+        "mathop" => Box::new(MathOp::new(id)),
+        "letter_of" => Box::new(LetterOf::new(id)),
+        "length" => Box::new(Length::new(id)),
+        "contains" => Box::new(Contains::new(id)),
         _ => return Err(Error::msg(format!("{} does not exist", name))),
     })
 }
@@ -70,7 +80,7 @@ impl Block for Equals {
     async fn value(&mut self) -> Result<Value> {
         let a = self.operand1.value().await?;
         let b = self.operand2.value().await?;
-        Ok((a == b).into())
+        Ok((cast::compare(&a, &b) == Ordering::Equal).into())
     }
 }
 
@@ -472,9 +482,9 @@ impl Block for LessThan {
     }
 
     async fn value(&mut self) -> Result<Value> {
-        let left: f64 = self.operand1.value().await?.try_into()?;
-        let right: f64 = self.operand2.value().await?.try_into()?;
-        Ok((left < right).into())
+        let left = self.operand1.value().await?;
+        let right = self.operand2.value().await?;
+        Ok((cast::compare(&left, &right) == Ordering::Less).into())
     }
 }
 
@@ -525,9 +535,9 @@ impl Block for GreaterThan {
     }
 
     async fn value(&mut self) -> Result<Value> {
-        let left: f64 = self.operand1.value().await?.try_into()?;
-        let right: f64 = self.operand2.value().await?.try_into()?;
-        Ok((left > right).into())
+        let left = self.operand1.value().await?;
+        let right = self.operand2.value().await?;
+        Ok((cast::compare(&left, &right) == Ordering::Greater).into())
     }
 }
 
@@ -645,6 +655,409 @@ impl Block for Join {
     }
 }
 
+#[derive(Debug)]
+pub struct Mod {
+    id: BlockID,
+    num1: Box<dyn Block>,
+    num2: Box<dyn Block>,
+}
+
+impl Mod {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            num1: Box::new(EmptyInput {}),
+            num2: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Mod {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Mod",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("NUM1", self.num1.as_ref()), ("NUM2", self.num2.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "NUM1" => self.num1 = block,
+            "NUM2" => self.num2 = block,
+            _ => {}
+        }
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let a: f64 = self.num1.value().await?.try_into()?;
+        let b: f64 = self.num2.value().await?.try_into()?;
+        Ok(floor_mod(a, b).into())
+    }
+}
+
+/// The result has the same sign as the divisor.
+fn floor_mod(a: f64, b: f64) -> f64 {
+    let result = a % b;
+    if result / b < 0.0 {
+        result + b
+    } else {
+        result
+    }
+}
+
+#[derive(Debug)]
+pub struct Round {
+    id: BlockID,
+    num: Box<dyn Block>,
+}
+
+impl Round {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            num: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Round {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Round",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("NUM", self.num.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "NUM" {
+            self.num = block;
+        }
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let n: f64 = self.num.value().await?.try_into()?;
+        Ok(round(n).into())
+    }
+}
+
+/// Rounds half towards positive infinity like JavaScript's Math.round().
+fn round(n: f64) -> f64 {
+    let floor = n.floor();
+    if n - floor >= 0.5 {
+        floor + 1.0
+    } else {
+        floor
+    }
+}
+
+#[derive(Debug)]
+pub struct MathOp {
+    id: BlockID,
+    operator: MathOperator,
+    num: Box<dyn Block>,
+}
+
+impl MathOp {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            operator: MathOperator::Abs,
+            num: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for MathOp {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "MathOp",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("OPERATOR", self.operator.to_string())],
+            vec![("NUM", self.num.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "NUM" {
+            self.num = block;
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "OPERATOR" {
+            self.operator = MathOperator::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let n: f64 = self.num.value().await?.try_into()?;
+        Ok(self.operator.apply(n).into())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, EnumString, strum::Display)]
+pub enum MathOperator {
+    #[strum(serialize = "abs")]
+    Abs,
+    #[strum(serialize = "floor")]
+    Floor,
+    #[strum(serialize = "ceiling")]
+    Ceiling,
+    #[strum(serialize = "sqrt")]
+    Sqrt,
+    #[strum(serialize = "sin")]
+    Sin,
+    #[strum(serialize = "cos")]
+    Cos,
+    #[strum(serialize = "tan")]
+    Tan,
+    #[strum(serialize = "asin")]
+    Asin,
+    #[strum(serialize = "acos")]
+    Acos,
+    #[strum(serialize = "atan")]
+    Atan,
+    #[strum(serialize = "ln")]
+    Ln,
+    #[strum(serialize = "log")]
+    Log,
+    #[strum(serialize = "e ^")]
+    Exp,
+    #[strum(serialize = "10 ^")]
+    Pow10,
+}
+
+impl MathOperator {
+    /// Trigonometric functions use degrees. The results of sin, cos and tan are rounded to 10
+    /// decimal places so that sin(180) is 0.
+    fn apply(self, n: f64) -> f64 {
+        match self {
+            Self::Abs => n.abs(),
+            Self::Floor => n.floor(),
+            Self::Ceiling => n.ceil(),
+            Self::Sqrt => n.sqrt(),
+            Self::Sin => round_decimal_places(n.to_radians().sin()),
+            Self::Cos => round_decimal_places(n.to_radians().cos()),
+            Self::Tan => tan(n),
+            Self::Asin => n.asin().to_degrees(),
+            Self::Acos => n.acos().to_degrees(),
+            Self::Atan => n.atan().to_degrees(),
+            Self::Ln => n.ln(),
+            Self::Log => n.log10(),
+            Self::Exp => n.exp(),
+            Self::Pow10 => 10f64.powf(n),
+        }
+    }
+}
+
+fn tan(degrees: f64) -> f64 {
+    let degrees = degrees % 360.0;
+    if degrees == 90.0 || degrees == -270.0 {
+        f64::INFINITY
+    } else if degrees == 270.0 || degrees == -90.0 {
+        f64::NEG_INFINITY
+    } else {
+        round_decimal_places(degrees.to_radians().tan())
+    }
+}
+
+fn round_decimal_places(n: f64) -> f64 {
+    (n * 1e10).round() / 1e10
+}
+
+#[derive(Debug)]
+pub struct LetterOf {
+    id: BlockID,
+    letter: Box<dyn Block>,
+    string: Box<dyn Block>,
+}
+
+impl LetterOf {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            letter: Box::new(EmptyInput {}),
+            string: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for LetterOf {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "LetterOf",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![
+                ("LETTER", self.letter.as_ref()),
+                ("STRING", self.string.as_ref()),
+            ],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "LETTER" => self.letter = block,
+            "STRING" => self.string = block,
+            _ => {}
+        }
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let letter: f64 = self.letter.value().await?.try_into()?;
+        let string = self.string.value().await?.to_string();
+        Ok(letter_of(letter, &string).into())
+    }
+}
+
+/// Letters start at 1. Returns an empty string if the letter is out of range.
+fn letter_of(letter: f64, s: &str) -> String {
+    let index = letter - 1.0;
+    if index < 0.0 || index >= s.chars().count() as f64 {
+        return String::new();
+    }
+    s.chars()
+        .nth(index as usize)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+pub struct Length {
+    id: BlockID,
+    string: Box<dyn Block>,
+}
+
+impl Length {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            string: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Length {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Length",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("STRING", self.string.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "STRING" {
+            self.string = block;
+        }
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let string = self.string.value().await?.to_string();
+        Ok((string.chars().count() as f64).into())
+    }
+}
+
+#[derive(Debug)]
+pub struct Contains {
+    id: BlockID,
+    string1: Box<dyn Block>,
+    string2: Box<dyn Block>,
+}
+
+impl Contains {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            string1: Box::new(EmptyInput {}),
+            string2: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Contains {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Contains",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![
+                ("STRING1", self.string1.as_ref()),
+                ("STRING2", self.string2.as_ref()),
+            ],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        match key {
+            "STRING1" => self.string1 = block,
+            "STRING2" => self.string2 = block,
+            _ => {}
+        }
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        // Scratch ignores case
+        let string1 = self.string1.value().await?.to_string().to_lowercase();
+        let string2 = self.string2.value().await?.to_string().to_lowercase();
+        Ok(string1.contains(&string2).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -663,6 +1076,20 @@ mod tests {
         assert_eq!(equals.value().await.unwrap(), Value::Bool(expected));
     }
 
+    #[rstest]
+    #[case("10", "10.0", true)]
+    #[case("abc", "ABC", true)]
+    #[case(" 1 ", "1", true)]
+    #[case("", "0", false)]
+    #[case("a", "b", false)]
+    #[tokio::test]
+    async fn equals_strings(#[case] a: &str, #[case] b: &str, #[case] expected: bool) {
+        let mut equals = Equals::new(BlockID::default());
+        equals.set_input("OPERAND1", Box::new(ValueString::new(a.to_string())));
+        equals.set_input("OPERAND2", Box::new(ValueString::new(b.to_string())));
+        assert_eq!(equals.value().await.unwrap(), Value::Bool(expected));
+    }
+
     #[tokio::test]
     async fn add() {
         let num1 = Box::new(ValueNumber::new(1.0));
@@ -755,6 +1182,19 @@ mod tests {
         assert_eq!(less_than.value().await.unwrap(), Value::Bool(expected));
     }
 
+    #[rstest]
+    #[case("2", "10", true)]
+    #[case("apple", "Banana", true)]
+    #[case("banana", "apple", false)]
+    #[case("10", "9a", true)]
+    #[tokio::test]
+    async fn less_than_strings(#[case] a: &str, #[case] b: &str, #[case] expected: bool) {
+        let mut less_than = LessThan::new(BlockID::default());
+        less_than.set_input("OPERAND1", Box::new(ValueString::new(a.to_string())));
+        less_than.set_input("OPERAND2", Box::new(ValueString::new(b.to_string())));
+        assert_eq!(less_than.value().await.unwrap(), Value::Bool(expected));
+    }
+
     #[rstest]
     #[case(0.0, 0.0, false)]
     #[case(1.0, 0.0, true)]
@@ -797,4 +1237,137 @@ mod tests {
             Value::String(expected.to_string())
         );
     }
+
+    #[rstest]
+    #[case(7.0, 3.0, 1.0)]
+    #[case(-7.0, 3.0, 2.0)]
+    #[case(7.0, -3.0, -2.0)]
+    #[case(-7.0, -3.0, -1.0)]
+    #[case(5.5, 2.0, 1.5)]
+    #[case(6.0, 3.0, 0.0)]
+    #[case(5.0, f64::INFINITY, 5.0)]
+    #[tokio::test]
+    async fn modulo(#[case] a: f64, #[case] b: f64, #[case] expected: f64) {
+        let mut m = Mod::new(BlockID::default());
+        m.set_input("NUM1", Box::new(ValueNumber::new(a)));
+        m.set_input("NUM2", Box::new(ValueNumber::new(b)));
+        assert_eq!(m.value().await.unwrap(), Value::Number(expected));
+    }
+
+    #[rstest]
+    #[case(1.0, 0.0)]
+    #[case(f64::INFINITY, 1.0)]
+    fn floor_mod_nan(#[case] a: f64, #[case] b: f64) {
+        assert!(floor_mod(a, b).is_nan());
+    }
+
+    #[rstest]
+    #[case(1.4, 1.0)]
+    #[case(1.5, 2.0)]
+    #[case(2.5, 3.0)]
+    #[case(-1.5, -1.0)]
+    #[case(-2.5, -2.0)]
+    #[case(-2.6, -3.0)]
+    #[case(0.49999999999999994, 0.0)]
+    #[case(f64::INFINITY, f64::INFINITY)]
+    #[tokio::test]
+    async fn round(#[case] n: f64, #[case] expected: f64) {
+        let mut round = Round::new(BlockID::default());
+        round.set_input("NUM", Box::new(ValueNumber::new(n)));
+        assert_eq!(round.value().await.unwrap(), Value::Number(expected));
+    }
+
+    #[rstest]
+    #[case("abs", -2.0, 2.0)]
+    #[case("floor", -2.5, -3.0)]
+    #[case("ceiling", 2.1, 3.0)]
+    #[case("sqrt", 16.0, 4.0)]
+    #[case("sin", 30.0, 0.5)]
+    #[case("sin", 180.0, 0.0)]
+    #[case("cos", 60.0, 0.5)]
+    #[case("cos", 90.0, 0.0)]
+    #[case("tan", 45.0, 1.0)]
+    #[case("tan", 90.0, f64::INFINITY)]
+    #[case("tan", -90.0, f64::NEG_INFINITY)]
+    #[case("tan", 450.0, f64::INFINITY)]
+    #[case("tan", 270.0, f64::NEG_INFINITY)]
+    #[case("asin", 1.0, 90.0)]
+    #[case("acos", 1.0, 0.0)]
+    #[case("atan", 1.0, 45.0)]
+    #[case("ln", 1.0, 0.0)]
+    #[case("ln", 0.0, f64::NEG_INFINITY)]
+    #[case("log", 100.0, 2.0)]
+    #[case("e ^", 0.0, 1.0)]
+    #[case("10 ^", 3.0, 1000.0)]
+    #[tokio::test]
+    async fn math_op(#[case] operator: &str, #[case] n: f64, #[case] expected: f64) {
+        let mut math_op = MathOp::new(BlockID::default());
+        math_op
+            .set_field("OPERATOR", &[Some(operator.to_string()), None])
+            .unwrap();
+        math_op.set_input("NUM", Box::new(ValueNumber::new(n)));
+        let result: f64 = math_op.value().await.unwrap().try_into().unwrap();
+        assert!(
+            result == expected || (result - expected).abs() < 1e-9,
+            "{} {} = {}",
+            operator,
+            n,
+            result
+        );
+    }
+
+    #[rstest]
+    #[case("sqrt", -1.0)]
+    #[case("asin", 2.0)]
+    #[case("acos", -2.0)]
+    #[case("ln", -1.0)]
+    fn math_op_nan(#[case] operator: &str, #[case] n: f64) {
+        assert!(MathOperator::from_str(operator).unwrap().apply(n).is_nan());
+    }
+
+    #[rstest]
+    #[case(1.0, "apple", "a")]
+    #[case(5.0, "apple", "e")]
+    #[case(6.0, "apple", "")]
+    #[case(0.0, "apple", "")]
+    #[case(-1.0, "apple", "")]
+    #[case(1.5, "apple", "a")]
+    #[case(2.0, "héllo", "é")]
+    #[case(1.0, "", "")]
+    #[tokio::test]
+    async fn letter_of(#[case] letter: f64, #[case] s: &str, #[case] expected: &str) {
+        let mut letter_of = LetterOf::new(BlockID::default());
+        letter_of.set_input("LETTER", Box::new(ValueNumber::new(letter)));
+        letter_of.set_input("STRING", Box::new(ValueString::new(s.to_string())));
+        assert_eq!(
+            letter_of.value().await.unwrap(),
+            Value::String(expected.to_string())
+        );
+    }
+
+    #[rstest]
+    #[case("", 0.0)]
+    #[case("apple", 5.0)]
+    #[case("héllo", 5.0)]
+    #[tokio::test]
+    async fn length(#[case] s: &str, #[case] expected: f64) {
+        let mut length = Length::new(BlockID::default());
+        length.set_input("STRING", Box::new(ValueString::new(s.to_string())));
+        assert_eq!(length.value().await.unwrap(), Value::Number(expected));
+    }
+
+    #[rstest]
+    #[case("apple", "a", true)]
+    #[case("apple", "PL", true)]
+    #[case("Apple", "ap", true)]
+    #[case("apple", "", true)]
+    #[case("apple", "b", false)]
+    #[case("", "a", false)]
+    #[tokio::test]
+    async fn contains(#[case] str1: &str, #[case] str2: &str, #[case] expected: bool) {
+        let mut contains = Contains::new(BlockID::default());
+        contains.set_input("STRING1", Box::new(ValueString::new(str1.to_string())));
+        contains.set_input("STRING2", Box::new(ValueString::new(str2.to_string())));
+        assert_eq!(contains.value().await.unwrap(), Value::Bool(expected));
+    }
 }
@@ -0,0 +1,689 @@
+use super::*;
+use crate::blocks::value::cast;
+use scratch_file::Mutation;
+use std::convert::TryFrom;
+
+pub fn get_block(
+    name: &str,
+    id: BlockID,
+    runtime: Runtime,
+    info: &scratch_file::Block,
+    infos: &HashMap<BlockID, scratch_file::Block>,
+) -> Result<Box<dyn Block>> {
+    Ok(match name {
+        "definition" => Box::new(Definition::new(id)),
+        "prototype" => Box::new(Prototype::new(id, mutation(info)?.proccode.clone())),
+        "call" => Box::new(Call::from_mutation(id, runtime, mutation(info)?, infos)?),
+        _ => return Err(Error::msg(format!("{} does not exist", name))),
+    })
+}
+
+pub fn get_argument_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
+    Ok(match name {
+        "reporter_string_number" => Box::new(ReporterStringNumber::new(id, runtime)),
+        "reporter_boolean" => Box::new(ReporterBoolean::new(id, runtime)),
+        _ => return Err(Error::msg(format!("{} does not exist", name))),
+    })
+}
+
+fn mutation(info: &scratch_file::Block) -> Result<&Mutation> {
+    info.mutation
+        .as_ref()
+        .ok_or_else(|| Error::msg("mutation is missing"))
+}
+
+/// Returns the ID of the definition block and the prototype mutation of the procedure.
+fn find_definition<'a>(
+    proccode: &str,
+    infos: &'a HashMap<BlockID, scratch_file::Block>,
+) -> Result<(BlockID, &'a Mutation)> {
+    for (id, info) in infos {
+        if info.opcode != "procedures_prototype" {
+            continue;
+        }
+
+        if let Some(prototype_mutation) = &info.mutation {
+            if prototype_mutation.proccode != proccode {
+                continue;
+            }
+
+            // The definition block has the prototype as its custom_block input
+            let definition = infos.iter().find(|(_, b)| {
+                b.opcode == "procedures_definition"
+                    && b.inputs
+                        .get("custom_block")
+                        .and_then(|input| input.get(1))
+                        .and_then(|prototype_id| prototype_id.as_str())
+                        .and_then(|prototype_id| BlockID::try_from(prototype_id).ok())
+                        == Some(*id)
+            });
+            if let Some((definition_id, _)) = definition {
+                return Ok((*definition_id, prototype_mutation));
+            }
+        }
+    }
+    Err(Error::msg(format!(
+        "procedure does not exist: {}",
+        proccode
+    )))
+}
+
+/// The hat block of a custom block's script.
+#[derive(Debug)]
+pub struct Definition {
+    id: BlockID,
+    prototype: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl Definition {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            prototype: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Definition {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Definition",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("custom_block", self.prototype.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "custom_block" {
+            self.prototype = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        Next::continue_(self.next)
+    }
+}
+
+/// The custom block shown inside the definition block.
+#[derive(Debug)]
+pub struct Prototype {
+    id: BlockID,
+    proccode: String,
+}
+
+impl Prototype {
+    pub fn new(id: BlockID, proccode: String) -> Self {
+        Self { id, proccode }
+    }
+}
+
+#[async_trait]
+impl Block for Prototype {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Prototype",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("proccode", self.proccode.clone())],
+            vec![],
+            vec![],
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Call {
+    id: BlockID,
+    runtime: Runtime,
+    proccode: String,
+    definition: BlockID,
+    /// (argument ID, argument name)
+    parameters: Vec<(String, String)>,
+    /// Values of the parameters that have no input
+    defaults: Vec<Value>,
+    /// Maps argument ID to input
+    arguments: HashMap<String, Box<dyn Block>>,
+    warp: bool,
+    next: Option<BlockID>,
+}
+
+impl Call {
+    pub fn new(
+        id: BlockID,
+        runtime: Runtime,
+        proccode: String,
+        definition: BlockID,
+        parameters: Vec<(String, String)>,
+        warp: bool,
+    ) -> Self {
+        Self {
+            id,
+            runtime,
+            defaults: parameter_defaults(&proccode),
+            proccode,
+            definition,
+            parameters,
+            arguments: HashMap::default(),
+            warp,
+            next: None,
+        }
+    }
+
+    fn from_mutation(
+        id: BlockID,
+        runtime: Runtime,
+        mutation: &Mutation,
+        infos: &HashMap<BlockID, scratch_file::Block>,
+    ) -> Result<Self> {
+        let (definition, prototype_mutation) = find_definition(&mutation.proccode, infos)?;
+        let parameters = prototype_mutation
+            .argument_ids()?
+            .drain(..)
+            .zip(prototype_mutation.argument_names()?.drain(..))
+            .collect();
+        Ok(Call::new(
+            id,
+            runtime,
+            mutation.proccode.clone(),
+            definition,
+            parameters,
+            prototype_mutation.warp(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Block for Call {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Call",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("proccode", self.proccode.clone())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        self.arguments.insert(key.to_string(), block);
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let mut arguments: HashMap<String, Value> = HashMap::default();
+        for (i, (argument_id, name)) in self.parameters.iter().enumerate() {
+            let value = match self.arguments.get_mut(argument_id) {
+                Some(block) => block.value().await?,
+                None => self
+                    .defaults
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| Value::String(String::new())),
+            };
+            arguments.insert(name.clone(), value);
+        }
+        self.runtime.push_arguments(arguments).await;
+
+        Ok(Next::Call {
+            procedure: self.definition,
+            warp: self.warp,
+        })
+    }
+
+    async fn procedure_return(&mut self) -> Result<Next> {
+        self.runtime.pop_arguments().await;
+        Next::continue_(self.next)
+    }
+
+    async fn reset(&mut self) {
+        self.runtime.clear_arguments().await;
+    }
+}
+
+/// Returns the values of empty arguments in the order of the parameters in the proccode. Boolean
+/// parameters (%b) are false and the others are empty strings.
+fn parameter_defaults(proccode: &str) -> Vec<Value> {
+    let mut defaults = Vec::new();
+    let mut chars = proccode.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            match chars.next() {
+                Some('b') => defaults.push(Value::Bool(false)),
+                Some('s') | Some('n') => defaults.push(Value::String(String::new())),
+                _ => {}
+            }
+        }
+    }
+    defaults
+}
+
+#[derive(Debug)]
+pub struct ReporterStringNumber {
+    id: BlockID,
+    runtime: Runtime,
+    name: String,
+}
+
+impl ReporterStringNumber {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            name: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ReporterStringNumber {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ReporterStringNumber",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("VALUE", self.name.clone())],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "VALUE" {
+            self.name = get_field_value(field, 0)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        // Scratch returns 0 if the reporter is used outside of its definition
+        Ok(self
+            .runtime
+            .argument(&self.name)
+            .await
+            .unwrap_or(Value::Number(0.0)))
+    }
+}
+
+#[derive(Debug)]
+pub struct ReporterBoolean {
+    id: BlockID,
+    runtime: Runtime,
+    name: String,
+}
+
+impl ReporterBoolean {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            name: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ReporterBoolean {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ReporterBoolean",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("VALUE", self.name.clone())],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "VALUE" {
+            self.name = get_field_value(field, 0)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(Value::Bool(match self.runtime.argument(&self.name).await {
+            Some(value) => cast::to_boolean(&value),
+            None => false,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::control::Repeat;
+    use crate::blocks::test::{BlockStub, BlockStubMsg};
+    use crate::blocks::value::ValueNumber;
+    use crate::broadcaster::BroadcastMsg;
+    use crate::thread::{StepStatus, Thread};
+
+    #[tokio::test]
+    async fn call() {
+        let runtime = Runtime::default();
+        let mut receiver = runtime.global.broadcaster.subscribe();
+        let mut gen = BlockIDGenerator::new();
+        let call_id = gen.get_id();
+        let after_call_id = gen.get_id();
+        let definition_id = gen.get_id();
+        let body_id = gen.get_id();
+
+        let mut call = Call::new(
+            call_id,
+            runtime.clone(),
+            "block %s".to_string(),
+            definition_id,
+            vec![("a".to_string(), "x".to_string())],
+            false,
+        );
+        call.set_input("a", Box::new(ValueNumber::new(1.0)));
+        call.set_substack("next", after_call_id);
+
+        let mut definition = Definition::new(definition_id);
+        definition.set_substack("next", body_id);
+
+        let blocks = block_map(vec![
+            (call_id, Box::new(call)),
+            (
+                after_call_id,
+                Box::new(BlockStub::new(after_call_id, runtime.clone())),
+            ),
+            (definition_id, Box::new(definition)),
+            (body_id, Box::new(BlockStub::new(body_id, runtime.clone()))),
+        ]);
+
+        let mut reporter = ReporterStringNumber::new(gen.get_id(), runtime.clone());
+        reporter
+            .set_field("VALUE", &[Some("x".to_string())])
+            .unwrap();
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(0.0));
+
+        let mut thread = Thread::new(call_id, blocks);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(1.0));
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(0.0));
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::BlockStub(body_id, BlockStubMsg::Executed)
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::BlockStub(after_call_id, BlockStubMsg::Executed)
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_arguments() {
+        let runtime = Runtime::default();
+        let mut call = Call::new(
+            BlockID::default(),
+            runtime.clone(),
+            "block %s and %b".to_string(),
+            BlockID::default(),
+            vec![
+                ("a".to_string(), "x".to_string()),
+                ("b".to_string(), "y".to_string()),
+            ],
+            false,
+        );
+        call.execute().await.unwrap();
+        assert_eq!(
+            runtime.argument("x").await,
+            Some(Value::String(String::new()))
+        );
+        assert_eq!(runtime.argument("y").await, Some(Value::Bool(false)));
+    }
+
+    #[rstest]
+    #[case(Some(Value::Bool(true)), true)]
+    #[case(Some(Value::from("true")), true)]
+    #[case(Some(Value::Number(1.0)), true)]
+    #[case(Some(Value::from("false")), false)]
+    #[case(Some(Value::Number(0.0)), false)]
+    #[case(Some(Value::from("")), false)]
+    #[case(None, false)]
+    #[tokio::test]
+    async fn reporter_boolean(#[case] argument: Option<Value>, #[case] expected: bool) {
+        let runtime = Runtime::default();
+        let mut arguments: HashMap<String, Value> = HashMap::default();
+        if let Some(argument) = argument {
+            arguments.insert("x".to_string(), argument);
+        }
+        runtime.push_arguments(arguments).await;
+
+        let mut reporter = ReporterBoolean::new(BlockID::default(), runtime);
+        reporter
+            .set_field("VALUE", &[Some("x".to_string())])
+            .unwrap();
+        assert_eq!(reporter.value().await.unwrap(), Value::Bool(expected));
+    }
+
+    #[rstest]
+    #[case("block", vec![])]
+    #[case("say %s %n times", vec![Value::String(String::new()), Value::String(String::new())])]
+    #[case("if %b then %s", vec![Value::Bool(false), Value::String(String::new())])]
+    fn parameter_defaults(#[case] proccode: &str, #[case] expected: Vec<Value>) {
+        assert_eq!(super::parameter_defaults(proccode), expected);
+    }
+
+    #[tokio::test]
+    async fn recursion_in_repeat() {
+        let runtime = Runtime::default();
+        let mut receiver = runtime.global.broadcaster.subscribe();
+        let mut gen = BlockIDGenerator::new();
+        let call_id = gen.get_id();
+        let after_call_id = gen.get_id();
+        let definition_id = gen.get_id();
+        let repeat_id = gen.get_id();
+        let inner_call_id = gen.get_id();
+        let body_id = gen.get_id();
+
+        // block 2 calls block 0 inside "repeat x"
+        let mut call = Call::new(
+            call_id,
+            runtime.clone(),
+            "block %s".to_string(),
+            definition_id,
+            vec![("a".to_string(), "x".to_string())],
+            false,
+        );
+        call.set_input("a", Box::new(ValueNumber::new(2.0)));
+        call.set_substack("next", after_call_id);
+
+        let mut definition = Definition::new(definition_id);
+        definition.set_substack("next", repeat_id);
+
+        let mut times = ReporterStringNumber::new(gen.get_id(), runtime.clone());
+        times.set_field("VALUE", &[Some("x".to_string())]).unwrap();
+        let mut repeat = Repeat::new(repeat_id);
+        repeat.set_input("TIMES", Box::new(times));
+        repeat.set_substack("SUBSTACK", inner_call_id);
+
+        let mut inner_call = Call::new(
+            inner_call_id,
+            runtime.clone(),
+            "block %s".to_string(),
+            definition_id,
+            vec![("a".to_string(), "x".to_string())],
+            false,
+        );
+        inner_call.set_input("a", Box::new(ValueNumber::new(0.0)));
+        inner_call.set_substack("next", body_id);
+
+        let blocks = block_map(vec![
+            (call_id, Box::new(call)),
+            (
+                after_call_id,
+                Box::new(BlockStub::new(after_call_id, runtime.clone())),
+            ),
+            (definition_id, Box::new(definition)),
+            (repeat_id, Box::new(repeat)),
+            (inner_call_id, Box::new(inner_call)),
+            (body_id, Box::new(BlockStub::new(body_id, runtime.clone()))),
+        ]);
+
+        let mut thread = Thread::new(call_id, blocks);
+        let mut steps = 0;
+        while thread.step().await.unwrap() != StepStatus::Done {
+            steps += 1;
+            assert!(steps < 100);
+        }
+
+        for _ in 0..2 {
+            assert_eq!(
+                receiver.try_recv().unwrap(),
+                BroadcastMsg::BlockStub(body_id, BlockStubMsg::Executed)
+            );
+        }
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            BroadcastMsg::BlockStub(after_call_id, BlockStubMsg::Executed)
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn leave_procedure() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let call_id = gen.get_id();
+        let definition_id = gen.get_id();
+        let body_id = gen.get_id();
+
+        let mut call = Call::new(
+            call_id,
+            runtime.clone(),
+            "block %s".to_string(),
+            definition_id,
+            vec![("a".to_string(), "x".to_string())],
+            false,
+        );
+        call.set_input("a", Box::new(ValueNumber::new(1.0)));
+
+        let mut definition = Definition::new(definition_id);
+        definition.set_substack("next", body_id);
+
+        let blocks = block_map(vec![
+            (call_id, Box::new(call)),
+            (definition_id, Box::new(definition)),
+            (body_id, Box::new(BlockStub::new(body_id, runtime.clone()))),
+        ]);
+
+        let mut reporter = ReporterStringNumber::new(gen.get_id(), runtime.clone());
+        reporter
+            .set_field("VALUE", &[Some("x".to_string())])
+            .unwrap();
+
+        let mut thread = Thread::new(call_id, blocks);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(1.0));
+
+        // The arguments of the call that was left are removed
+        thread.restart().await;
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(0.0));
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(1.0));
+
+        thread.stop().await;
+        assert_eq!(reporter.value().await.unwrap(), Value::Number(0.0));
+        assert!(thread.step().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn call_warp() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let call_id = gen.get_id();
+        let definition_id = gen.get_id();
+        let body_id = gen.get_id();
+
+        let call = Call::new(
+            call_id,
+            runtime.clone(),
+            "block".to_string(),
+            definition_id,
+            Vec::new(),
+            true,
+        );
+
+        let mut definition = Definition::new(definition_id);
+        definition.set_substack("next", body_id);
+
+        let blocks = block_map(vec![
+            (call_id, Box::new(call)),
+            (definition_id, Box::new(definition)),
+            (body_id, Box::new(BlockStub::new(body_id, runtime.clone()))),
+        ]);
+
+        // The whole procedure runs in one step
+        let mut thread = Thread::new(call_id, blocks);
+        assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+    }
+
+    #[tokio::test]
+    async fn reporter_boolean() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let mut reporter = ReporterBoolean::new(gen.get_id(), runtime.clone());
+        reporter
+            .set_field("VALUE", &[Some("b".to_string())])
+            .unwrap();
+        assert_eq!(reporter.value().await.unwrap(), Value::Bool(false));
+
+        let mut arguments: HashMap<String, Value> = HashMap::default();
+        arguments.insert("b".to_string(), Value::Bool(true));
+        runtime.push_arguments(arguments).await;
+        assert_eq!(reporter.value().await.unwrap(), Value::Bool(true));
+
+        runtime.push_arguments(HashMap::default()).await;
+        assert_eq!(reporter.value().await.unwrap(), Value::Bool(false));
+    }
+}
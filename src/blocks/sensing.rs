@@ -1,17 +1,19 @@
 use super::*;
-use crate::broadcaster::BroadcastMsg;
-use crate::coordinate::{canvas_const, CanvasCoordinate};
+use crate::clock::{days_since_2000, DateTime};
+use crate::coordinate::{canvas_const, CanvasCoordinate, CanvasRegion, SpriteCoordinate};
 use crate::sprite::SpriteID;
+use crate::sprite_runtime::BUFFER_GLYPHS;
 use graphics::types::Rectangle;
 use graphics::Context;
-use graphics_buffer::{buffer_glyphs_from_path, BufferGlyphs, RenderBuffer};
-use image::{Pixel, Rgba, RgbaImage};
+use graphics_buffer::RenderBuffer;
+use image::{imageops, Pixel, Rgba, RgbaImage};
 use input::Key;
 use itertools::{any, zip_eq};
 use palette::Srgb;
 use std::fmt::{Display, Formatter};
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
+use strum::EnumString;
 
 pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
@@ -21,6 +23,19 @@ pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Bl
         "touchingcolor" => Box::new(TouchingColor::new(id, runtime)),
         "touchingobject" => Box::new(TouchingObject::new(id, runtime)),
         "touchingobjectmenu" => Box::new(TouchingObjectMenu::new(id)),
+        "mousex" => Box::new(MouseX::new(id, runtime)),
+        "mousey" => Box::new(MouseY::new(id, runtime)),
+        "mousedown" => Box::new(MouseDown::new(id, runtime)),
+        "distanceto" => Box::new(DistanceTo::new(id, runtime)),
+        "distancetomenu" => Box::new(DistanceToMenu::new(id)),
+        "timer" => Box::new(Timer::new(id, runtime)),
+        "resettimer" => Box::new(ResetTimer::new(id, runtime)),
+        "current" => Box::new(Current::new(id, runtime)),
+        "dayssince2000" => Box::new(DaysSince2000::new(id, runtime)),
+        "username" => Box::new(Username::new(id)),
+        "askandwait" => Box::new(AskAndWait::new(id, runtime)),
+        "answer" => Box::new(Answer::new(id, runtime)),
+        "setdragmode" => Box::new(SetDragMode::new(id, runtime)),
         _ => return Err(Error::msg(format!("{} does not exist", name))),
     })
 }
@@ -31,21 +46,93 @@ pub enum KeyOption {
     Key(Key),
 }
 
+/// Scratch key names and the keys that they refer to. Letters are lowercase.
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("space", Key::Space),
+    ("left arrow", Key::Left),
+    ("right arrow", Key::Right),
+    ("up arrow", Key::Up),
+    ("down arrow", Key::Down),
+    ("enter", Key::Return),
+    ("a", Key::A),
+    ("b", Key::B),
+    ("c", Key::C),
+    ("d", Key::D),
+    ("e", Key::E),
+    ("f", Key::F),
+    ("g", Key::G),
+    ("h", Key::H),
+    ("i", Key::I),
+    ("j", Key::J),
+    ("k", Key::K),
+    ("l", Key::L),
+    ("m", Key::M),
+    ("n", Key::N),
+    ("o", Key::O),
+    ("p", Key::P),
+    ("q", Key::Q),
+    ("r", Key::R),
+    ("s", Key::S),
+    ("t", Key::T),
+    ("u", Key::U),
+    ("v", Key::V),
+    ("w", Key::W),
+    ("x", Key::X),
+    ("y", Key::Y),
+    ("z", Key::Z),
+    ("0", Key::D0),
+    ("1", Key::D1),
+    ("2", Key::D2),
+    ("3", Key::D3),
+    ("4", Key::D4),
+    ("5", Key::D5),
+    ("6", Key::D6),
+    ("7", Key::D7),
+    ("8", Key::D8),
+    ("9", Key::D9),
+    ("!", Key::Exclaim),
+    ("\"", Key::Quotedbl),
+    ("#", Key::Hash),
+    ("$", Key::Dollar),
+    ("%", Key::Percent),
+    ("&", Key::Ampersand),
+    ("'", Key::Quote),
+    ("(", Key::LeftParen),
+    (")", Key::RightParen),
+    ("*", Key::Asterisk),
+    ("+", Key::Plus),
+    (",", Key::Comma),
+    ("-", Key::Minus),
+    (".", Key::Period),
+    ("/", Key::Slash),
+    (":", Key::Colon),
+    (";", Key::Semicolon),
+    ("<", Key::Less),
+    ("=", Key::Equals),
+    (">", Key::Greater),
+    ("?", Key::Question),
+    ("@", Key::At),
+    ("[", Key::LeftBracket),
+    ("\\", Key::Backslash),
+    ("]", Key::RightBracket),
+    ("^", Key::Caret),
+    ("_", Key::Underscore),
+    ("`", Key::Backquote),
+];
+
 impl FromStr for KeyOption {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        Ok(match s {
-            "any" => KeyOption::Any,
-            _ => KeyOption::Key(match s {
-                "space" => Key::Space,
-                "left arrow" => Key::Left,
-                "right arrow" => Key::Right,
-                "up arrow" => Key::Up,
-                "down arrow" => Key::Down,
-                _ => unimplemented!(),
-            }),
-        })
+        if s == "any" {
+            return Ok(KeyOption::Any);
+        }
+
+        let name = s.to_lowercase();
+        match KEY_NAMES.iter().find(|(key_name, _)| *key_name == name) {
+            Some((_, key)) => Ok(KeyOption::Key(*key)),
+            None => Err(Error::msg(format!("unknown key: {}", s))),
+        }
     }
 }
 
@@ -53,12 +140,9 @@ impl Display for KeyOption {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             KeyOption::Any => write!(f, "any"),
-            KeyOption::Key(k) => match serde_json::to_string(k) {
-                Ok(s) => write!(f, "{}", &s),
-                Err(e) => {
-                    log::error!("{}", e);
-                    Err(std::fmt::Error {})
-                }
+            KeyOption::Key(k) => match KEY_NAMES.iter().find(|(_, key)| key == k) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", k),
             },
         }
     }
@@ -144,7 +228,7 @@ impl Block for KeyOptions {
     fn block_inputs(&self) -> BlockInputsPartial {
         BlockInputsPartial::new(
             self.block_info(),
-            vec![("KEY_OPTION", format!("{:?}", self.key))],
+            vec![("KEY_OPTION", self.key.to_string())],
             vec![],
             vec![],
         )
@@ -164,11 +248,6 @@ impl Block for KeyOptions {
 
 impl_try_from_value!(KeyOption);
 
-lazy_static::lazy_static! {
-    static ref BUFFER_GLYPHS: RwLock<BufferGlyphs<'static>>
-        = RwLock::new(buffer_glyphs_from_path("assets/Roboto-Regular.ttf").unwrap());
-}
-
 #[derive(Debug)]
 pub struct ColorIsTouchingColor {
     id: BlockID,
@@ -250,23 +329,32 @@ impl Block for ColorIsTouchingColor {
             render_buffer
         };
 
-        let sprite_id = self.runtime.thread_id().sprite_id;
-        self.runtime
+        // Only the pixels of this sprite are compared
+        let region = match CanvasRegion::opaque_pixels(&sprite_image) {
+            Some(region) => region,
+            None => return Ok(false.into()),
+        };
+        let canvas_image = self
+            .runtime
             .global
-            .broadcaster
-            .send(BroadcastMsg::RequestCanvasImage(sprite_id))?;
-        let mut channel = self.runtime.global.broadcaster.subscribe();
-        loop {
-            if let BroadcastMsg::CanvasImage(canvas_image) = channel.recv().await? {
-                let result = ColorIsTouchingColor::sprite_color_touching_canvas_color(
-                    &sprite_image,
-                    &sprite_color,
-                    &canvas_image,
-                    &canvas_color,
-                );
-                return Ok(result.into());
-            }
-        }
+            .stage
+            .canvas_image(self.runtime.thread_id().sprite_id, region)
+            .await?;
+        let sprite_image = imageops::crop_imm(
+            sprite_image.deref(),
+            region.left,
+            region.top,
+            region.width(),
+            region.height(),
+        )
+        .to_image();
+        let result = ColorIsTouchingColor::sprite_color_touching_canvas_color(
+            &sprite_image,
+            &sprite_color,
+            &canvas_image,
+            &canvas_color,
+        );
+        Ok(result.into())
     }
 }
 
@@ -344,19 +432,26 @@ impl Block for TouchingColor {
 
         let match_color = srgb_to_rgba(self.color.value().await?.try_into()?);
 
-        let sprite_id = self.runtime.thread_id().sprite_id;
-        self.runtime
+        let region = match CanvasRegion::opaque_pixels(&sprite_image) {
+            Some(region) => region,
+            None => return Ok(false.into()),
+        };
+        let canvas_image = self
+            .runtime
             .global
-            .broadcaster
-            .send(BroadcastMsg::RequestCanvasImage(sprite_id))?;
-        let mut channel = self.runtime.global.broadcaster.subscribe();
-        loop {
-            if let BroadcastMsg::CanvasImage(canvas_image) = channel.recv().await? {
-                let result =
-                    TouchingColor::touching_color(&canvas_image, &sprite_image, &match_color);
-                return Ok(result.into());
-            }
-        }
+            .stage
+            .canvas_image(self.runtime.thread_id().sprite_id, region)
+            .await?;
+        let sprite_image = imageops::crop_imm(
+            sprite_image.deref(),
+            region.left,
+            region.top,
+            region.width(),
+            region.height(),
+        )
+        .to_image();
+        let result = TouchingColor::touching_color(&canvas_image, &sprite_image, &match_color);
+        Ok(result.into())
     }
 }
 
@@ -409,13 +504,6 @@ impl TouchingObject {
             || rectangle[0] + rectangle[2] > canvas_const::X_MAX
             || rectangle[1] + rectangle[3] > canvas_const::Y_MAX
     }
-
-    fn rectangle_contains(rectangle: &Rectangle, coordinate: &CanvasCoordinate) -> bool {
-        coordinate.x >= rectangle[0]
-            && coordinate.y >= rectangle[1]
-            && coordinate.x <= rectangle[0] + rectangle[2]
-            && coordinate.y <= rectangle[1] + rectangle[3]
-    }
 }
 
 #[async_trait]
@@ -445,31 +533,26 @@ impl Block for TouchingObject {
     async fn value(&mut self) -> Result<Value> {
         let option: TouchingObjectOption = self.menu.value().await?.try_into()?;
 
-        let sprite_rectangle = self.runtime.sprite.read().await.rectangle();
-
         let result = match option {
             TouchingObjectOption::MousePointer => {
-                sleep(Duration::from_secs(0)).await; // Prevents unresponsiveness
-                let canvas_rectangle: Rectangle = sprite_rectangle.into();
-                let position = self.runtime.global.inputs.mouse_position().await;
-                TouchingObject::rectangle_contains(&canvas_rectangle, &position)
+                let mask = match self.runtime.sprite.read().await.collision_mask()? {
+                    Some(mask) => mask,
+                    None => return Ok(false.into()),
+                };
+                mask.contains(&self.runtime.global.inputs.mouse_position().await)
+            }
+            TouchingObjectOption::Edge => {
+                let sprite_rectangle = self.runtime.sprite.read().await.rectangle();
+                TouchingObject::sprite_on_edge(&sprite_rectangle.into())
             }
-            TouchingObjectOption::Edge => TouchingObject::sprite_on_edge(&sprite_rectangle.into()),
             TouchingObjectOption::Sprite(id) => {
-                self.runtime
-                    .global
-                    .broadcaster
-                    .send(BroadcastMsg::RequestSpriteRectangle(id))?;
-
-                let mut channel = self.runtime.global.broadcaster.subscribe();
-                loop {
-                    if let BroadcastMsg::SpriteRectangle { sprite, rectangle } =
-                        channel.recv().await?
-                    {
-                        if sprite == id {
-                            break sprite_rectangle.intersects(&rectangle);
-                        }
-                    }
+                let own_mask = match self.runtime.sprite.read().await.collision_mask()? {
+                    Some(mask) => mask,
+                    None => return Ok(false.into()),
+                };
+                match self.runtime.global.stage.collision_mask(id).await? {
+                    Some(mask) => own_mask.intersects(&mask),
+                    None => false,
                 }
             }
         };
@@ -552,3 +635,797 @@ impl Display for TouchingObjectOption {
 }
 
 impl_try_from_value!(TouchingObjectOption);
+
+/// Returns the mouse position in sprite coordinates.
+async fn mouse_position(runtime: &Runtime) -> SpriteCoordinate {
+    runtime
+        .global
+        .inputs
+        .mouse_position()
+        .await
+        .round_to_stage()
+        .into()
+}
+
+#[derive(Debug)]
+pub struct MouseX {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl MouseX {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for MouseX {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "MouseX",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(mouse_position(&self.runtime).await.x.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct MouseY {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl MouseY {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for MouseY {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "MouseY",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(mouse_position(&self.runtime).await.y.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct MouseDown {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl MouseDown {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for MouseDown {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "MouseDown",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self.runtime.global.inputs.mouse_down().await.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct DistanceTo {
+    id: BlockID,
+    runtime: Runtime,
+    menu: Box<dyn Block>,
+}
+
+impl DistanceTo {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            menu: Box::new(EmptyInput {}),
+        }
+    }
+}
+
+#[async_trait]
+impl Block for DistanceTo {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "DistanceTo",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("DISTANCETOMENU", self.menu.as_ref())],
+            vec![],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "DISTANCETOMENU" {
+            self.menu = block;
+        }
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let option: TouchingObjectOption = self.menu.value().await?.try_into()?;
+        let center = self.runtime.sprite.read().await.center();
+
+        let other = match option {
+            TouchingObjectOption::MousePointer => mouse_position(&self.runtime).await,
+            TouchingObjectOption::Edge => {
+                return Err(Error::msg("distance to edge is not supported"))
+            }
+            TouchingObjectOption::Sprite(id) => {
+                self.runtime.global.stage.sprite_rectangle(id).await?.center
+            }
+        };
+        Ok(center.distance(&other).into())
+    }
+}
+
+/// The options are the mouse-pointer and sprites.
+#[derive(Debug)]
+pub struct DistanceToMenu {
+    id: BlockID,
+    option: TouchingObjectOption,
+}
+
+impl DistanceToMenu {
+    pub fn new(id: BlockID) -> Self {
+        Self {
+            id,
+            option: TouchingObjectOption::MousePointer,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for DistanceToMenu {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "DistanceToMenu",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("DISTANCETOMENU", self.option.to_string())],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "DISTANCETOMENU" {
+            self.option = TouchingObjectOption::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(Value::TouchingObjectOption(self.option))
+    }
+}
+
+#[derive(Debug)]
+pub struct Timer {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl Timer {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for Timer {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Timer",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self.runtime.global.timer.seconds().into())
+    }
+}
+
+#[derive(Debug)]
+pub struct ResetTimer {
+    id: BlockID,
+    runtime: Runtime,
+    next: Option<BlockID>,
+}
+
+impl ResetTimer {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for ResetTimer {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ResetTimer",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime.global.timer.reset();
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct Current {
+    id: BlockID,
+    runtime: Runtime,
+    option: CurrentOption,
+}
+
+impl Current {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            option: CurrentOption::Year,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for Current {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Current",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("CURRENTMENU", self.option.to_string())],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "CURRENTMENU" {
+            self.option = CurrentOption::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let date_time = DateTime::from_since_epoch(self.runtime.global.clock.since_epoch());
+        let value = match self.option {
+            CurrentOption::Year => date_time.year as f64,
+            CurrentOption::Month => date_time.month as f64,
+            CurrentOption::Date => date_time.date as f64,
+            CurrentOption::DayOfWeek => date_time.day_of_week as f64,
+            CurrentOption::Hour => date_time.hour as f64,
+            CurrentOption::Minute => date_time.minute as f64,
+            CurrentOption::Second => date_time.second as f64,
+        };
+        Ok(value.into())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, EnumString, strum::Display)]
+pub enum CurrentOption {
+    #[strum(serialize = "YEAR")]
+    Year,
+    #[strum(serialize = "MONTH")]
+    Month,
+    #[strum(serialize = "DATE")]
+    Date,
+    #[strum(serialize = "DAYOFWEEK")]
+    DayOfWeek,
+    #[strum(serialize = "HOUR")]
+    Hour,
+    #[strum(serialize = "MINUTE")]
+    Minute,
+    #[strum(serialize = "SECOND")]
+    Second,
+}
+
+#[derive(Debug)]
+pub struct DaysSince2000 {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl DaysSince2000 {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for DaysSince2000 {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "DaysSince2000",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(days_since_2000(self.runtime.global.clock.since_epoch()).into())
+    }
+}
+
+/// Projects are not run by a signed in user, so the username is empty.
+#[derive(Debug)]
+pub struct Username {
+    id: BlockID,
+}
+
+impl Username {
+    pub fn new(id: BlockID) -> Self {
+        Self { id }
+    }
+}
+
+#[async_trait]
+impl Block for Username {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Username",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(String::new().into())
+    }
+}
+
+#[derive(Debug)]
+pub struct AskAndWait {
+    id: BlockID,
+    runtime: Runtime,
+    question: Box<dyn Block>,
+    next: Option<BlockID>,
+}
+
+impl AskAndWait {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            question: Box::new(EmptyInput {}),
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for AskAndWait {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "AskAndWait",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![],
+            vec![("QUESTION", self.question.as_ref())],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "QUESTION" {
+            self.question = block;
+        }
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        let question = self.question.value().await?.to_string();
+        self.runtime.global.questions.ask(question).await;
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug)]
+pub struct Answer {
+    id: BlockID,
+    runtime: Runtime,
+}
+
+impl Answer {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for Answer {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "Answer",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self.runtime.global.questions.answer().await.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct SetDragMode {
+    id: BlockID,
+    runtime: Runtime,
+    drag_mode: DragMode,
+    next: Option<BlockID>,
+}
+
+impl SetDragMode {
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            drag_mode: DragMode::Draggable,
+            next: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Block for SetDragMode {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "SetDragMode",
+            id: self.id,
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("DRAG_MODE", self.drag_mode.to_string())],
+            vec![],
+            vec![("next", &self.next)],
+        )
+    }
+
+    fn set_substack(&mut self, key: &str, block: BlockID) {
+        if key == "next" {
+            self.next = Some(block);
+        }
+    }
+
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "DRAG_MODE" {
+            self.drag_mode = DragMode::from_str(get_field_value(field, 0)?)?;
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self) -> Result<Next> {
+        self.runtime
+            .sprite
+            .write()
+            .await
+            .set_draggable(self.drag_mode == DragMode::Draggable);
+        Next::continue_(self.next)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, EnumString, strum::Display)]
+pub enum DragMode {
+    #[strum(serialize = "draggable")]
+    Draggable,
+    #[strum(serialize = "not draggable")]
+    NotDraggable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::value::ValueString;
+    use crate::clock::VirtualClock;
+    use crate::runtime::Global;
+    use crate::sprite_runtime::SpriteRuntime;
+    use crate::vm::ThreadID;
+
+    #[rstest]
+    #[case("any", Some(KeyOption::Any))]
+    #[case("space", Some(KeyOption::Key(Key::Space)))]
+    #[case("left arrow", Some(KeyOption::Key(Key::Left)))]
+    #[case("enter", Some(KeyOption::Key(Key::Return)))]
+    #[case("a", Some(KeyOption::Key(Key::A)))]
+    #[case("A", Some(KeyOption::Key(Key::A)))]
+    #[case("1", Some(KeyOption::Key(Key::D1)))]
+    #[case("/", Some(KeyOption::Key(Key::Slash)))]
+    #[case("f1", None)]
+    #[case("", None)]
+    fn key_option_from_str(#[case] s: &str, #[case] expected: Option<KeyOption>) {
+        assert_eq!(KeyOption::from_str(s).ok(), expected);
+    }
+
+    #[test]
+    fn key_option_display() {
+        for (name, key) in KEY_NAMES {
+            assert_eq!(KeyOption::Key(*key).to_string(), *name);
+            assert_eq!(KeyOption::from_str(name).unwrap(), KeyOption::Key(*key));
+        }
+        assert_eq!(KeyOption::Any.to_string(), "any");
+    }
+
+    #[tokio::test]
+    async fn mouse_x_and_mouse_y() {
+        let runtime = Runtime::default();
+        runtime
+            .global
+            .inputs
+            .set_mouse_position(CanvasCoordinate { x: 250.4, y: 500.0 })
+            .await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut mouse_x = MouseX::new(gen.get_id(), runtime.clone());
+        assert_eq!(mouse_x.value().await.unwrap(), Value::Number(10.0));
+        let mut mouse_y = MouseY::new(gen.get_id(), runtime.clone());
+        assert_eq!(mouse_y.value().await.unwrap(), Value::Number(-180.0));
+    }
+
+    #[tokio::test]
+    async fn touching_mouse_pointer() {
+        // 10x10 costume with a transparent left half
+        let mut image = image::RgbaImage::from_pixel(10, 10, image::Rgba([0, 0, 0, 255]));
+        for x in 0..5 {
+            for y in 0..10 {
+                image.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            }
+        }
+        let mut png: Vec<u8> = Vec::new();
+        image::DynamicImage::ImageRgba8(image)
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        let costume = scratch_file::Costume {
+            name: "a".to_string(),
+            md5ext: Some("a.png".to_string()),
+            asset_id: String::new(),
+            data_format: "png".to_string(),
+            rotation_center_x: 5.0,
+            rotation_center_y: 5.0,
+            bitmap_resolution: 1.0,
+            extra: Default::default(),
+        };
+        let mut images = HashMap::new();
+        images.insert("a.png".to_string(), scratch_file::Image::PNG(png));
+        let runtime = Runtime::default();
+        runtime.sprite.write().await.set_costumes(
+            crate::sprite_runtime::Costumes::new(None, &[costume], &images)
+                .await
+                .unwrap(),
+        );
+
+        let mut gen = BlockIDGenerator::new();
+        let mut menu = TouchingObjectMenu::new(gen.get_id());
+        menu.set_field("TOUCHINGOBJECTMENU", &[Some("_mouse_".to_string())])
+            .unwrap();
+        let mut touching = TouchingObject::new(gen.get_id(), runtime.clone());
+        touching.set_input("TOUCHINGOBJECTMENU", Box::new(menu));
+
+        // The costume covers x 235..245 and y 175..185 on the canvas
+        for (x, expected) in &[(242.0, true), (237.0, false), (250.0, false)] {
+            runtime
+                .global
+                .inputs
+                .set_mouse_position(CanvasCoordinate { x: *x, y: 180.0 })
+                .await;
+            assert_eq!(touching.value().await.unwrap(), Value::Bool(*expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn mouse_down() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let mut mouse_down = MouseDown::new(gen.get_id(), runtime.clone());
+        assert_eq!(mouse_down.value().await.unwrap(), Value::Bool(false));
+        runtime.global.inputs.set_mouse_down(true).await;
+        assert_eq!(mouse_down.value().await.unwrap(), Value::Bool(true));
+    }
+
+    #[tokio::test]
+    async fn distance_to_mouse_pointer() {
+        let runtime = Runtime::default();
+        runtime
+            .global
+            .inputs
+            .set_mouse_position(CanvasCoordinate { x: 270.0, y: 140.0 })
+            .await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut menu = DistanceToMenu::new(gen.get_id());
+        menu.set_field("DISTANCETOMENU", &[Some("_mouse_".to_string())])
+            .unwrap();
+        let mut distance_to = DistanceTo::new(gen.get_id(), runtime.clone());
+        distance_to.set_input("DISTANCETOMENU", Box::new(menu));
+        assert_eq!(distance_to.value().await.unwrap(), Value::Number(50.0));
+    }
+
+    #[tokio::test]
+    async fn timer_and_reset_timer() {
+        let clock = Arc::new(VirtualClock::default());
+        let runtime = Runtime::new(
+            Arc::new(RwLock::new(SpriteRuntime::default())),
+            Arc::new(Global {
+                timer: crate::clock::Timer::new(clock.clone()),
+                clock: clock.clone(),
+                ..Default::default()
+            }),
+            ThreadID::default(),
+        );
+        runtime.global.timer.resume();
+        clock.advance(Duration::from_secs(2));
+
+        let mut gen = BlockIDGenerator::new();
+        let mut timer = Timer::new(gen.get_id(), runtime.clone());
+        assert_eq!(timer.value().await.unwrap(), Value::Number(2.0));
+
+        let mut reset_timer = ResetTimer::new(gen.get_id(), runtime.clone());
+        reset_timer.execute().await.unwrap();
+        assert_eq!(timer.value().await.unwrap(), Value::Number(0.0));
+    }
+
+    #[tokio::test]
+    async fn current() {
+        // 2000-02-29 01:02:03 UTC
+        let clock = Arc::new(VirtualClock::new(Duration::from_secs(951_786_123)));
+        let runtime = Runtime::new(
+            Arc::new(RwLock::new(SpriteRuntime::default())),
+            Arc::new(Global {
+                clock,
+                ..Default::default()
+            }),
+            ThreadID::default(),
+        );
+
+        let mut gen = BlockIDGenerator::new();
+        for (option, expected) in &[
+            ("YEAR", 2000.0),
+            ("MONTH", 2.0),
+            ("DATE", 29.0),
+            ("DAYOFWEEK", 3.0),
+            ("HOUR", 1.0),
+            ("MINUTE", 2.0),
+            ("SECOND", 3.0),
+        ] {
+            let mut current = Current::new(gen.get_id(), runtime.clone());
+            current
+                .set_field("CURRENTMENU", &[Some(option.to_string())])
+                .unwrap();
+            assert_eq!(current.value().await.unwrap(), Value::Number(*expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn ask_and_wait() {
+        let runtime = Runtime::default();
+        runtime
+            .global
+            .questions
+            .set_scripted_answers(vec!["yes".to_string()])
+            .await;
+
+        let mut gen = BlockIDGenerator::new();
+        let mut answer = Answer::new(gen.get_id(), runtime.clone());
+        assert_eq!(answer.value().await.unwrap(), Value::String(String::new()));
+
+        let mut ask_and_wait = AskAndWait::new(gen.get_id(), runtime.clone());
+        ask_and_wait.set_input("QUESTION", Box::new(ValueString::new("ready?".to_string())));
+        ask_and_wait.execute().await.unwrap();
+        assert_eq!(
+            answer.value().await.unwrap(),
+            Value::String("yes".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn set_drag_mode() {
+        let runtime = Runtime::default();
+        let mut gen = BlockIDGenerator::new();
+        let mut set_drag_mode = SetDragMode::new(gen.get_id(), runtime.clone());
+        set_drag_mode.execute().await.unwrap();
+        assert!(runtime.sprite.read().await.draggable());
+
+        set_drag_mode
+            .set_field("DRAG_MODE", &[Some("not draggable".to_string())])
+            .unwrap();
+        set_drag_mode.execute().await.unwrap();
+        assert!(!runtime.sprite.read().await.draggable());
+        assert!(set_drag_mode
+            .set_field("DRAG_MODE", &[Some("a".to_string())])
+            .is_err());
+    }
+}
@@ -1,4 +1,6 @@
 use super::*;
+use crate::audio::Sound;
+use crate::blocks::value::cast;
 
 pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Block>> {
     Ok(match name {
@@ -10,15 +12,54 @@ pub fn get_block(name: &str, id: BlockID, runtime: Runtime) -> Result<Box<dyn Bl
     })
 }
 
+/// Finds the sound by name. If no sound has that name, a number selects the sound by its
+/// position, wrapping around the end of the list.
+fn find_sound(sounds: &[Sound], value: &Value) -> Option<Sound> {
+    let name = value.to_string();
+    if let Some(sound) = sounds.iter().find(|sound| sound.name() == name) {
+        return Some(sound.clone());
+    }
+
+    if sounds.is_empty() {
+        return None;
+    }
+    let number = cast::number(value);
+    if number.is_nan() || cast::is_whitespace(value) {
+        return None;
+    }
+    let len = sounds.len() as i64;
+    let index = ((number.round() as i64 - 1) % len + len) % len;
+    sounds.get(index as usize).cloned()
+}
+
+async fn play_sound(runtime: &Runtime, sound_menu: &mut dyn Block) -> Result<Option<Sound>> {
+    let value = sound_menu.value().await?;
+    let sound = find_sound(runtime.sprite.read().await.sounds(), &value);
+    if let Some(sound) = &sound {
+        // A sound that cannot be decoded or played does not stop the project
+        if let Err(e) = runtime.global.mixer.play(sound) {
+            log::warn!("cannot play sound {}: {}", sound.name(), e);
+        }
+    }
+    Ok(sound)
+}
+
 #[derive(Debug)]
 pub struct Play {
     id: BlockID,
+    runtime: Runtime,
     next: Option<BlockID>,
+    sound_menu: Box<dyn Block>,
 }
 
 impl Play {
-    pub fn new(id: BlockID, _runtime: Runtime) -> Self {
-        Self { id, next: None }
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            sound_menu: Box::new(EmptyInput {}),
+        }
     }
 }
 
@@ -35,11 +76,17 @@ impl Block for Play {
         BlockInputsPartial::new(
             self.block_info(),
             vec![],
-            vec![],
+            vec![("SOUND_MENU", self.sound_menu.as_ref())],
             vec![("next", &self.next)],
         )
     }
 
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "SOUND_MENU" {
+            self.sound_menu = block;
+        }
+    }
+
     fn set_substack(&mut self, key: &str, block: BlockID) {
         if key == "next" {
             self.next = Some(block);
@@ -47,6 +94,7 @@ impl Block for Play {
     }
 
     async fn execute(&mut self) -> Result<Next> {
+        play_sound(&self.runtime, self.sound_menu.as_mut()).await?;
         Next::continue_(self.next)
     }
 }
@@ -54,11 +102,15 @@ impl Block for Play {
 #[derive(Debug)]
 pub struct SoundsMenu {
     id: BlockID,
+    name: String,
 }
 
 impl SoundsMenu {
     pub fn new(id: BlockID, _runtime: Runtime) -> Self {
-        Self { id }
+        Self {
+            id,
+            name: String::new(),
+        }
     }
 }
 
@@ -72,21 +124,44 @@ impl Block for SoundsMenu {
     }
 
     fn block_inputs(&self) -> BlockInputsPartial {
-        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+        BlockInputsPartial::new(
+            self.block_info(),
+            vec![("SOUND_MENU", self.name.clone())],
+            vec![],
+            vec![],
+        )
     }
 
-    fn set_input(&mut self, _: &str, _: Box<dyn Block>) {}
+    fn set_field(&mut self, key: &str, field: &[Option<String>]) -> Result<()> {
+        if key == "SOUND_MENU" {
+            self.name = get_field_value(field, 0)?.to_string();
+        }
+        Ok(())
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        Ok(self.name.clone().into())
+    }
 }
 
 #[derive(Debug)]
 pub struct PlayUntilDone {
     id: BlockID,
+    runtime: Runtime,
     next: Option<BlockID>,
+    sound_menu: Box<dyn Block>,
+    end: Option<Duration>,
 }
 
 impl PlayUntilDone {
-    pub fn new(id: BlockID, _runtime: Runtime) -> Self {
-        Self { id, next: None }
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+            sound_menu: Box::new(EmptyInput {}),
+            end: None,
+        }
     }
 }
 
@@ -103,11 +178,17 @@ impl Block for PlayUntilDone {
         BlockInputsPartial::new(
             self.block_info(),
             vec![],
-            vec![],
+            vec![("SOUND_MENU", self.sound_menu.as_ref())],
             vec![("next", &self.next)],
         )
     }
 
+    fn set_input(&mut self, key: &str, block: Box<dyn Block>) {
+        if key == "SOUND_MENU" {
+            self.sound_menu = block;
+        }
+    }
+
     fn set_substack(&mut self, key: &str, block: BlockID) {
         if key == "next" {
             self.next = Some(block);
@@ -115,19 +196,39 @@ impl Block for PlayUntilDone {
     }
 
     async fn execute(&mut self) -> Result<Next> {
+        if let Some(sound) = play_sound(&self.runtime, self.sound_menu.as_mut()).await? {
+            self.end = Some(self.runtime.global.clock.elapsed() + sound.duration());
+        }
+        self.loop_return(0).await
+    }
+
+    async fn loop_return(&mut self, _: usize) -> Result<Next> {
+        if waiting(&self.runtime, self.end) {
+            return Ok(Next::Loop(self.id));
+        }
+        self.end = None;
         Next::continue_(self.next)
     }
+
+    async fn reset(&mut self) {
+        self.end = None;
+    }
 }
 
 #[derive(Debug)]
 pub struct StopAllSounds {
     id: BlockID,
+    runtime: Runtime,
     next: Option<BlockID>,
 }
 
 impl StopAllSounds {
-    pub fn new(id: BlockID, _runtime: Runtime) -> Self {
-        Self { id, next: None }
+    pub fn new(id: BlockID, runtime: Runtime) -> Self {
+        Self {
+            id,
+            runtime,
+            next: None,
+        }
     }
 }
 
@@ -156,6 +257,143 @@ impl Block for StopAllSounds {
     }
 
     async fn execute(&mut self) -> Result<Next> {
+        self.runtime.global.mixer.stop_all();
         Next::continue_(self.next)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{Mixer, MixerEvent, RecordingMixer};
+    use crate::blocks::value::{ValueNumber, ValueString};
+    use crate::clock::VirtualClock;
+    use crate::runtime::Global;
+    use crate::sprite_runtime::SpriteRuntime;
+    use crate::vm::ThreadID;
+    use rstest::rstest;
+    use scratch_file::Audio;
+
+    fn sound(name: &str, sample_count: u32) -> Sound {
+        let md5ext = format!("{}.wav", name);
+        let mut sounds: HashMap<String, Audio> = HashMap::default();
+        sounds.insert(md5ext.clone(), Audio::WAV(Vec::new()));
+        let info = scratch_file::Sound {
+            name: name.to_string(),
+            md5ext: Some(md5ext),
+            rate: 1000,
+            sample_count,
+            ..Default::default()
+        };
+        Sound::new(&info, &sounds).unwrap()
+    }
+
+    fn runtime(mixer: Arc<dyn Mixer>) -> Runtime {
+        runtime_with_clock(mixer, Arc::new(VirtualClock::default()))
+    }
+
+    fn runtime_with_clock(mixer: Arc<dyn Mixer>, clock: Arc<VirtualClock>) -> Runtime {
+        let mut sprite_runtime = SpriteRuntime::default();
+        sprite_runtime.set_sounds(vec![sound("a", 50), sound("b", 50)]);
+        Runtime::new(
+            Arc::new(RwLock::new(sprite_runtime)),
+            Arc::new(Global {
+                mixer,
+                clock,
+                ..Default::default()
+            }),
+            ThreadID::default(),
+        )
+    }
+
+    #[rstest]
+    #[case(Value::from("a"), Some("a"))]
+    #[case(Value::from("b"), Some("b"))]
+    #[case(Value::from("c"), None)]
+    #[case(Value::Number(1.0), Some("a"))]
+    #[case(Value::Number(2.4), Some("b"))]
+    #[case(Value::Number(3.0), Some("a"))]
+    #[case(Value::Number(0.0), Some("b"))]
+    #[case(Value::from("2"), Some("b"))]
+    #[case(Value::from(""), None)]
+    fn test_find_sound(#[case] value: Value, #[case] expected: Option<&str>) {
+        let sounds = vec![sound("a", 0), sound("b", 0)];
+        assert_eq!(
+            find_sound(&sounds, &value).as_ref().map(|s| s.name()),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn play() {
+        let mixer = Arc::new(RecordingMixer::default());
+        let mut gen = BlockIDGenerator::new();
+        let mut play = Play::new(gen.get_id(), runtime(mixer.clone()));
+        play.set_input("SOUND_MENU", Box::new(ValueString::new("b".to_string())));
+        play.execute().await.unwrap();
+
+        let events: Vec<MixerEvent> = mixer.events().into_iter().map(|(e, _)| e).collect();
+        assert_eq!(events, vec![MixerEvent::Play("b".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn play_until_done() {
+        let mixer = Arc::new(RecordingMixer::default());
+        let clock = Arc::new(VirtualClock::default());
+        let mut gen = BlockIDGenerator::new();
+        let id = gen.get_id();
+        let next_id = gen.get_id();
+        let mut play_until_done =
+            PlayUntilDone::new(id, runtime_with_clock(mixer.clone(), clock.clone()));
+        play_until_done.set_input("SOUND_MENU", Box::new(ValueNumber::new(1.0)));
+        play_until_done.set_substack("next", next_id);
+
+        // The sound "a" takes 50 ms
+        assert_eq!(play_until_done.execute().await.unwrap(), Next::Loop(id));
+        clock.advance(Duration::from_millis(49));
+        assert_eq!(
+            play_until_done.loop_return(1).await.unwrap(),
+            Next::Loop(id)
+        );
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(
+            play_until_done.loop_return(2).await.unwrap(),
+            Next::Continue(next_id)
+        );
+
+        let events: Vec<MixerEvent> = mixer.events().into_iter().map(|(e, _)| e).collect();
+        assert_eq!(events, vec![MixerEvent::Play("a".to_string())]);
+    }
+
+    #[derive(Debug)]
+    struct FailingMixer;
+
+    impl Mixer for FailingMixer {
+        fn play(&self, _: &Sound) -> Result<()> {
+            Err(Error::msg("unsupported format"))
+        }
+
+        fn stop_all(&self) {}
+    }
+
+    #[tokio::test]
+    async fn play_failed() {
+        let mut gen = BlockIDGenerator::new();
+        let next_id = gen.get_id();
+        let mut play = Play::new(gen.get_id(), runtime(Arc::new(FailingMixer)));
+        play.set_input("SOUND_MENU", Box::new(ValueString::new("a".to_string())));
+        play.set_substack("next", next_id);
+        assert_eq!(play.execute().await.unwrap(), Next::Continue(next_id));
+    }
+
+    #[tokio::test]
+    async fn stop_all_sounds() {
+        let mixer = Arc::new(RecordingMixer::default());
+        let mut gen = BlockIDGenerator::new();
+        let mut stop_all_sounds = StopAllSounds::new(gen.get_id(), runtime(mixer.clone()));
+        stop_all_sounds.execute().await.unwrap();
+
+        let events: Vec<MixerEvent> = mixer.events().into_iter().map(|(e, _)| e).collect();
+        assert_eq!(events, vec![MixerEvent::StopAll]);
+    }
+}
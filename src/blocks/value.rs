@@ -1,3 +1,5 @@
+pub mod cast;
+
 use super::*;
 use palette::Srgb;
 use std::convert::TryFrom;
@@ -17,25 +19,29 @@ impl Variable {
     }
 }
 
+/// Variable and list IDs are longer than block IDs so they are shortened to create a BlockID.
+fn block_id_from_variable_id(id: &str) -> BlockID {
+    // Start from first dash or zero
+    let start_index = id.find('-').map_or(0, |n| n + 1);
+    let bytes: Vec<u8> = id
+        .bytes()
+        .skip(start_index)
+        .take(id.bytes().len().saturating_sub(start_index + 1)) // Truncate last dash
+        .chain(repeat(b' ')) // Ensure length
+        .take(20)
+        .collect();
+
+    let mut b: [u8; 20] = [0; 20];
+    b.copy_from_slice(&bytes);
+    BlockID::try_from(std::str::from_utf8(&b).unwrap()).unwrap()
+}
+
 #[async_trait]
 impl Block for Variable {
     fn block_info(&self) -> BlockInfo {
-        // Start from first dash or zero
-        let start_index = self.id.find('-').map_or(0, |n| n + 1);
-        let bytes: Vec<u8> = self
-            .id
-            .bytes()
-            .skip(start_index)
-            .take(self.id.bytes().len() - start_index - 1) // Truncate last dash
-            .chain(repeat(b' ')) // Ensure length
-            .take(20)
-            .collect();
-
-        let mut b: [u8; 20] = [0; 20];
-        b.copy_from_slice(&bytes);
         BlockInfo {
             name: "Variable",
-            id: BlockID::try_from(std::str::from_utf8(&b).unwrap()).unwrap(),
+            id: block_id_from_variable_id(&self.id),
         }
     }
 
@@ -46,7 +52,49 @@ impl Block for Variable {
     fn set_input(&mut self, _: &str, _: Box<dyn Block>) {}
 
     async fn value(&mut self) -> Result<Value> {
-        self.runtime.global.variables.get(&self.id).await
+        self.runtime.variable(&self.id).await
+    }
+}
+
+/// Reports the items of a list joined into a string.
+#[derive(Debug)]
+pub struct ListContents {
+    id: String,
+    runtime: Runtime,
+}
+
+impl ListContents {
+    pub fn new(id: String, runtime: Runtime) -> Self {
+        Self { id, runtime }
+    }
+}
+
+#[async_trait]
+impl Block for ListContents {
+    fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            name: "ListContents",
+            id: block_id_from_variable_id(&self.id),
+        }
+    }
+
+    fn block_inputs(&self) -> BlockInputsPartial {
+        BlockInputsPartial::new(self.block_info(), vec![], vec![], vec![])
+    }
+
+    async fn value(&mut self) -> Result<Value> {
+        let contents = self.runtime.list(&self.id, list_contents).await?;
+        Ok(Value::String(contents))
+    }
+}
+
+/// Items are separated by spaces unless every item is a single character.
+fn list_contents(values: &[Value]) -> String {
+    let strings: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    if strings.iter().all(|s| s.chars().count() == 1) {
+        strings.concat()
+    } else {
+        strings.join(" ")
     }
 }
 
@@ -57,12 +105,16 @@ pub fn value_block_from_input_arr(arr: &[serde_json::Value]) -> Result<Box<dyn B
     let value = arr.get(1).ok_or_else(err)?;
     Ok(match value_type {
         4 | 5 | 6 | 7 | 8 => {
-            let number = if let Some(f) = value.as_f64() {
-                f
+            if let Some(f) = value.as_f64() {
+                Box::new(ValueNumber::new(f))
             } else {
-                f64::from_str(value.as_str().unwrap())?
-            };
-            Box::new(ValueNumber::new(number))
+                let s = value.as_str().ok_or_else(err)?;
+                match f64::from_str(s) {
+                    Ok(f) => Box::new(ValueNumber::new(f)),
+                    // List index inputs can contain "last", "random" or "all"
+                    Err(_) => Box::new(ValueString::new(s.to_string())),
+                }
+            }
         }
         9 => Box::new(ValueColor::new(value.as_str().unwrap())?),
         10 | 11 => {
@@ -273,11 +325,7 @@ impl TryInto<bool> for Value {
     type Error = Error;
 
     fn try_into(self) -> Result<bool> {
-        if let Self::Bool(b) = self {
-            Ok(b)
-        } else {
-            Err(Error::msg(format!("value is not bool: {}", self)))
-        }
+        Ok(cast::to_boolean(&self))
     }
 }
 
@@ -293,22 +341,7 @@ impl TryInto<f64> for &Value {
     type Error = Error;
 
     fn try_into(self) -> Result<f64> {
-        Ok(match self {
-            Value::Number(f) => {
-                if f.is_nan() {
-                    0.0
-                } else {
-                    *f
-                }
-            }
-            Value::String(s) => s.parse()?,
-            _ => {
-                return Err(Error::msg(format!(
-                    "expected String or Number but got: {:?}",
-                    self
-                )))
-            }
-        })
+        Ok(cast::to_number(self))
     }
 }
 
@@ -340,7 +373,7 @@ impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let o: &dyn Display = match self {
             Self::Bool(b) => b,
-            Self::Number(n) => n,
+            Self::Number(n) => return write!(f, "{}", cast::number_to_string(*n)),
             Self::String(s) => s,
             Self::Color(c) => return write!(f, "{:?}", c),
             Self::TouchingObjectOption(o) => o,
@@ -400,4 +433,13 @@ mod tests {
     fn test_to_string(#[case] value: Value, #[case] expected: &'static str) {
         assert_eq!(value.to_string(), expected);
     }
+
+    #[rstest]
+    #[case(vec![], "")]
+    #[case(vec![Value::from("a"), Value::from("b")], "ab")]
+    #[case(vec![Value::from("a"), Value::from("bc")], "a bc")]
+    #[case(vec![Value::Number(1.0), Value::Number(10.0)], "1 10")]
+    fn test_list_contents(#[case] values: Vec<Value>, #[case] expected: &'static str) {
+        assert_eq!(list_contents(&values), expected);
+    }
 }
@@ -0,0 +1,317 @@
+//! Type conversions that follow the rules of scratch-vm's `Cast` class, which mostly follow
+//! JavaScript's `Number()`, `Boolean()` and `String()`.
+
+use super::Value;
+use std::cmp::Ordering;
+
+/// Converts the value like JavaScript's `Number()`. The result can be NaN.
+pub fn number(value: &Value) -> f64 {
+    match value {
+        Value::Bool(b) => *b as u8 as f64,
+        Value::Number(n) => *n,
+        Value::String(s) => parse_number(s),
+        _ => parse_number(&value.to_string()),
+    }
+}
+
+/// Converts the value to a number. Values that are not numbers become 0.
+pub fn to_number(value: &Value) -> f64 {
+    let n = number(value);
+    if n.is_nan() {
+        0.0
+    } else {
+        n
+    }
+}
+
+/// Empty strings, "0" and "false" are false. All other strings are true.
+pub fn to_boolean(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => !(*n == 0.0 || n.is_nan()),
+        Value::String(s) => !(s.is_empty() || s == "0" || s.eq_ignore_ascii_case("false")),
+        _ => true,
+    }
+}
+
+/// true if the value is a string that is empty or only contains whitespace.
+pub fn is_whitespace(value: &Value) -> bool {
+    match value {
+        Value::String(s) => trim(s).is_empty(),
+        _ => false,
+    }
+}
+
+/// Compares numerically if both values are numbers. Otherwise, the values are compared as
+/// lowercase strings.
+pub fn compare(a: &Value, b: &Value) -> Ordering {
+    let mut n1 = number(a);
+    let mut n2 = number(b);
+    if n1 == 0.0 && is_whitespace(a) {
+        n1 = f64::NAN;
+    } else if n2 == 0.0 && is_whitespace(b) {
+        n2 = f64::NAN;
+    }
+
+    if n1.is_nan() || n2.is_nan() {
+        let s1 = a.to_string().to_lowercase();
+        let s2 = b.to_string().to_lowercase();
+        return s1.cmp(&s2);
+    }
+    n1.partial_cmp(&n2).unwrap_or(Ordering::Equal)
+}
+
+/// Formats the number like JavaScript's `Number.prototype.toString()`.
+pub fn number_to_string(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".to_string();
+    }
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+
+    let sign = if n < 0.0 { "-" } else { "" };
+    // Shortest representation that round-trips, such as "1.5e-7"
+    let exp_format = format!("{:e}", n.abs());
+    let (mantissa, exponent) = exp_format.split_at(exp_format.find('e').unwrap());
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent[1..].parse().unwrap();
+
+    let k = digits.len() as i32;
+    let point = exponent + 1;
+    let s = if k <= point && point <= 21 {
+        digits + &"0".repeat((point - k) as usize)
+    } else if 0 < point && point <= 21 {
+        format!(
+            "{}.{}",
+            &digits[..point as usize],
+            &digits[point as usize..]
+        )
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let fraction = if k > 1 {
+            format!(".{}", &digits[1..])
+        } else {
+            String::new()
+        };
+        let exponent_sign = if point > 0 { "+" } else { "-" };
+        format!(
+            "{}{}e{}{}",
+            &digits[..1],
+            fraction,
+            exponent_sign,
+            (point - 1).abs()
+        )
+    };
+    format!("{}{}", sign, s)
+}
+
+fn trim(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}')
+}
+
+/// Parses the string like JavaScript's `Number()`. Returns NaN if the string is not a number.
+fn parse_number(s: &str) -> f64 {
+    let s = trim(s);
+    if s.is_empty() {
+        return 0.0;
+    }
+
+    let radix = match s.get(..2) {
+        Some("0x") | Some("0X") => Some(16),
+        Some("0o") | Some("0O") => Some(8),
+        Some("0b") | Some("0B") => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        let digits = &s[2..];
+        if digits.is_empty() {
+            return f64::NAN;
+        }
+        return digits
+            .chars()
+            .try_fold(0.0, |n, c| {
+                c.to_digit(radix).map(|d| n * radix as f64 + d as f64)
+            })
+            .unwrap_or(f64::NAN);
+    }
+
+    let unsigned = s.strip_prefix(|c| c == '+' || c == '-').unwrap_or(s);
+    if unsigned == "Infinity" {
+        return if s.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+
+    if is_decimal(unsigned) {
+        s.parse().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    }
+}
+
+/// Matches digits with an optional decimal point and exponent, such as "1.", ".5" or "1e-3".
+/// Rust's parser also accepts words such as "inf" and "NaN" which are not numbers in Scratch.
+fn is_decimal(s: &str) -> bool {
+    let (mantissa, exponent) = match s.find(|c| c == 'e' || c == 'E') {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+
+    let mut parts = mantissa.splitn(2, '.');
+    let integer = parts.next().unwrap_or("");
+    let fraction = parts.next().unwrap_or("");
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(integer) || !all_digits(fraction) || integer.len() + fraction.len() == 0 {
+        return false;
+    }
+
+    match exponent {
+        Some(exponent) => {
+            let exponent = exponent
+                .strip_prefix(|c| c == '+' || c == '-')
+                .unwrap_or(exponent);
+            !exponent.is_empty() && all_digits(exponent)
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Value::Number(1.5), 1.5)]
+    #[case(Value::Number(f64::NAN), 0.0)]
+    #[case(Value::Number(f64::INFINITY), f64::INFINITY)]
+    #[case(Value::Bool(true), 1.0)]
+    #[case(Value::Bool(false), 0.0)]
+    #[case(Value::from(""), 0.0)]
+    #[case(Value::from("   "), 0.0)]
+    #[case(Value::from("\t\n"), 0.0)]
+    #[case(Value::from("10"), 10.0)]
+    #[case(Value::from("10.0"), 10.0)]
+    #[case(Value::from(" 10 "), 10.0)]
+    #[case(Value::from("+10"), 10.0)]
+    #[case(Value::from("-10"), -10.0)]
+    #[case(Value::from("1."), 1.0)]
+    #[case(Value::from(".5"), 0.5)]
+    #[case(Value::from("-.5"), -0.5)]
+    #[case(Value::from("1e3"), 1000.0)]
+    #[case(Value::from("1E-3"), 0.001)]
+    #[case(Value::from("1e+3"), 1000.0)]
+    #[case(Value::from("0x10"), 16.0)]
+    #[case(Value::from("0XfF"), 255.0)]
+    #[case(Value::from("0o17"), 15.0)]
+    #[case(Value::from("0b101"), 5.0)]
+    #[case(Value::from("Infinity"), f64::INFINITY)]
+    #[case(Value::from("+Infinity"), f64::INFINITY)]
+    #[case(Value::from("-Infinity"), f64::NEG_INFINITY)]
+    #[case(Value::from("007"), 7.0)]
+    #[case(Value::from("infinity"), 0.0)]
+    #[case(Value::from("inf"), 0.0)]
+    #[case(Value::from("NaN"), 0.0)]
+    #[case(Value::from("abc"), 0.0)]
+    #[case(Value::from("1a"), 0.0)]
+    #[case(Value::from("1 2"), 0.0)]
+    #[case(Value::from("1e"), 0.0)]
+    #[case(Value::from("e5"), 0.0)]
+    #[case(Value::from("."), 0.0)]
+    #[case(Value::from("-"), 0.0)]
+    #[case(Value::from("0x"), 0.0)]
+    #[case(Value::from("0x1g"), 0.0)]
+    #[case(Value::from("-0x10"), 0.0)]
+    #[case(Value::from("1,000"), 0.0)]
+    #[case(Value::from("true"), 0.0)]
+    fn test_to_number(#[case] value: Value, #[case] expected: f64) {
+        assert_eq!(to_number(&value), expected);
+    }
+
+    #[rstest]
+    #[case(Value::Bool(true), true)]
+    #[case(Value::Bool(false), false)]
+    #[case(Value::Number(0.0), false)]
+    #[case(Value::Number(-0.0), false)]
+    #[case(Value::Number(f64::NAN), false)]
+    #[case(Value::Number(1.0), true)]
+    #[case(Value::Number(-0.5), true)]
+    #[case(Value::from(""), false)]
+    #[case(Value::from("0"), false)]
+    #[case(Value::from("false"), false)]
+    #[case(Value::from("FALSE"), false)]
+    #[case(Value::from("False"), false)]
+    #[case(Value::from("true"), true)]
+    #[case(Value::from("1"), true)]
+    #[case(Value::from("0.0"), true)]
+    #[case(Value::from(" "), true)]
+    #[case(Value::from("abc"), true)]
+    fn test_to_boolean(#[case] value: Value, #[case] expected: bool) {
+        assert_eq!(to_boolean(&value), expected);
+    }
+
+    #[rstest]
+    #[case(Value::from("10"), Value::from("10.0"), Ordering::Equal)]
+    #[case(Value::from("10"), Value::Number(10.0), Ordering::Equal)]
+    #[case(Value::from("1e1"), Value::from("10"), Ordering::Equal)]
+    #[case(Value::from("0x10"), Value::from("16"), Ordering::Equal)]
+    #[case(Value::from(" 5 "), Value::from("5"), Ordering::Equal)]
+    #[case(Value::from("abc"), Value::from("ABC"), Ordering::Equal)]
+    #[case(Value::from("Hello"), Value::from("hello"), Ordering::Equal)]
+    #[case(Value::from("a"), Value::from("b"), Ordering::Less)]
+    #[case(Value::from("B"), Value::from("a"), Ordering::Greater)]
+    #[case(Value::from("2"), Value::from("10"), Ordering::Less)]
+    #[case(Value::from("2"), Value::from("10a"), Ordering::Greater)]
+    #[case(Value::Number(2.0), Value::Number(10.0), Ordering::Less)]
+    #[case(Value::Number(-1.0), Value::Number(0.0), Ordering::Less)]
+    #[case(Value::from(""), Value::Number(0.0), Ordering::Less)]
+    #[case(Value::from(""), Value::from(""), Ordering::Equal)]
+    #[case(Value::from(" "), Value::from("0"), Ordering::Less)]
+    #[case(Value::Number(0.0), Value::from(""), Ordering::Greater)]
+    #[case(Value::Bool(true), Value::Number(1.0), Ordering::Equal)]
+    #[case(Value::Bool(true), Value::from("true"), Ordering::Equal)]
+    #[case(Value::Bool(false), Value::from("FALSE"), Ordering::Equal)]
+    #[case(Value::Bool(false), Value::Number(0.0), Ordering::Equal)]
+    #[case(Value::from("Infinity"), Value::Number(f64::INFINITY), Ordering::Equal)]
+    #[case(
+        Value::Number(f64::INFINITY),
+        Value::Number(f64::INFINITY),
+        Ordering::Equal
+    )]
+    #[case(Value::Number(f64::NEG_INFINITY), Value::Number(0.0), Ordering::Less)]
+    #[case(Value::Number(f64::NAN), Value::from("nan"), Ordering::Equal)]
+    #[case(Value::Number(f64::NAN), Value::Number(f64::NAN), Ordering::Equal)]
+    #[case(Value::Number(0.1 + 0.2), Value::Number(0.3), Ordering::Greater)]
+    #[case(Value::Number(-0.0), Value::Number(0.0), Ordering::Equal)]
+    fn test_compare(#[case] a: Value, #[case] b: Value, #[case] expected: Ordering) {
+        assert_eq!(compare(&a, &b), expected);
+        assert_eq!(compare(&b, &a), expected.reverse());
+    }
+
+    #[rstest]
+    #[case(0.0, "0")]
+    #[case(-0.0, "0")]
+    #[case(1.0, "1")]
+    #[case(-1.5, "-1.5")]
+    #[case(0.1 + 0.2, "0.30000000000000004")]
+    #[case(123456789.0, "123456789")]
+    #[case(1e20, "100000000000000000000")]
+    #[case(1e21, "1e+21")]
+    #[case(1.5e22, "1.5e+22")]
+    #[case(0.000001, "0.000001")]
+    #[case(0.0000001, "1e-7")]
+    #[case(-1.25e-8, "-1.25e-8")]
+    #[case(f64::INFINITY, "Infinity")]
+    #[case(f64::NEG_INFINITY, "-Infinity")]
+    #[case(f64::NAN, "NaN")]
+    fn test_number_to_string(#[case] n: f64, #[case] expected: &str) {
+        assert_eq!(number_to_string(n), expected);
+    }
+}
@@ -1,9 +1,9 @@
 use super::*;
 use crate::blocks::test::BlockStubMsg;
-use crate::coordinate::{CanvasCoordinate, SpriteRectangle};
+use crate::coordinate::CanvasCoordinate;
 use crate::sprite::SpriteID;
 use crate::vm::ThreadID;
-use graphics_buffer::RenderBuffer;
+use input::Key;
 use tokio::sync::broadcast::{channel, Receiver, Sender};
 
 #[derive(Debug, Clone)]
@@ -32,6 +32,7 @@ impl Default for Broadcaster {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BroadcastMsg {
+    GreenFlag,
     Start(String),
     Finished(String),
     Clone(SpriteID),
@@ -42,14 +43,8 @@ pub enum BroadcastMsg {
         action: LayerChange,
     },
     MouseClick(CanvasCoordinate),
-    RequestSpriteRectangle(SpriteID),
-    SpriteRectangle {
-        sprite: SpriteID,
-        rectangle: SpriteRectangle,
-    },
-    /// Requests image but with sprite removed
-    RequestCanvasImage(SpriteID),
-    CanvasImage(RenderBuffer),
+    BackdropSwitched(String),
+    KeyPressed(Key),
     BlockStub(BlockID, BlockStubMsg),
 }
 
@@ -64,4 +59,6 @@ pub enum Stop {
 pub enum LayerChange {
     Front,
     Back,
+    /// Moves the sprite this many layers toward the front. Negative numbers move it backward.
+    Forward(i64),
 }
@@ -0,0 +1,238 @@
+use super::*;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of time for the VM. Blocks read the time from here instead of the system clock so that
+/// tests can control the time with VirtualClock.
+pub trait Clock: Debug + Send + Sync {
+    /// Monotonic time since the clock was created.
+    fn elapsed(&self) -> Duration;
+
+    /// Time since 1970-01-01 00:00 UTC.
+    fn since_epoch(&self) -> Duration;
+}
+
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn since_epoch(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// Time only passes when advance() is called.
+#[derive(Debug, Default)]
+pub struct VirtualClock {
+    start_since_epoch: Duration,
+    elapsed: Mutex<Duration>,
+}
+
+impl VirtualClock {
+    pub fn new(start_since_epoch: Duration) -> Self {
+        Self {
+            start_since_epoch,
+            elapsed: Mutex::default(),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut elapsed) = self.elapsed.lock() {
+            *elapsed += duration;
+        }
+    }
+}
+
+impl Clock for VirtualClock {
+    fn elapsed(&self) -> Duration {
+        self.elapsed.lock().map(|e| *e).unwrap_or_default()
+    }
+
+    fn since_epoch(&self) -> Duration {
+        self.start_since_epoch + self.elapsed()
+    }
+}
+
+/// Scratch's timer. It only counts while the VM is running.
+#[derive(Debug)]
+pub struct Timer {
+    clock: Arc<dyn Clock>,
+    state: Mutex<TimerState>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct TimerState {
+    /// Clock time when the timer was at 0
+    start: Duration,
+    /// Clock time when the timer was paused
+    paused_at: Option<Duration>,
+}
+
+impl Timer {
+    /// The timer starts paused.
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        let now = clock.elapsed();
+        Self {
+            clock,
+            state: Mutex::new(TimerState {
+                start: now,
+                paused_at: Some(now),
+            }),
+        }
+    }
+
+    pub fn seconds(&self) -> f64 {
+        match self.state.lock() {
+            Ok(state) => {
+                let now = state.paused_at.unwrap_or_else(|| self.clock.elapsed());
+                (now - state.start).as_secs_f64()
+            }
+            Err(_) => 0.0,
+        }
+    }
+
+    pub fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.start = state.paused_at.unwrap_or_else(|| self.clock.elapsed());
+        }
+    }
+
+    pub fn pause(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.paused_at.is_none() {
+                state.paused_at = Some(self.clock.elapsed());
+            }
+        }
+    }
+
+    pub fn resume(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(paused_at) = state.paused_at.take() {
+                state.start += self.clock.elapsed() - paused_at;
+            }
+        }
+    }
+}
+
+/// Calendar date and time in UTC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    /// 1 = January
+    pub month: u32,
+    /// Day of the month, starting at 1
+    pub date: u32,
+    /// 1 = Sunday
+    pub day_of_week: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    pub fn from_since_epoch(since_epoch: Duration) -> Self {
+        let seconds = since_epoch.as_secs();
+        let days = (seconds / 86400) as i64;
+        let second_of_day = (seconds % 86400) as u32;
+
+        // Converts days to a date in the proleptic Gregorian calendar
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let date = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year,
+            month,
+            date,
+            // 1970-01-01 was a Thursday
+            day_of_week: ((days + 4).rem_euclid(7) + 1) as u32,
+            hour: second_of_day / 3600,
+            minute: second_of_day / 60 % 60,
+            second: second_of_day % 60,
+        }
+    }
+}
+
+/// Returns the number of days since 2000-01-01 00:00 UTC, including the fraction of the day.
+pub fn days_since_2000(since_epoch: Duration) -> f64 {
+    const SECONDS_TO_2000: f64 = 946_684_800.0;
+    (since_epoch.as_secs_f64() - SECONDS_TO_2000) / 86400.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer() {
+        let clock = Arc::new(VirtualClock::default());
+        let timer = Timer::new(clock.clone());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(timer.seconds(), 0.0);
+
+        timer.resume();
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(timer.seconds(), 1.5);
+
+        timer.pause();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(timer.seconds(), 1.5);
+
+        timer.resume();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(timer.seconds(), 2.5);
+
+        timer.reset();
+        assert_eq!(timer.seconds(), 0.0);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(timer.seconds(), 2.0);
+    }
+
+    #[rstest]
+    #[case(0, DateTime { year: 1970, month: 1, date: 1, day_of_week: 5, hour: 0, minute: 0, second: 0 })]
+    #[case(951_782_400, DateTime { year: 2000, month: 2, date: 29, day_of_week: 3, hour: 0, minute: 0, second: 0 })]
+    #[case(1_609_459_199, DateTime { year: 2020, month: 12, date: 31, day_of_week: 5, hour: 23, minute: 59, second: 59 })]
+    #[case(1_623_761_145, DateTime { year: 2021, month: 6, date: 15, day_of_week: 3, hour: 12, minute: 45, second: 45 })]
+    fn date_time(#[case] seconds: u64, #[case] expected: DateTime) {
+        assert_eq!(
+            DateTime::from_since_epoch(Duration::from_secs(seconds)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_days_since_2000() {
+        assert_eq!(days_since_2000(Duration::from_secs(946_684_800)), 0.0);
+        assert_eq!(days_since_2000(Duration::from_secs(946_728_000)), 0.5);
+        assert_eq!(days_since_2000(Duration::from_secs(946_598_400)), -1.0);
+    }
+}
@@ -0,0 +1,287 @@
+use super::*;
+use crate::coordinate::{canvas_const, CanvasCoordinate, Scale, Size, SpriteCoordinate};
+use image::RgbaImage;
+
+/// How a costume is drawn. A cached mask is only valid for the transform it was made with.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MaskTransform {
+    /// Clockwise rotation in degrees
+    pub rotation: f64,
+    /// -1.0 if the costume is mirrored horizontally, otherwise 1.0
+    pub flip: f64,
+    pub scale: Scale,
+}
+
+/// Opaque pixels of a rotated and scaled costume, one value per stage pixel. The mask is relative
+/// to the sprite position so that it stays valid while the sprite moves.
+#[derive(Debug, PartialEq)]
+pub struct CostumeMask {
+    transform: MaskTransform,
+    /// Top left pixel relative to the sprite position
+    left: i64,
+    top: i64,
+    width: usize,
+    height: usize,
+    opaque: Vec<bool>,
+}
+
+impl CostumeMask {
+    /// image_size and center are in stage units before the transform.
+    pub fn new(
+        image: &RgbaImage,
+        image_size: Size,
+        center: SpriteCoordinate,
+        transform: MaskTransform,
+    ) -> Self {
+        let width = image_size.width * transform.scale.x;
+        let height = image_size.height * transform.scale.y;
+        let center_x = center.x * transform.scale.x;
+        let center_y = center.y * transform.scale.y;
+        let (sin, cos) = transform.rotation.to_radians().sin_cos();
+
+        // Same transform as the one used for drawing: canvas = position + rotate(flip(p - center))
+        let corners: Vec<(f64, f64)> = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)]
+            .iter()
+            .map(|(x, y)| {
+                let x = (x - center_x) * transform.flip;
+                let y = y - center_y;
+                (x * cos - y * sin, x * sin + y * cos)
+            })
+            .collect();
+        let left = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::INFINITY, f64::min)
+            .floor();
+        let top = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::INFINITY, f64::min)
+            .floor();
+        let right = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::NEG_INFINITY, f64::max)
+            .ceil();
+        let bottom = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max)
+            .ceil();
+        let mask_width = (right - left).max(0.0) as usize;
+        let mask_height = (bottom - top).max(0.0) as usize;
+
+        let mut opaque = vec![false; mask_width * mask_height];
+        for row in 0..mask_height {
+            for column in 0..mask_width {
+                // Samples the middle of each stage pixel
+                let x = left + column as f64 + 0.5;
+                let y = top + row as f64 + 0.5;
+                let image_x = (x * cos + y * sin) * transform.flip + center_x;
+                let image_y = -x * sin + y * cos + center_y;
+                if image_x < 0.0 || image_y < 0.0 || image_x >= width || image_y >= height {
+                    continue;
+                }
+                let pixel_x = (image_x / width * image.width() as f64) as u32;
+                let pixel_y = (image_y / height * image.height() as f64) as u32;
+                if pixel_x < image.width() && pixel_y < image.height() {
+                    opaque[row * mask_width + column] = image.get_pixel(pixel_x, pixel_y)[3] > 0;
+                }
+            }
+        }
+
+        Self {
+            transform,
+            left: left as i64,
+            top: top as i64,
+            width: mask_width,
+            height: mask_height,
+            opaque,
+        }
+    }
+
+    pub fn transform(&self) -> MaskTransform {
+        self.transform
+    }
+}
+
+/// A costume mask placed on the stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionMask {
+    mask: Arc<CostumeMask>,
+    /// Canvas coordinate of the top left pixel
+    left: i64,
+    top: i64,
+}
+
+impl CollisionMask {
+    pub fn new(mask: Arc<CostumeMask>, position: CanvasCoordinate) -> Self {
+        Self {
+            left: position.x.round() as i64 + mask.left,
+            top: position.y.round() as i64 + mask.top,
+            mask,
+        }
+    }
+
+    /// Returns true if an opaque pixel of both masks is at the same place on the stage. Pixels
+    /// outside of the stage are ignored.
+    pub fn intersects(&self, other: &CollisionMask) -> bool {
+        let left = self.left.max(other.left).max(0);
+        let top = self.top.max(other.top).max(0);
+        let right = self
+            .right()
+            .min(other.right())
+            .min(canvas_const::X_MAX as i64);
+        let bottom = self
+            .bottom()
+            .min(other.bottom())
+            .min(canvas_const::Y_MAX as i64);
+
+        (top..bottom).any(|y| (left..right).any(|x| self.is_opaque(x, y) && other.is_opaque(x, y)))
+    }
+
+    /// Returns true if the pixel at the point is opaque.
+    pub fn contains(&self, point: &CanvasCoordinate) -> bool {
+        let x = point.x.floor() as i64;
+        let y = point.y.floor() as i64;
+        x >= self.left
+            && y >= self.top
+            && x < self.right()
+            && y < self.bottom()
+            && self.is_opaque(x, y)
+    }
+
+    fn right(&self) -> i64 {
+        self.left + self.mask.width as i64
+    }
+
+    fn bottom(&self) -> i64 {
+        self.top + self.mask.height as i64
+    }
+
+    /// x and y are canvas coordinates inside the mask.
+    fn is_opaque(&self, x: i64, y: i64) -> bool {
+        let column = (x - self.left) as usize;
+        let row = (y - self.top) as usize;
+        self.mask.opaque[row * self.mask.width + column]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const NO_TRANSFORM: MaskTransform = MaskTransform {
+        rotation: 0.0,
+        flip: 1.0,
+        scale: Scale { x: 1.0, y: 1.0 },
+    };
+
+    /// Opaque square with the center as the rotation center
+    fn square(size: u32) -> RgbaImage {
+        RgbaImage::from_pixel(size, size, Rgba([0, 0, 0, 255]))
+    }
+
+    fn mask(image: &RgbaImage, transform: MaskTransform, x: f64, y: f64) -> CollisionMask {
+        let size = Size {
+            width: image.width() as f64,
+            height: image.height() as f64,
+        };
+        let center = SpriteCoordinate {
+            x: size.width / 2.0,
+            y: size.height / 2.0,
+        };
+        CollisionMask::new(
+            Arc::new(CostumeMask::new(image, size, center, transform)),
+            CanvasCoordinate { x, y },
+        )
+    }
+
+    #[rstest]
+    #[case(100.0, 100.0, true)]
+    #[case(109.0, 100.0, true)]
+    #[case(110.0, 100.0, false)]
+    #[case(100.0, 90.0, false)]
+    fn intersects(#[case] x: f64, #[case] y: f64, #[case] expected: bool) {
+        let image = square(10);
+        let a = mask(&image, NO_TRANSFORM, 100.0, 100.0);
+        let b = mask(&image, NO_TRANSFORM, x, y);
+        assert_eq!(a.intersects(&b), expected);
+        assert_eq!(b.intersects(&a), expected);
+    }
+
+    #[rstest]
+    #[case(100.0, 100.0, true)]
+    #[case(95.0, 95.0, true)]
+    #[case(104.9, 104.9, true)]
+    #[case(105.0, 100.0, false)]
+    #[case(94.9, 100.0, false)]
+    #[case(100.0, 105.0, false)]
+    #[case(97.0, 100.0, false)]
+    fn contains(#[case] x: f64, #[case] y: f64, #[case] expected: bool) {
+        let mut image = square(10);
+        // Transparent hole left of the center
+        for x in 1..3 {
+            for y in 4..6 {
+                image.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+        let a = mask(&image, NO_TRANSFORM, 100.0, 100.0);
+        assert_eq!(a.contains(&CanvasCoordinate { x, y }), expected);
+    }
+
+    #[test]
+    fn transparent_pixels() {
+        let mut image = square(10);
+        for x in 0..5 {
+            for y in 0..10 {
+                image.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+        let a = mask(&image, NO_TRANSFORM, 100.0, 100.0);
+        // Only overlaps the transparent left half
+        assert!(!a.intersects(&mask(&square(10), NO_TRANSFORM, 92.0, 100.0)));
+        assert!(a.intersects(&mask(&square(10), NO_TRANSFORM, 96.0, 100.0)));
+
+        let flipped = MaskTransform {
+            flip: -1.0,
+            ..NO_TRANSFORM
+        };
+        let a = mask(&image, flipped, 100.0, 100.0);
+        assert!(a.intersects(&mask(&square(10), NO_TRANSFORM, 92.0, 100.0)));
+        assert!(!a.intersects(&mask(&square(10), NO_TRANSFORM, 108.0, 100.0)));
+    }
+
+    #[test]
+    fn rotated() {
+        let rotated = MaskTransform {
+            rotation: 45.0,
+            ..NO_TRANSFORM
+        };
+        let a = mask(&square(20), rotated, 100.0, 100.0);
+        // The bounding boxes overlap near the corner, but the rotated square does not reach it
+        assert!(!a.intersects(&mask(&square(4), NO_TRANSFORM, 112.0, 112.0)));
+        // The corner of the rotated square reaches 14 pixels to the right
+        assert!(a.intersects(&mask(&square(4), NO_TRANSFORM, 114.0, 100.0)));
+    }
+
+    #[test]
+    fn outside_of_stage() {
+        let image = square(10);
+        let a = mask(&image, NO_TRANSFORM, -10.0, 100.0);
+        let b = mask(&image, NO_TRANSFORM, -8.0, 100.0);
+        assert!(!a.intersects(&b));
+    }
+
+    #[test]
+    fn scaled() {
+        let scaled = MaskTransform {
+            scale: Scale { x: 2.0, y: 2.0 },
+            ..NO_TRANSFORM
+        };
+        let a = mask(&square(10), scaled, 100.0, 100.0);
+        assert!(a.intersects(&mask(&square(2), NO_TRANSFORM, 109.0, 100.0)));
+        assert!(!a.intersects(&mask(&square(2), NO_TRANSFORM, 112.0, 100.0)));
+    }
+}
@@ -1,4 +1,5 @@
 use graphics::types::Rectangle;
+use image::RgbaImage;
 
 /// Center = 0, 0
 /// Left = -240, right = +240
@@ -23,6 +24,10 @@ impl SpriteCoordinate {
             y: self.y + direction.to_radians().cos() * magnitude,
         }
     }
+
+    pub fn distance(&self, other: &Self) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
 }
 
 impl PartialEq for SpriteCoordinate {
@@ -48,6 +53,17 @@ pub struct CanvasCoordinate {
     pub y: f64,
 }
 
+impl CanvasCoordinate {
+    /// Rounds to whole pixels and moves the coordinate inside the stage. Scratch reports the
+    /// mouse position this way.
+    pub fn round_to_stage(&self) -> Self {
+        Self {
+            x: self.x.round().max(0.0).min(canvas_const::X_MAX),
+            y: self.y.round().max(0.0).min(canvas_const::Y_MAX),
+        }
+    }
+}
+
 impl From<SpriteCoordinate> for CanvasCoordinate {
     fn from(sprite_coordinate: SpriteCoordinate) -> Self {
         Self {
@@ -57,11 +73,58 @@ impl From<SpriteCoordinate> for CanvasCoordinate {
     }
 }
 
+/// Area of canvas pixels. right and bottom are exclusive.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CanvasRegion {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl CanvasRegion {
+    /// Returns the smallest region that contains every pixel that is not transparent, or None if
+    /// the image is transparent.
+    pub fn opaque_pixels(image: &RgbaImage) -> Option<Self> {
+        let mut result: Option<Self> = None;
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            result = Some(match result {
+                Some(region) => Self {
+                    left: region.left.min(x),
+                    top: region.top.min(y),
+                    right: region.right.max(x + 1),
+                    bottom: region.bottom.max(y + 1),
+                },
+                None => Self {
+                    left: x,
+                    top: y,
+                    right: x + 1,
+                    bottom: y + 1,
+                },
+            });
+        }
+        result
+    }
+
+    pub fn width(&self) -> u32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> u32 {
+        self.bottom - self.top
+    }
+}
+
 pub mod canvas_const {
     /// Right edge
     pub const X_MAX: f64 = 480.0;
     /// Bottom edge
     pub const Y_MAX: f64 = 360.0;
+    /// Moving sprites keep at least this many pixels on the stage.
+    pub const FENCE_WIDTH: f64 = 15.0;
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Default)]
@@ -120,15 +183,65 @@ impl SpriteRectangle {
         })
     }
 
-    pub fn intersects(&self, other: &SpriteRectangle) -> bool {
-        let self_top_left = self.top_left();
-        let self_bottom_right = self.bottom_right();
-        let other_top_left = other.top_left();
-        let other_bottom_right = other.bottom_right();
-        !(self_top_left.x > other_bottom_right.x
-            || self_bottom_right.x < other_top_left.x
-            || self_top_left.y < other_bottom_right.y
-            || self_bottom_right.y > other_top_left.y)
+    /// Returns the center that keeps part of the rectangle on the stage. Small sprites keep at least
+    /// half of their size on the stage.
+    pub fn fenced_center(&self) -> SpriteCoordinate {
+        let inset = f64::min(
+            canvas_const::FENCE_WIDTH,
+            (f64::min(self.size.width, self.size.height) / 2.0).floor(),
+        );
+        let top_left = self.top_left();
+        let bottom_right = self.bottom_right();
+        let mut center = self.center;
+
+        let sx = canvas_const::X_MAX / 2.0 - inset;
+        if bottom_right.x < -sx {
+            center.x = (center.x - (sx + bottom_right.x)).ceil();
+        } else if top_left.x > sx {
+            center.x = (center.x + (sx - top_left.x)).floor();
+        }
+
+        let sy = canvas_const::Y_MAX / 2.0 - inset;
+        if top_left.y < -sy {
+            center.y = (center.y - (sy + top_left.y)).ceil();
+        } else if bottom_right.y > sy {
+            center.y = (center.y + (sy - bottom_right.y)).floor();
+        }
+        center
+    }
+
+    /// Returns the center that keeps the whole rectangle on the stage.
+    pub fn center_inside_stage(&self) -> SpriteCoordinate {
+        let top_left = self.top_left();
+        let bottom_right = self.bottom_right();
+        let mut center = self.center;
+
+        if top_left.x < -canvas_const::X_MAX / 2.0 {
+            center.x += -canvas_const::X_MAX / 2.0 - top_left.x;
+        }
+        if bottom_right.x > canvas_const::X_MAX / 2.0 {
+            center.x += canvas_const::X_MAX / 2.0 - bottom_right.x;
+        }
+        if top_left.y > canvas_const::Y_MAX / 2.0 {
+            center.y += canvas_const::Y_MAX / 2.0 - top_left.y;
+        }
+        if bottom_right.y < -canvas_const::Y_MAX / 2.0 {
+            center.y += -canvas_const::Y_MAX / 2.0 - bottom_right.y;
+        }
+        center
+    }
+
+    /// Returns the distance to each edge of the stage in the order left, top, right, bottom. The
+    /// distance is zero if the rectangle touches or crosses the edge.
+    pub fn distances_to_edges(&self) -> [f64; 4] {
+        let top_left = self.top_left();
+        let bottom_right = self.bottom_right();
+        [
+            f64::max(0.0, canvas_const::X_MAX / 2.0 + top_left.x),
+            f64::max(0.0, canvas_const::Y_MAX / 2.0 - top_left.y),
+            f64::max(0.0, canvas_const::X_MAX / 2.0 - bottom_right.x),
+            f64::max(0.0, canvas_const::Y_MAX / 2.0 + bottom_right.y),
+        ]
     }
 }
 
@@ -192,6 +305,14 @@ mod tests {
                 SpriteCoordinate { x: -1.0, y: 0.0 }
             );
         }
+
+        #[test]
+        fn distance() {
+            let a = SpriteCoordinate { x: 1.0, y: 2.0 };
+            let b = SpriteCoordinate { x: 4.0, y: -2.0 };
+            assert_eq!(a.distance(&b), 5.0);
+            assert_eq!(a.distance(&a), 0.0);
+        }
     }
 
     mod canvas_coordinate {
@@ -222,10 +343,49 @@ mod tests {
                 }
             );
         }
+
+        #[test]
+        fn round_to_stage() {
+            assert_eq!(
+                CanvasCoordinate { x: 1.4, y: 2.6 }.round_to_stage(),
+                CanvasCoordinate { x: 1.0, y: 3.0 }
+            );
+            assert_eq!(
+                CanvasCoordinate { x: -10.0, y: 400.0 }.round_to_stage(),
+                CanvasCoordinate { x: 0.0, y: 360.0 }
+            );
+        }
+    }
+
+    mod canvas_region {
+        use super::*;
+        use image::Rgba;
+
+        #[test]
+        fn opaque_pixels() {
+            let mut image = RgbaImage::new(10, 10);
+            assert_eq!(CanvasRegion::opaque_pixels(&image), None);
+
+            image.put_pixel(2, 7, Rgba([0, 0, 0, 1]));
+            image.put_pixel(5, 3, Rgba([255, 255, 255, 255]));
+            let region = CanvasRegion::opaque_pixels(&image).unwrap();
+            assert_eq!(
+                region,
+                CanvasRegion {
+                    left: 2,
+                    top: 3,
+                    right: 6,
+                    bottom: 8,
+                }
+            );
+            assert_eq!(region.width(), 4);
+            assert_eq!(region.height(), 5);
+        }
     }
 
     mod sprite_rectangle {
         use super::*;
+        use rstest::rstest;
 
         #[test]
         fn test_contains() {
@@ -345,88 +505,82 @@ mod tests {
             }
         }
 
-        #[test]
-        fn intersects() {
-            struct Test {
-                a: SpriteRectangle,
-                b: SpriteRectangle,
-                expected: bool,
-            }
+        #[rstest]
+        #[case(0.0, 0.0, 0.0, 0.0)]
+        #[case(1000.0, 0.0, 240.0, 0.0)]
+        #[case(-1000.0, -1000.0, -240.0, -180.0)]
+        #[case(239.0, 179.0, 239.0, 179.0)]
+        fn fenced_center_of_point(
+            #[case] x: f64,
+            #[case] y: f64,
+            #[case] expected_x: f64,
+            #[case] expected_y: f64,
+        ) {
+            let rect = SpriteRectangle {
+                center: SpriteCoordinate { x, y },
+                size: Size::default(),
+            };
+            assert_eq!(
+                rect.fenced_center(),
+                SpriteCoordinate {
+                    x: expected_x,
+                    y: expected_y
+                }
+            );
+        }
 
-            let tests = vec![
-                Test {
-                    a: SpriteRectangle {
-                        center: SpriteCoordinate { x: 0.0, y: 0.0 },
-                        size: Size {
-                            width: 0.0,
-                            height: 0.0,
-                        },
-                    },
-                    b: SpriteRectangle {
-                        center: SpriteCoordinate { x: 0.0, y: 0.0 },
-                        size: Size {
-                            width: 0.0,
-                            height: 0.0,
-                        },
-                    },
-                    expected: true,
-                },
-                Test {
-                    a: SpriteRectangle {
-                        center: SpriteCoordinate { x: 1.0, y: 1.0 },
-                        size: Size {
-                            width: 2.0,
-                            height: 2.0,
-                        },
-                    },
-                    b: SpriteRectangle {
-                        center: SpriteCoordinate { x: 1.0, y: 1.0 },
-                        size: Size {
-                            width: 2.0,
-                            height: 2.0,
-                        },
-                    },
-                    expected: true,
-                },
-                Test {
-                    a: SpriteRectangle {
-                        center: SpriteCoordinate { x: 1.0, y: 1.0 },
-                        size: Size {
-                            width: 2.0,
-                            height: 2.0,
-                        },
-                    },
-                    b: SpriteRectangle {
-                        center: SpriteCoordinate { x: 2.0, y: 2.0 },
-                        size: Size {
-                            width: 2.0,
-                            height: 2.0,
-                        },
-                    },
-                    expected: true,
+        #[rstest]
+        #[case(0.0, 0.0, 0.0, 0.0)]
+        #[case(300.0, 0.0, 265.0, 0.0)]
+        #[case(-300.0, 0.0, -265.0, 0.0)]
+        #[case(0.0, 300.0, 0.0, 205.0)]
+        #[case(0.0, -300.0, 0.0, -205.0)]
+        fn fenced_center(
+            #[case] x: f64,
+            #[case] y: f64,
+            #[case] expected_x: f64,
+            #[case] expected_y: f64,
+        ) {
+            let rect = SpriteRectangle {
+                center: SpriteCoordinate { x, y },
+                size: Size {
+                    width: 80.0,
+                    height: 80.0,
                 },
-                Test {
-                    a: SpriteRectangle {
-                        center: SpriteCoordinate { x: 1.0, y: 1.0 },
-                        size: Size {
-                            width: 4.0,
-                            height: 4.0,
-                        },
-                    },
-                    b: SpriteRectangle {
-                        center: SpriteCoordinate { x: 2.0, y: 2.0 },
-                        size: Size {
-                            width: 4.0,
-                            height: 4.0,
-                        },
-                    },
-                    expected: true,
-                },
-            ];
+            };
+            assert_eq!(
+                rect.fenced_center(),
+                SpriteCoordinate {
+                    x: expected_x,
+                    y: expected_y
+                }
+            );
+        }
 
-            for (i, test) in tests.iter().enumerate() {
-                assert_eq!(test.a.intersects(&test.b), test.expected, "{}", i);
-            }
+        #[rstest]
+        #[case(0.0, 0.0, 0.0, 0.0)]
+        #[case(235.0, 0.0, 230.0, 0.0)]
+        #[case(-235.0, -175.0, -230.0, -170.0)]
+        fn center_inside_stage(
+            #[case] x: f64,
+            #[case] y: f64,
+            #[case] expected_x: f64,
+            #[case] expected_y: f64,
+        ) {
+            let rect = SpriteRectangle {
+                center: SpriteCoordinate { x, y },
+                size: Size {
+                    width: 20.0,
+                    height: 20.0,
+                },
+            };
+            assert_eq!(
+                rect.center_inside_stage(),
+                SpriteCoordinate {
+                    x: expected_x,
+                    y: expected_y
+                }
+            );
         }
     }
 }
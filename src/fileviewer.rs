@@ -33,7 +33,7 @@ async fn block_inputs(targets: &[scratch_file::Target]) -> Result<Vec<SpriteBloc
     let mut block_inputs: Vec<SpriteBlocks> = Vec::with_capacity(targets.len());
 
     for target in targets {
-        let sprite_runtime = SpriteRuntime::new(&target);
+        let sprite_runtime = SpriteRuntime::new(&target, &[]);
         let sprite_id = SpriteID::from_sprite_name(&target.name);
         let sprite = Sprite::new(
             sprite_id,
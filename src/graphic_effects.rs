@@ -0,0 +1,357 @@
+use super::*;
+use crate::coordinate::Size;
+use image::{Rgba, RgbaImage};
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GraphicEffect {
+    Color,
+    Fisheye,
+    Whirl,
+    Pixelate,
+    Mosaic,
+    Brightness,
+    Ghost,
+}
+
+impl FromStr for GraphicEffect {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "color" => Self::Color,
+            "fisheye" => Self::Fisheye,
+            "whirl" => Self::Whirl,
+            "pixelate" => Self::Pixelate,
+            "mosaic" => Self::Mosaic,
+            "brightness" => Self::Brightness,
+            "ghost" => Self::Ghost,
+            _ => return Err(Error::msg(format!("s is invalid: {}", s))),
+        })
+    }
+}
+
+impl Display for GraphicEffect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            GraphicEffect::Color => "color",
+            GraphicEffect::Fisheye => "fisheye",
+            GraphicEffect::Whirl => "whirl",
+            GraphicEffect::Pixelate => "pixelate",
+            GraphicEffect::Mosaic => "mosaic",
+            GraphicEffect::Brightness => "brightness",
+            GraphicEffect::Ghost => "ghost",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Effect values of a sprite, in the units of the effect blocks. All effects are 0 by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct GraphicEffects {
+    color: f64,
+    fisheye: f64,
+    whirl: f64,
+    pixelate: f64,
+    mosaic: f64,
+    brightness: f64,
+    ghost: f64,
+}
+
+impl GraphicEffects {
+    pub fn get(&self, effect: GraphicEffect) -> f64 {
+        match effect {
+            GraphicEffect::Color => self.color,
+            GraphicEffect::Fisheye => self.fisheye,
+            GraphicEffect::Whirl => self.whirl,
+            GraphicEffect::Pixelate => self.pixelate,
+            GraphicEffect::Mosaic => self.mosaic,
+            GraphicEffect::Brightness => self.brightness,
+            GraphicEffect::Ghost => self.ghost,
+        }
+    }
+
+    /// Brightness is clamped to -100 to 100 and ghost is clamped to 0 to 100.
+    pub fn set(&mut self, effect: GraphicEffect, value: f64) {
+        match effect {
+            GraphicEffect::Color => self.color = value,
+            GraphicEffect::Fisheye => self.fisheye = value,
+            GraphicEffect::Whirl => self.whirl = value,
+            GraphicEffect::Pixelate => self.pixelate = value,
+            GraphicEffect::Mosaic => self.mosaic = value,
+            GraphicEffect::Brightness => self.brightness = value.max(-100.0).min(100.0),
+            GraphicEffect::Ghost => self.ghost = value.max(0.0).min(100.0),
+        }
+    }
+
+    /// 0.0 = transparent, 1.0 = opaque
+    pub fn alpha(&self) -> f64 {
+        1.0 - self.ghost / 100.0
+    }
+
+    /// Returns false if apply() would return the same image. Ghost is not applied to pixels.
+    pub fn changes_pixels(&self) -> bool {
+        self.color != 0.0
+            || self.fisheye != 0.0
+            || self.whirl != 0.0
+            || self.pixelate != 0.0
+            || self.mosaic != 0.0
+            || self.brightness != 0.0
+    }
+
+    /// Returns the costume image with the effects applied. This follows Scratch's shader; each
+    /// pixel samples the source image at a distorted position and then its color is shifted.
+    /// skin_size is the size of the costume on the stage.
+    pub fn apply(&self, image: &RgbaImage, skin_size: Size) -> RgbaImage {
+        let (width, height) = image.dimensions();
+        let mut result = RgbaImage::new(width, height);
+        if width == 0 || height == 0 {
+            return result;
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let coordinate = self.distort(
+                    [
+                        (x as f64 + 0.5) / width as f64,
+                        (y as f64 + 0.5) / height as f64,
+                    ],
+                    skin_size,
+                );
+                let source_x = (coordinate[0] * width as f64).floor();
+                let source_y = (coordinate[1] * height as f64).floor();
+                let pixel = image.get_pixel(
+                    source_x.max(0.0).min(width as f64 - 1.0) as u32,
+                    source_y.max(0.0).min(height as f64 - 1.0) as u32,
+                );
+                result.put_pixel(x, y, self.shift_color(pixel));
+            }
+        }
+        result
+    }
+
+    /// Moves a texture coordinate (0 to 1) by the mosaic, pixelate, whirl and fisheye effects.
+    fn distort(&self, coordinate: [f64; 2], skin_size: Size) -> [f64; 2] {
+        const CENTER: f64 = 0.5;
+
+        let [mut x, mut y] = coordinate;
+
+        let mosaic = ((self.mosaic.abs() + 10.0) / 10.0 + 0.5)
+            .floor()
+            .max(1.0)
+            .min(512.0);
+        if mosaic != 1.0 {
+            x = (mosaic * x).fract();
+            y = (mosaic * y).fract();
+        }
+
+        let pixelate = self.pixelate.abs() / 10.0;
+        if pixelate != 0.0 {
+            // Pixels are squares on the stage regardless of the image resolution
+            x = ((x * skin_size.width / pixelate).floor() + 0.5) * pixelate / skin_size.width;
+            y = ((y * skin_size.height / pixelate).floor() + 0.5) * pixelate / skin_size.height;
+        }
+
+        if self.whirl != 0.0 {
+            let whirl = -self.whirl * PI / 180.0;
+            let (offset_x, offset_y) = (x - CENTER, y - CENTER);
+            let factor = (1.0 - offset_x.hypot(offset_y) / CENTER).max(0.0);
+            let angle = whirl * factor * factor;
+            let (sin, cos) = angle.sin_cos();
+            x = offset_x * cos + offset_y * sin + CENTER;
+            y = -offset_x * sin + offset_y * cos + CENTER;
+        }
+
+        if self.fisheye != 0.0 {
+            let fisheye = ((self.fisheye + 100.0) / 100.0).max(0.0);
+            let (vector_x, vector_y) = ((x - CENTER) / CENTER, (y - CENTER) / CENTER);
+            let length = vector_x.hypot(vector_y);
+            if length > 0.0 {
+                let r = length.min(1.0).powf(fisheye) * length.max(1.0);
+                x = CENTER + r * vector_x / length * CENTER;
+                y = CENTER + r * vector_y / length * CENTER;
+            }
+        }
+
+        [x, y]
+    }
+
+    /// Changes the hue by the color effect and the lightness by the brightness effect.
+    fn shift_color(&self, pixel: &Rgba<u8>) -> Rgba<u8> {
+        let [r, g, b, a] = pixel.0;
+        if a == 0 {
+            return *pixel;
+        }
+
+        let mut rgb = [r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0];
+
+        let color = (self.color / 200.0) % 1.0;
+        if color != 0.0 {
+            const MIN_LIGHTNESS: f64 = 0.11 / 2.0;
+            const MIN_SATURATION: f64 = 0.09;
+
+            let [mut hue, mut saturation, mut value] = rgb_to_hsv(rgb);
+            // Black and gray have no hue so they are given some color first
+            if value < MIN_LIGHTNESS {
+                hue = 0.0;
+                saturation = 1.0;
+                value = MIN_LIGHTNESS;
+            } else if saturation < MIN_SATURATION {
+                hue = 0.0;
+                saturation = MIN_SATURATION;
+            }
+            hue = (hue + color).rem_euclid(1.0);
+            rgb = hsv_to_rgb([hue, saturation, value]);
+        }
+
+        let brightness = self.brightness / 100.0;
+        for channel in &mut rgb {
+            *channel = (*channel + brightness).max(0.0).min(1.0);
+        }
+
+        Rgba([
+            (rgb[0] * 255.0).round() as u8,
+            (rgb[1] * 255.0).round() as u8,
+            (rgb[2] * 255.0).round() as u8,
+            a,
+        ])
+    }
+}
+
+/// All components are 0 to 1.
+fn rgb_to_hsv([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta / 6.0).rem_euclid(1.0)
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    [hue, saturation, max]
+}
+
+/// All components are 0 to 1.
+fn hsv_to_rgb([hue, saturation, value]: [f64; 3]) -> [f64; 3] {
+    let sector = hue * 6.0;
+    let f = sector.fract();
+    let p = value * (1.0 - saturation);
+    let q = value * (1.0 - saturation * f);
+    let t = value * (1.0 - saturation * (1.0 - f));
+    match sector.floor() as i64 % 6 {
+        0 => [value, t, p],
+        1 => [q, value, p],
+        2 => [p, value, t],
+        3 => [p, q, value],
+        4 => [t, p, value],
+        _ => [value, p, q],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]], width: u32) -> RgbaImage {
+        let mut image = RgbaImage::new(width, pixels.len() as u32 / width);
+        for (i, pixel) in pixels.iter().enumerate() {
+            image.put_pixel(i as u32 % width, i as u32 / width, Rgba(*pixel));
+        }
+        image
+    }
+
+    fn effects(effect: GraphicEffect, value: f64) -> GraphicEffects {
+        let mut effects = GraphicEffects::default();
+        effects.set(effect, value);
+        effects
+    }
+
+    const SIZE: Size = Size {
+        width: 2.0,
+        height: 2.0,
+    };
+
+    #[rstest]
+    #[case(GraphicEffect::Brightness, 150.0, 100.0)]
+    #[case(GraphicEffect::Brightness, -150.0, -100.0)]
+    #[case(GraphicEffect::Ghost, 150.0, 100.0)]
+    #[case(GraphicEffect::Ghost, -1.0, 0.0)]
+    #[case(GraphicEffect::Color, 250.0, 250.0)]
+    fn set(#[case] effect: GraphicEffect, #[case] value: f64, #[case] expected: f64) {
+        assert_eq!(effects(effect, value).get(effect), expected);
+    }
+
+    #[test]
+    fn ghost_does_not_change_pixels() {
+        let effects = effects(GraphicEffect::Ghost, 25.0);
+        assert!(!effects.changes_pixels());
+        assert_eq!(effects.alpha(), 0.75);
+    }
+
+    #[rstest]
+    #[case(GraphicEffect::Color, 100.0, [255, 0, 0, 255], [0, 255, 255, 255])]
+    #[case(GraphicEffect::Color, 200.0, [255, 0, 0, 255], [255, 0, 0, 255])]
+    #[case(GraphicEffect::Color, -100.0, [0, 255, 0, 255], [255, 0, 255, 255])]
+    #[case(GraphicEffect::Brightness, 100.0, [10, 20, 30, 255], [255, 255, 255, 255])]
+    #[case(GraphicEffect::Brightness, -100.0, [10, 20, 30, 255], [0, 0, 0, 255])]
+    #[case(GraphicEffect::Brightness, 100.0, [10, 20, 30, 0], [10, 20, 30, 0])]
+    fn shift_color(
+        #[case] effect: GraphicEffect,
+        #[case] value: f64,
+        #[case] pixel: [u8; 4],
+        #[case] expected: [u8; 4],
+    ) {
+        let result = effects(effect, value).apply(&image(&[pixel], 1), SIZE);
+        assert_eq!(result.get_pixel(0, 0).0, expected);
+    }
+
+    #[test]
+    fn pixelate() {
+        let source = image(
+            &[
+                [1, 0, 0, 255],
+                [2, 0, 0, 255],
+                [3, 0, 0, 255],
+                [4, 0, 0, 255],
+            ],
+            2,
+        );
+        // One pixel covers the whole 2x2 costume
+        let result = effects(GraphicEffect::Pixelate, 20.0).apply(&source, SIZE);
+        let center = result.get_pixel(1, 1).0;
+        assert!(result.pixels().all(|pixel| pixel.0 == center));
+    }
+
+    #[test]
+    fn mosaic() {
+        let source = image(
+            &[
+                [1, 0, 0, 255],
+                [2, 0, 0, 255],
+                [3, 0, 0, 255],
+                [4, 0, 0, 255],
+            ],
+            2,
+        );
+        // Two copies of the image fit in each row, so each copy is 1x1
+        let result = effects(GraphicEffect::Mosaic, 10.0).apply(&source, SIZE);
+        assert_eq!(result.get_pixel(0, 0), result.get_pixel(1, 0));
+        assert_eq!(result.get_pixel(0, 1), result.get_pixel(1, 1));
+    }
+
+    #[rstest]
+    #[case(GraphicEffect::Whirl, 90.0)]
+    #[case(GraphicEffect::Fisheye, 50.0)]
+    fn distortion_keeps_center(#[case] effect: GraphicEffect, #[case] value: f64) {
+        let effects = effects(effect, value);
+        let [x, y] = effects.distort([0.5, 0.5], SIZE);
+        assert!((x - 0.5).abs() < 1e-9 && (y - 0.5).abs() < 1e-9);
+        assert_ne!(effects.distort([0.25, 0.5], SIZE), [0.25, 0.5]);
+    }
+}
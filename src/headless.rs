@@ -0,0 +1,157 @@
+use super::*;
+use crate::audio::NullMixer;
+use crate::blocks::value::Value;
+use crate::clock::{SystemClock, VirtualClock};
+use crate::vm::{FRAME_DURATION, VM};
+use graphics_buffer::RenderBuffer;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use tokio::time::{timeout, Duration};
+
+/// Runs the project from the green flag without opening a window. The run ends when all threads
+/// are done or when run_duration has passed. Questions are answered from answers in order. The
+/// final values of all variables are printed.
+pub async fn headless(
+    file_path: &Path,
+    run_duration: Duration,
+    answers: Vec<String>,
+) -> Result<()> {
+    let scratch_file = ScratchFile::parse(BufReader::new(File::open(file_path)?))?;
+    let variables = run(scratch_file, run_duration, answers).await?;
+
+    let mut w = BufWriter::new(std::io::stdout());
+    output_variables(&mut w, &variables)?;
+    w.flush()?;
+    Ok(())
+}
+
+async fn run(
+    scratch_file: ScratchFile,
+    run_duration: Duration,
+    answers: Vec<String>,
+) -> Result<Vec<(String, Value)>> {
+    let vm = VM::new(
+        None,
+        scratch_file,
+        Arc::new(NullMixer),
+        Arc::new(SystemClock::default()),
+    )
+    .await?;
+    vm.set_scripted_answers(answers).await;
+    vm.green_flag().await;
+
+    if timeout(run_duration, vm.wait_until_finished())
+        .await
+        .is_err()
+    {
+        log::info!("threads did not finish within {:?}", run_duration);
+    }
+
+    Ok(vm.variables().await)
+}
+
+/// Runs the project from the green flag for after_frames frames and saves the stage to a PNG file.
+pub async fn screenshot(file_path: &Path, after_frames: u32, out_path: &Path) -> Result<()> {
+    let scratch_file = ScratchFile::parse(BufReader::new(File::open(file_path)?))?;
+    let render_buffer = render_after_frames(scratch_file, after_frames).await?;
+    render_buffer.save(out_path)?;
+    Ok(())
+}
+
+/// Frames are stepped on a virtual clock, so the result does not depend on the speed of the
+/// machine.
+async fn render_after_frames(scratch_file: ScratchFile, after_frames: u32) -> Result<RenderBuffer> {
+    let clock = Arc::new(VirtualClock::default());
+    let vm = VM::new(None, scratch_file, Arc::new(NullMixer), clock.clone()).await?;
+    // Questions cannot be answered while frames are stepped
+    vm.set_scripted_answers(Vec::new()).await;
+    vm.start_green_flag().await;
+    for _ in 0..after_frames {
+        vm.step_frame().await;
+        clock.advance(FRAME_DURATION);
+    }
+    vm.render_stage().await
+}
+
+fn output_variables<W>(w: &mut W, variables: &[(String, Value)]) -> Result<()>
+where
+    W: std::io::Write,
+{
+    for (name, value) in variables {
+        writeln!(w, "{}: {}", name, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_run() {
+        let file = std::fs::File::open("file/test_saves/say.sb3").unwrap();
+        let scratch_file = ScratchFile::parse(&file).unwrap();
+        let variables = run(scratch_file, Duration::from_secs(10), Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            variables,
+            vec![("my variable".to_string(), Value::Number(0.0))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_with_edge_triggered_hat() {
+        let file = std::fs::File::open("file/test_saves/say.sb3").unwrap();
+        let mut scratch_file = ScratchFile::parse(&file).unwrap();
+        let hat: scratch_file::Block = serde_json::from_value(serde_json::json!({
+            "opcode": "event_whengreaterthan",
+            "next": null,
+            "parent": null,
+            "inputs": {"VALUE": [1, [4, "1000"]]},
+            "fields": {"WHENGREATERTHANMENU": ["TIMER", null]},
+            "shadow": false,
+            "topLevel": true,
+        }))
+        .unwrap();
+        scratch_file.project.targets[1]
+            .blocks
+            .insert(BlockID::try_from("whengreaterthan_hat_").unwrap(), hat);
+
+        // The run ends without waiting for the timer
+        let result = timeout(
+            Duration::from_secs(5),
+            run(scratch_file, Duration::from_secs(60), Vec::new()),
+        )
+        .await;
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_render_after_frames() {
+        let file = std::fs::File::open("file/test_saves/say.sb3").unwrap();
+        let scratch_file = ScratchFile::parse(&file).unwrap();
+        let render_buffer = render_after_frames(scratch_file, 1).await.unwrap();
+        assert_eq!(render_buffer.dimensions(), (480, 360));
+        assert!(render_buffer
+            .pixels()
+            .any(|pixel| *pixel != image::Rgba([255, 255, 255, 255])));
+    }
+
+    #[test]
+    fn test_output_variables() {
+        {
+            let mut result: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+            output_variables(&mut result, &[]).unwrap();
+            assert!(result.get_ref().is_empty());
+        }
+        {
+            let mut result: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+            output_variables(&mut result, &[("a".to_string(), Value::Number(1.0))]).unwrap();
+            assert_eq!(result.get_ref().as_slice(), b"a: 1\n");
+        }
+    }
+}
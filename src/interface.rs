@@ -1,5 +1,7 @@
 use super::*;
 use crate::app::WINDOW_SIZE;
+use crate::audio::{Mixer, NullMixer, RodioMixer};
+use crate::clock::SystemClock;
 use crate::coordinate::{canvas_const, CanvasCoordinate};
 use crate::vm::VM;
 use conrod_core::image::Id;
@@ -18,6 +20,7 @@ pub struct Interface {
     stop_image: Id,
     vm: VM,
     pause_state: PauseState,
+    turbo: bool,
 }
 
 widget_ids! {
@@ -26,6 +29,7 @@ widget_ids! {
         stop_button,
         pause_continue_button,
         step_button,
+        turbo_button,
     }
 }
 
@@ -43,13 +47,27 @@ impl Interface {
         green_flag_image: Id,
         stop_image: Id,
     ) -> Result<Self> {
-        let vm = VM::new(texture_context, scratch_file).await?;
+        let mixer: Arc<dyn Mixer> = match RodioMixer::new() {
+            Ok(mixer) => Arc::new(mixer),
+            Err(e) => {
+                log::warn!("sounds are muted: {}", e);
+                Arc::new(NullMixer)
+            }
+        };
+        let vm = VM::new(
+            Some(texture_context),
+            scratch_file,
+            mixer,
+            Arc::new(SystemClock::default()),
+        )
+        .await?;
         Ok(Self {
             ids,
             green_flag_image,
             stop_image,
             vm,
             pause_state: PauseState::Paused,
+            turbo: false,
         })
     }
 
@@ -61,7 +79,7 @@ impl Interface {
             .set(self.ids.green_flag_button, ui_cell);
 
         if green_flag_event.was_clicked() {
-            self.vm.continue_().await;
+            self.vm.green_flag().await;
             self.pause_state = PauseState::Running;
         }
 
@@ -98,6 +116,14 @@ impl Interface {
         if step_event.was_clicked() {
             self.vm.step().await;
         }
+
+        let turbo_button_text = if self.turbo { "Turbo off" } else { "Turbo on" };
+        let turbo_event =
+            Interface::button(291.0, turbo_button_text).set(self.ids.turbo_button, ui_cell);
+        if turbo_event.was_clicked() {
+            self.turbo = !self.turbo;
+            self.vm.set_turbo(self.turbo);
+        }
     }
 
     fn button(left: f64, label: &str) -> Button<Flat> {
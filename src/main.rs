@@ -6,17 +6,23 @@
 extern crate conrod_core;
 
 mod app;
+mod audio;
 mod blocks;
 mod broadcaster;
+mod clock;
+mod collision;
 mod coordinate;
 mod error;
 mod fileviewer;
+mod graphic_effects;
+mod headless;
 mod interface;
 mod pen;
 mod runtime;
 mod sprite;
 mod sprite_map;
 mod sprite_runtime;
+mod stage;
 mod thread;
 mod vm;
 
@@ -40,6 +46,21 @@ pub type HashSet<V> = std::collections::HashSet<V, fnv::FnvBuildHasher>;
 struct Options {
     command: Command,
     file_path: String,
+    /// Runs the project without opening a window (run command only)
+    #[clap(long)]
+    headless: bool,
+    /// Seconds until a headless run is stopped
+    #[clap(long, default_value = "60")]
+    timeout: f64,
+    /// Answer to an ask and wait block in a headless run. Repeat for each question
+    #[clap(long = "answer", number_of_values = 1)]
+    answers: Vec<String>,
+    /// Number of frames to run before the stage is saved (screenshot command only)
+    #[clap(long, default_value = "0")]
+    after_frames: u32,
+    /// PNG file to save the stage to (screenshot command only)
+    #[clap(long, default_value = "stage.png")]
+    out: String,
 }
 
 #[derive(strum::EnumString)]
@@ -47,6 +68,8 @@ struct Options {
 enum Command {
     Vm,
     Viewer,
+    Run,
+    Screenshot,
 }
 
 fn main() {
@@ -65,6 +88,18 @@ fn main() {
             let result = match options.command {
                 Command::Vm => app::app(path).await,
                 Command::Viewer => fileviewer::fileviewer(path).await,
+                Command::Run => {
+                    if options.headless {
+                        let run_duration = std::time::Duration::from_secs_f64(options.timeout);
+                        headless::headless(path, run_duration, options.answers.clone()).await
+                    } else {
+                        app::app(path).await
+                    }
+                }
+                Command::Screenshot => {
+                    let out_path = std::path::Path::new(&options.out);
+                    headless::screenshot(path, options.after_frames, out_path).await
+                }
             };
             let exit_code = match result {
                 Ok(_) => 0,
@@ -1,22 +1,30 @@
 use super::*;
+use crate::audio::{Mixer, RecordingMixer};
 use crate::blocks::value::Value;
 use crate::broadcaster::Broadcaster;
-use crate::coordinate::CanvasCoordinate;
+use crate::clock::{Clock, Timer, VirtualClock};
+use crate::coordinate::{canvas_const, CanvasCoordinate, Size};
 use crate::sprite_runtime::SpriteRuntime;
+use crate::stage::Stage;
 use crate::vm::ThreadID;
 use async_lock::RwLockReadGuard;
 use graphics::character::CharacterCache;
 use graphics::types::FontSize;
+use graphics::{ellipse, Context, Graphics, Transformed};
 use graphics::{rectangle, text};
-use graphics::{Context, Transformed};
 use input::{ButtonState, Key};
-use piston_window::{G2d, Glyphs};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tokio::time::{interval, Duration};
 
 #[derive(Debug, Clone, Default)]
 pub struct Runtime {
     pub sprite: Arc<RwLock<SpriteRuntime>>,
     pub global: Arc<Global>,
     thread_id: ThreadID,
+    /// Argument values of each procedure call in the thread. The last frame is the innermost call.
+    arguments: Arc<RwLock<Vec<HashMap<String, Value>>>>,
 }
 
 impl Runtime {
@@ -29,64 +37,206 @@ impl Runtime {
             sprite,
             global,
             thread_id,
+            arguments: Arc::default(),
         }
     }
 
     pub fn thread_id(&self) -> ThreadID {
         self.thread_id
     }
+
+    pub async fn push_arguments(&self, arguments: HashMap<String, Value>) {
+        self.arguments.write().await.push(arguments);
+    }
+
+    pub async fn pop_arguments(&self) {
+        self.arguments.write().await.pop();
+    }
+
+    /// Removes the arguments of every procedure call in the thread.
+    pub async fn clear_arguments(&self) {
+        self.arguments.write().await.clear();
+    }
+
+    /// Returns the value of the argument in the current procedure call.
+    pub async fn argument(&self, name: &str) -> Option<Value> {
+        self.arguments
+            .read()
+            .await
+            .last()
+            .and_then(|arguments| arguments.get(name).cloned())
+    }
+
+    /// Variables are looked up in the sprite's local variables first, then in the stage's
+    /// variables.
+    pub async fn variable(&self, key: &str) -> Result<Value> {
+        let sprite = self.sprite.read().await;
+        if sprite.variables().contains(key).await {
+            sprite.variables().get(key).await
+        } else {
+            self.global.variables.get(key).await
+        }
+    }
+
+    pub async fn set_variable(&self, key: &str, value: Value) {
+        let sprite = self.sprite.read().await;
+        if sprite.variables().contains(key).await {
+            sprite.variables().set(key, value).await
+        } else {
+            self.global.variables.set(key, value).await
+        }
+    }
+
+    pub async fn set_variable_with<F>(&self, key: &str, function: F) -> Result<()>
+    where
+        F: FnOnce(&Value) -> Value,
+    {
+        let sprite = self.sprite.read().await;
+        if sprite.variables().contains(key).await {
+            sprite.variables().set_with(key, function).await
+        } else {
+            self.global.variables.set_with(key, function).await
+        }
+    }
+
+    pub async fn set_variable_monitored(&self, key: &str, monitored: bool) -> Result<()> {
+        let sprite = self.sprite.read().await;
+        if sprite.variables().contains(key).await {
+            sprite.variables().set_monitored(key, monitored).await
+        } else {
+            self.global.variables.set_monitored(key, monitored).await
+        }
+    }
+
+    /// Lists are looked up the same way as variables.
+    pub async fn list<F, R>(&self, key: &str, function: F) -> Result<R>
+    where
+        F: FnOnce(&[Value]) -> R,
+    {
+        let sprite = self.sprite.read().await;
+        if sprite.lists().contains(key).await {
+            sprite.lists().list(key, function).await
+        } else {
+            self.global.lists.list(key, function).await
+        }
+    }
+
+    pub async fn list_mut<F, R>(&self, key: &str, function: F) -> Result<R>
+    where
+        F: FnOnce(&mut Vec<Value>) -> R,
+    {
+        let sprite = self.sprite.read().await;
+        if sprite.lists().contains(key).await {
+            sprite.lists().list_mut(key, function).await
+        } else {
+            self.global.lists.list_mut(key, function).await
+        }
+    }
+
+    pub async fn set_list_monitored(&self, key: &str, monitored: bool) -> Result<()> {
+        let sprite = self.sprite.read().await;
+        if sprite.lists().contains(key).await {
+            sprite.lists().set_monitored(key, monitored).await
+        } else {
+            self.global.lists.set_monitored(key, monitored).await
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Global {
     pub variables: Variables,
+    pub lists: Lists,
     pub broadcaster: Broadcaster,
     pub inputs: Inputs,
+    pub questions: Questions,
+    pub stage: Stage,
+    pub mixer: Arc<dyn Mixer>,
+    pub clock: Arc<dyn Clock>,
+    pub timer: Timer,
+}
+
+impl Default for Global {
+    fn default() -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(VirtualClock::default());
+        Self {
+            variables: Variables::default(),
+            lists: Lists::default(),
+            broadcaster: Broadcaster::default(),
+            inputs: Inputs::default(),
+            questions: Questions::default(),
+            stage: Stage::default(),
+            mixer: Arc::new(RecordingMixer::default()),
+            clock: clock.clone(),
+            timer: Timer::new(clock),
+        }
+    }
 }
 
 impl Global {
     pub fn new(
         scratch_file_variables: &HashMap<String, scratch_file::Variable>,
+        scratch_file_lists: &HashMap<String, scratch_file::List>,
         monitors: &[Monitor],
+        mixer: Arc<dyn Mixer>,
+        clock: Arc<dyn Clock>,
     ) -> Self {
         Self {
             variables: Variables::new(scratch_file_variables, monitors),
+            lists: Lists::new(scratch_file_lists, monitors),
             broadcaster: Broadcaster::default(),
             inputs: Inputs::default(),
+            questions: Questions::default(),
+            stage: Stage::default(),
+            mixer,
+            clock: clock.clone(),
+            timer: Timer::new(clock),
         }
     }
 
-    pub async fn draw(
+    pub async fn draw<G, C>(
         &self,
         context: &Context,
-        graphics: &mut G2d<'_>,
-        character_cache: &mut Glyphs,
-    ) -> Result<()> {
-        for variable in self.variables.variables.read().await.values() {
-            if variable.monitored {
-                Global::draw_monitor(
-                    &context.trans(variable.position.x, variable.position.y),
-                    graphics,
-                    character_cache,
-                    &variable.name,
-                    &variable.value.clone().to_string(),
-                )?;
-            }
+        graphics: &mut G,
+        character_cache: &mut C,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        self.variables
+            .draw_monitors(context, graphics, character_cache, None)
+            .await?;
+
+        self.lists
+            .draw_monitors(context, graphics, character_cache, None)
+            .await?;
+
+        if let Some((question, typed)) = self.questions.prompt().await {
+            Global::draw_prompt(context, graphics, character_cache, &question, &typed)?;
         }
         Ok(())
     }
 
-    fn draw_monitor(
+    fn draw_monitor<G, C>(
         context: &Context,
-        graphics: &mut G2d<'_>,
-        character_cache: &mut Glyphs,
+        graphics: &mut G,
+        character_cache: &mut C,
         variable_name: &str,
         value_str: &str,
-    ) -> Result<()> {
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
         const FONT_SIZE: FontSize = 14;
 
-        let name_width = character_cache.width(FONT_SIZE, variable_name)?;
-        let value_width = character_cache.width(FONT_SIZE, value_str)?;
+        let name_width = character_cache
+            .width(FONT_SIZE, variable_name)
+            .map_err(|_| Error::msg("width calculation error"))?;
+        let value_width = character_cache
+            .width(FONT_SIZE, value_str)
+            .map_err(|_| Error::msg("width calculation error"))?;
 
         let orange_rectangle_width = f64::max(39.0 - value_width, value_width + 4.0);
 
@@ -116,7 +266,8 @@ impl Global {
             &context.draw_state,
             context.transform.trans(7.0, 14.0),
             graphics,
-        )?;
+        )
+        .map_err(|_| Error::msg("text draw error"))?;
 
         let orange_transform = context.transform.trans(name_width + 16.0, 3.0);
         rectangle::Rectangle {
@@ -137,12 +288,237 @@ impl Global {
             round: false,
         }
         .draw(
-            variable_name,
+            value_str,
             character_cache,
             &context.draw_state,
             orange_transform.trans((orange_rectangle_width - value_width) / 2.0, 11.5),
             graphics,
-        )?;
+        )
+        .map_err(|_| Error::msg("text draw error"))?;
+        Ok(())
+    }
+
+    fn draw_list_monitor<G, C>(
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+        list_name: &str,
+        values: &[Value],
+        size: &Size,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        const FONT_SIZE: FontSize = 12;
+        const ROW_HEIGHT: f64 = 20.0;
+        const TEXT_COLOR: graphics::types::Color = [0.34, 0.37, 0.46, 1.0];
+
+        // Scratch uses this size if the monitor was never resized
+        let width = if size.width > 0.0 { size.width } else { 100.0 };
+        let height = if size.height > 0.0 {
+            size.height
+        } else {
+            200.0
+        };
+
+        rectangle::Rectangle {
+            color: [0.9, 0.94, 1.0, 1.0],
+            shape: rectangle::Shape::Round(3.5, 8),
+            border: Some(rectangle::Border {
+                color: [0.77, 0.8, 0.85, 1.0],
+                radius: 1.0,
+            }),
+        }
+        .draw(
+            [0.0, 0.0, width, height],
+            &context.draw_state,
+            context.transform,
+            graphics,
+        );
+
+        let name_width = character_cache
+            .width(FONT_SIZE, list_name)
+            .map_err(|_| Error::msg("width calculation error"))?;
+        text::Text {
+            color: TEXT_COLOR,
+            font_size: FONT_SIZE,
+            round: false,
+        }
+        .draw(
+            list_name,
+            character_cache,
+            &context.draw_state,
+            context.transform.trans((width - name_width) / 2.0, 14.0),
+            graphics,
+        )
+        .map_err(|_| Error::msg("text draw error"))?;
+
+        let index_width = character_cache
+            .width(FONT_SIZE, &values.len().to_string())
+            .map_err(|_| Error::msg("width calculation error"))?;
+
+        // Rows that do not fit between the header and the footer are not drawn
+        let visible_rows = ((height - ROW_HEIGHT * 2.0) / ROW_HEIGHT).max(0.0) as usize;
+        for (i, value) in values.iter().take(visible_rows).enumerate() {
+            let row_transform = context
+                .transform
+                .trans(0.0, ROW_HEIGHT + i as f64 * ROW_HEIGHT);
+
+            text::Text {
+                color: TEXT_COLOR,
+                font_size: FONT_SIZE,
+                round: false,
+            }
+            .draw(
+                &(i + 1).to_string(),
+                character_cache,
+                &context.draw_state,
+                row_transform.trans(4.0, 14.0),
+                graphics,
+            )
+            .map_err(|_| Error::msg("text draw error"))?;
+
+            rectangle::Rectangle {
+                color: [0.99, 0.4, 0.17, 1.0],
+                shape: rectangle::Shape::Round(3.5, 8),
+                border: None,
+            }
+            .draw(
+                [0.0, 0.0, width - index_width - 12.0, ROW_HEIGHT - 2.0],
+                &context.draw_state,
+                row_transform.trans(index_width + 8.0, 0.0),
+                graphics,
+            );
+
+            text::Text {
+                color: [1.0, 1.0, 1.0, 1.0],
+                font_size: FONT_SIZE,
+                round: false,
+            }
+            .draw(
+                &value.to_string(),
+                character_cache,
+                &context.draw_state,
+                row_transform.trans(index_width + 12.0, 14.0),
+                graphics,
+            )
+            .map_err(|_| Error::msg("text draw error"))?;
+        }
+
+        text::Text {
+            color: TEXT_COLOR,
+            font_size: FONT_SIZE,
+            round: false,
+        }
+        .draw(
+            &format!("length {}", values.len()),
+            character_cache,
+            &context.draw_state,
+            context.transform.trans(4.0, height - 6.0),
+            graphics,
+        )
+        .map_err(|_| Error::msg("text draw error"))?;
+        Ok(())
+    }
+
+    /// Draws the prompt of the ask and wait block at the bottom of the stage.
+    fn draw_prompt<G, C>(
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+        question: &str,
+        typed: &str,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        const FONT_SIZE: FontSize = 12;
+        const MARGIN: f64 = 8.0;
+        const INPUT_HEIGHT: f64 = 28.0;
+
+        let question_height = if question.is_empty() { 0.0 } else { 20.0 };
+        let width = canvas_const::X_MAX - MARGIN * 2.0;
+        let height = question_height + INPUT_HEIGHT + MARGIN * 2.0;
+        let transform = context
+            .transform
+            .trans(MARGIN, canvas_const::Y_MAX - MARGIN - height);
+
+        rectangle::Rectangle {
+            color: [1.0, 1.0, 1.0, 1.0],
+            shape: rectangle::Shape::Round(8.0, 8),
+            border: Some(rectangle::Border {
+                color: [0.85, 0.85, 0.85, 1.0],
+                radius: 1.0,
+            }),
+        }
+        .draw(
+            [0.0, 0.0, width, height],
+            &context.draw_state,
+            transform,
+            graphics,
+        );
+
+        if !question.is_empty() {
+            text::Text {
+                color: [0.34, 0.37, 0.46, 1.0],
+                font_size: FONT_SIZE,
+                round: false,
+            }
+            .draw(
+                question,
+                character_cache,
+                &context.draw_state,
+                transform.trans(MARGIN, MARGIN + 12.0),
+                graphics,
+            )
+            .map_err(|_| Error::msg("text draw error"))?;
+        }
+
+        let input_transform = transform.trans(MARGIN, MARGIN + question_height);
+        let input_width = width - MARGIN * 2.0;
+        rectangle::Rectangle {
+            color: [1.0, 1.0, 1.0, 1.0],
+            shape: rectangle::Shape::Round(INPUT_HEIGHT / 2.0, 8),
+            border: Some(rectangle::Border {
+                color: [0.3, 0.6, 1.0, 1.0],
+                radius: 1.0,
+            }),
+        }
+        .draw(
+            [0.0, 0.0, input_width, INPUT_HEIGHT],
+            &context.draw_state,
+            input_transform,
+            graphics,
+        );
+
+        text::Text {
+            color: [0.34, 0.37, 0.46, 1.0],
+            font_size: FONT_SIZE,
+            round: false,
+        }
+        .draw(
+            typed,
+            character_cache,
+            &context.draw_state,
+            input_transform.trans(12.0, 18.0),
+            graphics,
+        )
+        .map_err(|_| Error::msg("text draw error"))?;
+
+        // Submit button
+        ellipse::Ellipse::new([0.3, 0.6, 1.0, 1.0]).draw(
+            [
+                input_width - INPUT_HEIGHT + 2.0,
+                2.0,
+                INPUT_HEIGHT - 4.0,
+                INPUT_HEIGHT - 4.0,
+            ],
+            &context.draw_state,
+            input_transform,
+            graphics,
+        );
         Ok(())
     }
 }
@@ -185,6 +561,48 @@ impl Variables {
         }
     }
 
+    pub async fn contains(&self, key: &str) -> bool {
+        self.variables.read().await.contains_key(key)
+    }
+
+    /// Draws the monitors that are shown. Monitors of variables that are "for this sprite only" are
+    /// labeled with sprite_name.
+    pub async fn draw_monitors<G, C>(
+        &self,
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+        sprite_name: Option<&str>,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        for variable in self.variables.read().await.values() {
+            if variable.monitored {
+                let label = match sprite_name {
+                    Some(sprite_name) => format!("{}: {}", sprite_name, variable.name),
+                    None => variable.name.clone(),
+                };
+                Global::draw_monitor(
+                    &context.trans(variable.position.x, variable.position.y),
+                    graphics,
+                    character_cache,
+                    &label,
+                    &variable.value.clone().to_string(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the variables for a cloned sprite.
+    pub async fn clone_variables(&self) -> Self {
+        Self {
+            variables: RwLock::new(self.variables.read().await.clone()),
+        }
+    }
+
     pub async fn get(&self, key: &str) -> Result<Value> {
         match self.variables.read().await.get(key) {
             Some(v) => Ok(v.value.clone()),
@@ -238,16 +656,178 @@ impl Variables {
         }
     }
 
+    /// Returns (name, value) of all variables sorted by name.
+    pub async fn name_values(&self) -> Vec<(String, Value)> {
+        let mut result: Vec<(String, Value)> = self
+            .variables
+            .read()
+            .await
+            .values()
+            .map(|v| (v.name.clone(), v.value.clone()))
+            .collect();
+        result.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+
     #[cfg(test)]
     pub async fn monitored(&self, key: &str) -> bool {
         self.variables.read().await.get(key).unwrap().monitored
     }
 }
 
+#[derive(Debug, Default)]
+pub struct Lists {
+    lists: RwLock<HashMap<String, List>>,
+}
+
+impl Lists {
+    pub fn new(
+        scratch_file_lists: &HashMap<String, scratch_file::List>,
+        monitors: &[Monitor],
+    ) -> Self {
+        let mut lists: HashMap<String, List> = HashMap::default();
+        for (key, l) in scratch_file_lists {
+            let mut list = List {
+                name: l.name.clone(),
+                values: l.values.iter().cloned().map(Value::from).collect(),
+                monitored: false,
+                position: CanvasCoordinate::default(),
+                size: Size::default(),
+            };
+            if let Some(monitor) = monitors.iter().find(|m| &m.id == key) {
+                list.monitored = monitor.visible;
+                list.position = CanvasCoordinate {
+                    x: monitor.x,
+                    y: monitor.y,
+                };
+                list.size = Size {
+                    width: monitor.width,
+                    height: monitor.height,
+                };
+            }
+            lists.insert(key.clone(), list);
+        }
+
+        Self {
+            lists: RwLock::new(lists),
+        }
+    }
+
+    pub async fn contains(&self, key: &str) -> bool {
+        self.lists.read().await.contains_key(key)
+    }
+
+    /// Draws the monitors that are shown. Monitors of lists that are "for this sprite only" are
+    /// labeled with sprite_name.
+    pub async fn draw_monitors<G, C>(
+        &self,
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+        sprite_name: Option<&str>,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        for list in self.lists.read().await.values() {
+            if list.monitored {
+                let label = match sprite_name {
+                    Some(sprite_name) => format!("{}: {}", sprite_name, list.name),
+                    None => list.name.clone(),
+                };
+                Global::draw_list_monitor(
+                    &context.trans(list.position.x, list.position.y),
+                    graphics,
+                    character_cache,
+                    &label,
+                    &list.values,
+                    &list.size,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the lists for a cloned sprite.
+    pub async fn clone_lists(&self) -> Self {
+        Self {
+            lists: RwLock::new(self.lists.read().await.clone()),
+        }
+    }
+
+    /// Calls function with the values of the list associated with key.
+    pub async fn list<F, R>(&self, key: &str, function: F) -> Result<R>
+    where
+        F: FnOnce(&[Value]) -> R,
+    {
+        match self.lists.read().await.get(key) {
+            Some(list) => Ok(function(&list.values)),
+            None => Err(Error::msg(format!("key does not exist: {}", key))),
+        }
+    }
+
+    /// Calls function with the values of the list associated with key, which it can modify.
+    pub async fn list_mut<F, R>(&self, key: &str, function: F) -> Result<R>
+    where
+        F: FnOnce(&mut Vec<Value>) -> R,
+    {
+        match self.lists.write().await.get_mut(key) {
+            Some(list) => Ok(function(&mut list.values)),
+            None => Err(Error::msg(format!("key does not exist: {}", key))),
+        }
+    }
+
+    pub async fn set(&self, key: &str, values: Vec<Value>) {
+        let mut lists = self.lists.write().await;
+        match lists.get_mut(key) {
+            Some(list) => list.values = values,
+            None => {
+                // This path should not happen normally; used for tests
+                lists.insert(
+                    key.to_string(),
+                    List {
+                        name: key.to_string(),
+                        values,
+                        monitored: false,
+                        position: CanvasCoordinate::default(),
+                        size: Size::default(),
+                    },
+                );
+            }
+        }
+    }
+
+    pub async fn set_monitored(&self, key: &str, monitored: bool) -> Result<()> {
+        match self.lists.write().await.get_mut(key) {
+            Some(list) => {
+                list.monitored = monitored;
+                Ok(())
+            }
+            None => Err(Error::msg(format!("key does not exist: {}", key))),
+        }
+    }
+
+    #[cfg(test)]
+    pub async fn monitored(&self, key: &str) -> bool {
+        self.lists.read().await.get(key).unwrap().monitored
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct List {
+    name: String,
+    values: Vec<Value>,
+    monitored: bool,
+    position: CanvasCoordinate,
+    size: Size,
+}
+
 #[derive(Debug, Default)]
 pub struct Inputs {
     keys: RwLock<HashSet<Key>>,
     mouse_position: RwLock<CanvasCoordinate>,
+    mouse_down: RwLock<bool>,
 }
 
 impl Inputs {
@@ -269,6 +849,129 @@ impl Inputs {
     pub async fn set_mouse_position(&self, mouse_position: CanvasCoordinate) {
         *self.mouse_position.write().await = mouse_position;
     }
+
+    /// Returns true if the left mouse button is held down.
+    pub async fn mouse_down(&self) -> bool {
+        *self.mouse_down.read().await
+    }
+
+    pub async fn set_mouse_down(&self, mouse_down: bool) {
+        *self.mouse_down.write().await = mouse_down;
+    }
+}
+
+/// Questions of the ask and wait block. Questions are shown one at a time in the order that they
+/// were asked.
+#[derive(Debug, Default)]
+pub struct Questions {
+    questions: Mutex<VecDeque<Question>>,
+    next_question_id: AtomicUsize,
+    /// Text typed into the prompt
+    typed: Mutex<String>,
+    answer: RwLock<String>,
+    /// Answers that replace keyboard input in headless mode
+    scripted_answers: RwLock<Option<VecDeque<String>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Question {
+    id: usize,
+    text: String,
+}
+
+/// Removes the question when the ask block stops waiting, which also happens when its thread is
+/// stopped or restarted.
+struct QuestionGuard<'a> {
+    questions: &'a Questions,
+    id: usize,
+}
+
+impl Drop for QuestionGuard<'_> {
+    fn drop(&mut self) {
+        self.questions.remove(self.id);
+    }
+}
+
+impl Questions {
+    /// Shows the question and waits until it is answered. With scripted answers, the next answer is
+    /// taken from the queue instead. The answer is empty if the queue is empty.
+    pub async fn ask(&self, question: String) {
+        if let Some(answers) = self.scripted_answers.write().await.as_mut() {
+            *self.answer.write().await = answers.pop_front().unwrap_or_default();
+            return;
+        }
+
+        let id = self.next_question_id.fetch_add(1, Ordering::Relaxed);
+        self.questions
+            .lock()
+            .unwrap()
+            .push_back(Question { id, text: question });
+        let _guard = QuestionGuard {
+            questions: self,
+            id,
+        };
+
+        let mut interval = interval(Duration::from_millis(10));
+        while self.contains(id) {
+            interval.tick().await;
+        }
+    }
+
+    fn contains(&self, id: usize) -> bool {
+        self.questions.lock().unwrap().iter().any(|q| q.id == id)
+    }
+
+    /// Removes the question without answering it. The typed text is removed if the question was
+    /// shown.
+    fn remove(&self, id: usize) {
+        let mut questions = self.questions.lock().unwrap();
+        if matches!(questions.front(), Some(q) if q.id == id) {
+            self.typed.lock().unwrap().clear();
+        }
+        questions.retain(|q| q.id != id);
+    }
+
+    /// Returns the question that is shown and the text typed so far.
+    pub async fn prompt(&self) -> Option<(String, String)> {
+        let question = self.questions.lock().unwrap().front()?.text.clone();
+        Some((question, self.typed.lock().unwrap().clone()))
+    }
+
+    /// Adds text to the prompt if a question is shown.
+    pub async fn type_text(&self, text: &str) {
+        if !self.questions.lock().unwrap().is_empty() {
+            self.typed
+                .lock()
+                .unwrap()
+                .extend(text.chars().filter(|c| !c.is_control()));
+        }
+    }
+
+    pub async fn backspace(&self) {
+        self.typed.lock().unwrap().pop();
+    }
+
+    /// Answers the shown question with the typed text.
+    pub async fn submit(&self) {
+        if self.questions.lock().unwrap().pop_front().is_some() {
+            let typed = std::mem::take(&mut *self.typed.lock().unwrap());
+            *self.answer.write().await = typed;
+        }
+    }
+
+    pub async fn answer(&self) -> String {
+        self.answer.read().await.clone()
+    }
+
+    pub async fn set_scripted_answers(&self, answers: Vec<String>) {
+        *self.scripted_answers.write().await = Some(answers.into());
+    }
+
+    /// Removes all questions without answering them.
+    pub async fn clear(&self) {
+        self.questions.lock().unwrap().clear();
+        self.typed.lock().unwrap().clear();
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -310,6 +1013,31 @@ mod tests {
             assert_eq!(variables.get("key").await.unwrap(), Value::Number(1.0));
         }
 
+        #[tokio::test]
+        async fn clone_variables() {
+            let variables = Variables::default();
+            variables.set("key", Value::Number(0.0)).await;
+            let cloned = variables.clone_variables().await;
+            cloned.set("key", Value::Number(1.0)).await;
+            assert_eq!(variables.get("key").await.unwrap(), Value::Number(0.0));
+            assert_eq!(cloned.get("key").await.unwrap(), Value::Number(1.0));
+        }
+
+        #[tokio::test]
+        async fn name_values() {
+            let variables = Variables::default();
+            assert!(variables.name_values().await.is_empty());
+            variables.set("b", Value::Number(1.0)).await;
+            variables.set("a", Value::Number(0.0)).await;
+            assert_eq!(
+                variables.name_values().await,
+                vec![
+                    ("a".to_string(), Value::Number(0.0)),
+                    ("b".to_string(), Value::Number(1.0)),
+                ]
+            );
+        }
+
         #[tokio::test]
         async fn set_monitored() {
             let variables = Variables::default();
@@ -335,5 +1063,170 @@ mod tests {
                     .monitored
             );
         }
+
+        #[test]
+        fn new_with_monitor() {
+            let mut scratch_file_variables = HashMap::new();
+            scratch_file_variables.insert(
+                "local".to_string(),
+                scratch_file::Variable {
+                    id: "my variable".to_string(),
+                    ..Default::default()
+                },
+            );
+            let monitor = Monitor {
+                id: "local".to_string(),
+                x: 5.0,
+                y: 10.0,
+                visible: true,
+                ..Default::default()
+            };
+            let variables = Variables::new(&scratch_file_variables, &[monitor]);
+            let variables = variables.variables.try_read().unwrap();
+            let variable = variables.get("local").unwrap();
+            assert!(variable.monitored);
+            assert_eq!(variable.position, CanvasCoordinate { x: 5.0, y: 10.0 });
+        }
+    }
+
+    mod lists {
+        use super::*;
+
+        #[tokio::test]
+        async fn list_mut() {
+            let lists = Lists::default();
+            assert!(lists.list_mut("key", |_| unreachable!()).await.is_err());
+            lists.set("key", vec![Value::Number(0.0)]).await;
+            lists
+                .list_mut("key", |list| list.push(Value::Number(1.0)))
+                .await
+                .unwrap();
+            assert_eq!(
+                lists.list("key", |list| list.to_vec()).await.unwrap(),
+                vec![Value::Number(0.0), Value::Number(1.0)]
+            );
+        }
+
+        #[tokio::test]
+        async fn clone_lists() {
+            let lists = Lists::default();
+            lists.set("key", vec![Value::Number(0.0)]).await;
+            let cloned = lists.clone_lists().await;
+            cloned.list_mut("key", |list| list.clear()).await.unwrap();
+            assert_eq!(lists.list("key", |list| list.len()).await.unwrap(), 1);
+            assert_eq!(cloned.list("key", |list| list.len()).await.unwrap(), 0);
+        }
+
+        #[test]
+        fn new_with_monitor() {
+            let mut scratch_file_lists = HashMap::new();
+            scratch_file_lists.insert(
+                "local".to_string(),
+                scratch_file::List {
+                    name: "my list".to_string(),
+                    values: Vec::new(),
+                },
+            );
+            let monitor = Monitor {
+                id: "local".to_string(),
+                x: 5.0,
+                y: 10.0,
+                width: 100.0,
+                height: 200.0,
+                visible: true,
+                ..Default::default()
+            };
+            let lists = Lists::new(&scratch_file_lists, &[monitor]);
+            let lists = lists.lists.try_read().unwrap();
+            let list = lists.get("local").unwrap();
+            assert!(list.monitored);
+            assert_eq!(list.position, CanvasCoordinate { x: 5.0, y: 10.0 });
+            assert_eq!(
+                list.size,
+                Size {
+                    width: 100.0,
+                    height: 200.0
+                }
+            );
+        }
+    }
+
+    mod questions {
+        use super::*;
+        use futures::future::abortable;
+
+        #[tokio::test]
+        async fn ask() {
+            let questions = Arc::new(Questions::default());
+            questions.type_text("ignored").await;
+            assert!(questions.prompt().await.is_none());
+
+            let ask = spawn({
+                let questions = questions.clone();
+                async move { questions.ask("name?".to_string()).await }
+            });
+            while questions.prompt().await.is_none() {
+                tokio::task::yield_now().await;
+            }
+
+            questions.type_text("ab\rc").await;
+            questions.backspace().await;
+            questions.type_text("d").await;
+            assert_eq!(
+                questions.prompt().await,
+                Some(("name?".to_string(), "abd".to_string()))
+            );
+
+            questions.submit().await;
+            ask.await.unwrap();
+            assert_eq!(questions.answer().await, "abd");
+            assert!(questions.prompt().await.is_none());
+        }
+
+        #[tokio::test]
+        async fn abort_ask() {
+            let questions = Arc::new(Questions::default());
+            let (ask, abort_handle) = abortable({
+                let questions = questions.clone();
+                async move { questions.ask("name?".to_string()).await }
+            });
+            let ask = spawn(ask);
+            while questions.prompt().await.is_none() {
+                tokio::task::yield_now().await;
+            }
+            questions.type_text("a").await;
+
+            abort_handle.abort();
+            assert!(ask.await.unwrap().is_err());
+            assert!(questions.prompt().await.is_none());
+
+            // The next question is not queued behind the aborted one
+            let ask = spawn({
+                let questions = questions.clone();
+                async move { questions.ask("age?".to_string()).await }
+            });
+            while questions.prompt().await.is_none() {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(
+                questions.prompt().await,
+                Some(("age?".to_string(), String::new()))
+            );
+            questions.submit().await;
+            ask.await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn scripted_answers() {
+            let questions = Questions::default();
+            questions
+                .set_scripted_answers(vec!["a".to_string(), "b".to_string()])
+                .await;
+            for expected in &["a", "b", ""] {
+                questions.ask("?".to_string()).await;
+                assert_eq!(questions.answer().await, *expected);
+            }
+            assert!(questions.prompt().await.is_none());
+        }
     }
 }
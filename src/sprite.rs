@@ -1,12 +1,14 @@
 use super::*;
+use crate::audio::Sound;
 use crate::blocks::*;
-use crate::coordinate::SpriteRectangle;
+use crate::collision::CollisionMask;
+use crate::coordinate::{SpriteCoordinate, SpriteRectangle};
 use crate::runtime::{Global, Runtime};
 use crate::sprite_runtime::{Costumes, GraphicsCostumeTexture, SpriteRuntime};
 use crate::thread::{BlockInputs, StepStatus, Thread};
 use crate::vm::ThreadID;
 use graphics::character::CharacterCache;
-use graphics::Context;
+use graphics::{Context, Graphics};
 use std::collections::hash_map::DefaultHasher;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
@@ -14,6 +16,8 @@ use std::hash::{Hash, Hasher};
 #[derive(Debug)]
 pub struct Sprite {
     threads: Vec<RwLock<Thread>>,
+    /// Hat of each thread, which can be read while the thread runs
+    hats: Vec<Option<Hat>>,
     global_runtime: Arc<Global>,
     sprite_runtime: Arc<RwLock<SpriteRuntime>>,
     block_infos: HashMap<BlockID, scratch_file::Block>,
@@ -27,11 +31,12 @@ impl Sprite {
         block_infos: HashMap<BlockID, scratch_file::Block>,
     ) -> Result<Self> {
         let sprite_runtime_ref = Arc::new(RwLock::new(sprite_runtime));
+        let definitions = find_procedure_definitions(&block_infos);
 
-        let threads: Result<Vec<RwLock<Thread>>> = find_hats(&block_infos)
+        let threads: Result<Vec<Thread>> = find_hats(&block_infos)
             .drain(..)
             .enumerate()
-            .map(|(thread_id, hat_id)| -> Result<RwLock<Thread>> {
+            .map(|(thread_id, hat_id)| -> Result<Thread> {
                 let runtime = Runtime::new(
                     sprite_runtime_ref.clone(),
                     global.clone(),
@@ -41,13 +46,19 @@ impl Sprite {
                     },
                 );
 
-                let blocks = block_tree(hat_id, runtime, &block_infos)?;
-                Ok(RwLock::new(Thread::new(hat_id, blocks)))
+                let mut blocks = block_tree(hat_id, runtime.clone(), &block_infos)?;
+                // Every thread can call any custom block of the sprite
+                for definition_id in &definitions {
+                    blocks.extend(block_tree(*definition_id, runtime.clone(), &block_infos)?);
+                }
+                Ok(Thread::new(hat_id, blocks))
             })
             .collect();
+        let threads = threads?;
 
         Ok(Self {
-            threads: threads?,
+            hats: threads.iter().map(Thread::hat).collect(),
+            threads: threads.into_iter().map(RwLock::new).collect(),
             global_runtime: global,
             sprite_runtime: sprite_runtime_ref,
             block_infos,
@@ -58,6 +69,10 @@ impl Sprite {
         self.sprite_runtime.write().await.set_costumes(costumes);
     }
 
+    pub async fn set_sounds(&mut self, sounds: Vec<Sound>) {
+        self.sprite_runtime.write().await.set_sounds(sounds);
+    }
+
     pub fn number_of_threads(&self) -> usize {
         self.threads.len()
     }
@@ -77,6 +92,22 @@ impl Sprite {
         self.threads[thread_id].write().await.step().await
     }
 
+    pub fn hats(&self) -> &[Option<Hat>] {
+        &self.hats
+    }
+
+    pub async fn restart(&self, thread_id: usize) {
+        self.threads[thread_id].write().await.restart().await;
+    }
+
+    pub async fn stop(&self, thread_id: usize) {
+        self.threads[thread_id].write().await.stop().await;
+    }
+
+    pub async fn hat_triggered(&self, thread_id: usize) -> Result<bool> {
+        self.threads[thread_id].write().await.hat_triggered().await
+    }
+
     pub async fn draw<G, C>(
         &self,
         context: &Context,
@@ -93,6 +124,23 @@ impl Sprite {
             .draw(context, graphics, character_cache)
     }
 
+    pub async fn draw_monitors<G, C>(
+        &self,
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        self.sprite_runtime
+            .read()
+            .await
+            .draw_monitors(context, graphics, character_cache)
+            .await
+    }
+
     pub async fn block_inputs(&self) -> Result<Vec<BlockInputs>> {
         let mut result: Vec<BlockInputs> = Vec::with_capacity(self.threads.len());
         for thread in &self.threads {
@@ -102,7 +150,12 @@ impl Sprite {
     }
 
     pub async fn clone_sprite(&self, new_sprite_id: SpriteID) -> Result<Sprite> {
-        let sprite_runtime = self.sprite_runtime.read().await.clone_sprite_runtime();
+        let sprite_runtime = self
+            .sprite_runtime
+            .read()
+            .await
+            .clone_sprite_runtime()
+            .await;
         Sprite::new(
             new_sprite_id,
             sprite_runtime,
@@ -111,9 +164,47 @@ impl Sprite {
         )
     }
 
+    pub async fn take_redraw_requested(&self) -> bool {
+        self.sprite_runtime.write().await.take_redraw_requested()
+    }
+
+    pub async fn take_canvas_changed(&self) -> bool {
+        self.sprite_runtime.write().await.take_canvas_changed()
+    }
+
     pub async fn rectangle(&self) -> SpriteRectangle {
         self.sprite_runtime.read().await.rectangle()
     }
+
+    pub async fn set_center(&self, center: SpriteCoordinate) {
+        self.sprite_runtime.write().await.set_center(center);
+    }
+
+    pub async fn draggable(&self) -> bool {
+        self.sprite_runtime.read().await.draggable()
+    }
+
+    /// Returns None if the sprite is hidden or has no costume.
+    pub async fn collision_mask(&self) -> Result<Option<CollisionMask>> {
+        let sprite_runtime = self.sprite_runtime.read().await;
+        if !sprite_runtime.is_visible() {
+            return Ok(None);
+        }
+        sprite_runtime.collision_mask()
+    }
+
+    pub async fn is_a_clone(&self) -> bool {
+        self.sprite_runtime.read().await.is_a_clone()
+    }
+
+    /// Returns true if the point is inside this sprite and the sprite can be clicked. The stage
+    /// and hidden sprites cannot be clicked.
+    pub async fn contains(&self, point: &SpriteCoordinate) -> bool {
+        let sprite_runtime = self.sprite_runtime.read().await;
+        !sprite_runtime.is_stage()
+            && sprite_runtime.is_visible()
+            && sprite_runtime.rectangle().contains(point)
+    }
 }
 
 fn find_hats(block_infos: &HashMap<BlockID, scratch_file::Block>) -> Vec<BlockID> {
@@ -130,6 +221,14 @@ fn find_hats(block_infos: &HashMap<BlockID, scratch_file::Block>) -> Vec<BlockID
     hats
 }
 
+fn find_procedure_definitions(block_infos: &HashMap<BlockID, scratch_file::Block>) -> Vec<BlockID> {
+    block_infos
+        .iter()
+        .filter(|(_, block)| block.opcode == "procedures_definition" && block.top_level)
+        .map(|(id, _)| *id)
+        .collect()
+}
+
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Default)]
 pub struct SpriteID {
     hash: u64,
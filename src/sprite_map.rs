@@ -1,17 +1,23 @@
 use super::*;
-use crate::blocks::BlockInfo;
+use crate::blocks::{BlockInfo, Hat, HatEvent};
 use crate::broadcaster::LayerChange;
-use crate::coordinate::SpriteRectangle;
+use crate::collision::CollisionMask;
+use crate::coordinate::{canvas_const, CanvasRegion, SpriteCoordinate, SpriteRectangle};
 use crate::runtime::Global;
 use crate::sprite::{Sprite, SpriteID};
+use crate::sprite_runtime::BUFFER_GLYPHS;
+use crate::stage::StageQuery;
 use crate::thread::StepStatus;
 use crate::vm::ThreadID;
 use arrayvec::ArrayVec;
-use graphics::Context;
+use async_trait::async_trait;
+use graphics::character::CharacterCache;
+use graphics::{Context, Graphics, Transformed};
 use graphics_buffer::{BufferGlyphs, RenderBuffer};
+use image::{imageops, GenericImage, RgbaImage};
 use piston_window::{G2d, Glyphs};
 use std::iter::{once, repeat_with};
-use tokio::time::{sleep, Duration};
+use std::ops::{Deref, DerefMut};
 
 /// I needed a map that can to add cloned sprites while other sprites are still running.
 #[derive(Debug)]
@@ -20,6 +26,10 @@ pub struct SpriteMap {
     draw_order: RwLock<DrawOrder>,
     removed_sprites: RwLock<HashSet<SpriteID>>,
     stopped_threads: RwLock<HashSet<ThreadID>>,
+    /// Threads that are restarted from the hat before their next step
+    restarted_threads: RwLock<HashSet<ThreadID>>,
+    /// All sprites drawn for canvas_image(). None if it has to be drawn again.
+    canvas_cache: RwLock<Option<RgbaImage>>,
     global: Arc<Global>,
 }
 
@@ -42,41 +52,66 @@ impl SpriteMap {
             draw_order: RwLock::new(DrawOrder::new(targets)),
             removed_sprites: RwLock::default(),
             stopped_threads: RwLock::default(),
+            restarted_threads: RwLock::default(),
+            canvas_cache: RwLock::default(),
             global,
         }
     }
 
-    pub async fn step(&self, thread_id: ThreadID) -> Result<Option<ThreadID>> {
-        if self.stopped_threads.write().await.remove(&thread_id)
-            || self
-                .removed_sprites
-                .read()
-                .await
-                .contains(&thread_id.sprite_id)
+    /// Runs one block of the thread. Stopped threads and threads of removed sprites are done.
+    pub async fn step(&self, thread_id: ThreadID) -> Result<StepStatus> {
+        let stop = self.stopped_threads.write().await.remove(&thread_id);
+        if self
+            .removed_sprites
+            .read()
+            .await
+            .contains(&thread_id.sprite_id)
         {
-            return Ok(None);
+            return Ok(StepStatus::Done);
         }
 
+        let restart = self.restarted_threads.write().await.remove(&thread_id);
         for group in &self.sprite_groups {
             if let Some(sprite) = group.read().await.get(&thread_id.sprite_id) {
-                let result = sprite
-                    .step(thread_id.thread_id)
-                    .await
-                    .map(|status| match status {
-                        StepStatus::Continue => Some(thread_id),
-                        StepStatus::Done => None,
-                    });
-                // Hacky fix for unresponsive menu screen in Pixel Snake
-                // yield_now() did not work
-                sleep(Duration::from_millis(0)).await;
-                return result;
+                if stop {
+                    sprite.stop(thread_id.thread_id).await;
+                    return Ok(StepStatus::Done);
+                }
+                if restart {
+                    sprite.restart(thread_id.thread_id).await;
+                }
+                return sprite.step(thread_id.thread_id).await;
             }
         }
         Err(Error::msg("thread_id is invalid"))
     }
 
+    /// Returns true if the window has to be drawn again because any sprite changed since the last
+    /// call.
+    pub async fn take_redraw_requested(&self) -> bool {
+        let mut result = false;
+        for group in &self.sprite_groups {
+            for sprite in group.read().await.values() {
+                result |= sprite.take_redraw_requested().await;
+            }
+        }
+        result
+    }
+
+    /// Returns true if any sprite changed since the last call, which makes canvas_cache invalid.
+    async fn take_canvas_changed(&self) -> bool {
+        let mut result = false;
+        for group in &self.sprite_groups {
+            for sprite in group.read().await.values() {
+                result |= sprite.take_canvas_changed().await;
+            }
+        }
+        result
+    }
+
     pub async fn remove(&self, sprite_id: SpriteID) {
         self.removed_sprites.write().await.insert(sprite_id);
+        *self.canvas_cache.write().await = None;
     }
 
     pub async fn draw(
@@ -86,6 +121,8 @@ impl SpriteMap {
         character_cache: &mut Glyphs,
     ) -> Result<()> {
         self.global.draw(context, graphics, character_cache).await?;
+        self.draw_monitors(context, graphics, character_cache)
+            .await?;
 
         let removed_sprites = self.removed_sprites.read().await;
         for id in self.draw_order.read().await.iter() {
@@ -104,16 +141,16 @@ impl SpriteMap {
         Ok(())
     }
 
-    pub async fn draw_to_buffer(
+    async fn draw_to_buffer(
         &self,
         context: &mut Context,
         graphics: &mut RenderBuffer,
         character_cache: &mut BufferGlyphs<'_>,
-        removed_sprite: &SpriteID,
+        removed_sprite: Option<&SpriteID>,
     ) -> Result<()> {
         let removed_sprites = self.removed_sprites.read().await;
         for id in self.draw_order.read().await.iter() {
-            if !removed_sprites.contains(id) && id != removed_sprite {
+            if !removed_sprites.contains(id) && Some(id) != removed_sprite {
                 let mut found = false;
                 for group in &self.sprite_groups {
                     if let Some(sprite) = group.read().await.get(id) {
@@ -128,6 +165,47 @@ impl SpriteMap {
         Ok(())
     }
 
+    /// Draws every sprite with monitors on top, which is what the stage looks like in the window.
+    pub async fn draw_stage_to_buffer(
+        &self,
+        context: &mut Context,
+        graphics: &mut RenderBuffer,
+        character_cache: &mut BufferGlyphs<'_>,
+    ) -> Result<()> {
+        self.draw_to_buffer(context, graphics, character_cache, None)
+            .await?;
+        self.global.draw(context, graphics, character_cache).await?;
+        self.draw_monitors(context, graphics, character_cache).await
+    }
+
+    /// Draws the monitors of the sprites' local variables and lists.
+    async fn draw_monitors<G, C>(
+        &self,
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        let removed_sprites = self.removed_sprites.read().await;
+        for id in self.draw_order.read().await.iter() {
+            if removed_sprites.contains(id) {
+                continue;
+            }
+            for group in &self.sprite_groups {
+                if let Some(sprite) = group.read().await.get(id) {
+                    sprite
+                        .draw_monitors(context, graphics, character_cache)
+                        .await?;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub async fn all_thread_ids(&self) -> Vec<ThreadID> {
         let mut result: Vec<ThreadID> = Vec::new();
         for group in &self.sprite_groups {
@@ -163,6 +241,8 @@ impl SpriteMap {
         let mut draw_order = self.draw_order.write().await;
         let index = draw_order.iter().position(|s| s == &sprite_id).unwrap();
         draw_order.insert(index + 1, new_sprite_id);
+        drop(draw_order);
+        *self.canvas_cache.write().await = None;
         Ok(new_sprite_id)
     }
 
@@ -230,29 +310,222 @@ impl SpriteMap {
         Err(Error::msg(format!("sprite_id not found: {}", sprite_id)))
     }
 
+    pub async fn hat(&self, thread_id: ThreadID) -> Result<Option<Hat>> {
+        for group in &self.sprite_groups {
+            if let Some(sprite) = group.read().await.get(&thread_id.sprite_id) {
+                return Ok(sprite.hats()[thread_id.thread_id].clone());
+            }
+        }
+
+        Err(Error::msg(format!("thread_id not found: {:?}", thread_id)))
+    }
+
+    /// Returns the threads whose hats are started by the event, with their hats. If sprite_id is
+    /// given, only threads of that sprite are returned.
+    pub async fn triggered_threads(
+        &self,
+        event: &HatEvent,
+        sprite_id: Option<SpriteID>,
+    ) -> Vec<(ThreadID, Hat)> {
+        let removed_sprites = self.removed_sprites.read().await;
+        let mut result: Vec<(ThreadID, Hat)> = Vec::new();
+        for group in &self.sprite_groups {
+            for (id, sprite) in group.read().await.iter() {
+                if removed_sprites.contains(id) || matches!(sprite_id, Some(s) if s != *id) {
+                    continue;
+                }
+                for (thread_id, hat) in sprite.hats().iter().enumerate() {
+                    if let Some(hat) = hat {
+                        if hat.matches(event) {
+                            let thread_id = ThreadID {
+                                sprite_id: *id,
+                                thread_id,
+                            };
+                            result.push((thread_id, hat.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// The thread runs from the hat at its next step.
+    pub async fn restart(&self, thread_id: ThreadID) {
+        self.stopped_threads.write().await.remove(&thread_id);
+        self.restarted_threads.write().await.insert(thread_id);
+    }
+
+    /// Removes every clone. The original sprites stay.
+    pub async fn remove_clones(&self) {
+        {
+            let mut removed_sprites = self.removed_sprites.write().await;
+            for group in &self.sprite_groups {
+                for (id, sprite) in group.read().await.iter() {
+                    if sprite.is_a_clone().await {
+                        removed_sprites.insert(*id);
+                    }
+                }
+            }
+        }
+        *self.canvas_cache.write().await = None;
+    }
+
+    /// Evaluates the hat of an edge-triggered thread and returns true if the thread should run.
+    /// Returns None if the sprite was removed.
+    pub async fn hat_triggered(&self, thread_id: ThreadID) -> Result<Option<bool>> {
+        if self
+            .removed_sprites
+            .read()
+            .await
+            .contains(&thread_id.sprite_id)
+        {
+            return Ok(None);
+        }
+
+        for group in &self.sprite_groups {
+            if let Some(sprite) = group.read().await.get(&thread_id.sprite_id) {
+                let triggered = sprite.hat_triggered(thread_id.thread_id).await?;
+                if triggered {
+                    // A stop before the hat was triggered does not apply to the new run
+                    self.stopped_threads.write().await.remove(&thread_id);
+                }
+                return Ok(Some(triggered));
+            }
+        }
+
+        Err(Error::msg(format!("thread_id not found: {:?}", thread_id)))
+    }
+
+    /// Returns the front most sprite that contains the point.
+    pub async fn sprite_at(&self, point: &SpriteCoordinate) -> Option<SpriteID> {
+        let removed_sprites = self.removed_sprites.read().await;
+        for id in self.draw_order.read().await.iter().rev() {
+            if removed_sprites.contains(id) {
+                continue;
+            }
+            for group in &self.sprite_groups {
+                if let Some(sprite) = group.read().await.get(id) {
+                    if sprite.contains(point).await {
+                        return Some(*id);
+                    }
+                    break;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns true if the sprite can be dragged with the mouse.
+    pub async fn draggable(&self, id: SpriteID) -> Result<bool> {
+        for group in &self.sprite_groups {
+            if let Some(sprite) = group.read().await.get(&id) {
+                return Ok(sprite.draggable().await);
+            }
+        }
+
+        Err(Error::msg(format!("id not found: {}", id)))
+    }
+
+    pub async fn set_center(&self, id: SpriteID, center: SpriteCoordinate) -> Result<()> {
+        for group in &self.sprite_groups {
+            if let Some(sprite) = group.read().await.get(&id) {
+                sprite.set_center(center).await;
+                return Ok(());
+            }
+        }
+
+        Err(Error::msg(format!("id not found: {}", id)))
+    }
+
     pub async fn stop(&self, thread_id: ThreadID) {
+        self.restarted_threads.write().await.remove(&thread_id);
         self.stopped_threads.write().await.insert(thread_id);
     }
 
     pub async fn change_layer(&self, id: SpriteID, change: LayerChange) -> Result<()> {
-        self.draw_order.write().await.change_layer(id, change)
+        self.draw_order.write().await.change_layer(id, change)?;
+        *self.canvas_cache.write().await = None;
+        Ok(())
     }
+}
 
-    pub async fn sprite_rectangle(&self, id: &SpriteID) -> Result<SpriteRectangle> {
+#[async_trait]
+impl StageQuery for SpriteMap {
+    async fn sprite_rectangle(&self, id: SpriteID) -> Result<SpriteRectangle> {
         for group in &self.sprite_groups {
-            if let Some(sprite) = group.read().await.get(id) {
+            if let Some(sprite) = group.read().await.get(&id) {
                 return Ok(sprite.rectangle().await);
             }
         }
 
         Err(Error::msg(format!("id not found: {}", id)))
     }
+
+    async fn collision_mask(&self, id: SpriteID) -> Result<Option<CollisionMask>> {
+        if self.removed_sprites.read().await.contains(&id) {
+            return Ok(None);
+        }
+        for group in &self.sprite_groups {
+            if let Some(sprite) = group.read().await.get(&id) {
+                return sprite.collision_mask().await;
+            }
+        }
+
+        Err(Error::msg(format!("id not found: {}", id)))
+    }
+
+    async fn canvas_image(&self, without: SpriteID, region: CanvasRegion) -> Result<RgbaImage> {
+        let mut buffer_glyphs = BUFFER_GLYPHS.write().await;
+        let mut canvas_cache = self.canvas_cache.write().await;
+        if self.take_canvas_changed().await {
+            *canvas_cache = None;
+        }
+        let cached = match canvas_cache.take() {
+            Some(image) => image,
+            None => {
+                let mut render_buffer =
+                    RenderBuffer::new(canvas_const::X_MAX as u32, canvas_const::Y_MAX as u32);
+                self.draw_to_buffer(
+                    &mut Context::new(),
+                    &mut render_buffer,
+                    buffer_glyphs.deref_mut(),
+                    None,
+                )
+                .await?;
+                render_buffer.deref().clone()
+            }
+        };
+        let mut canvas_image = imageops::crop_imm(
+            &cached,
+            region.left,
+            region.top,
+            region.width(),
+            region.height(),
+        )
+        .to_image();
+        *canvas_cache = Some(cached);
+
+        // The cached image includes the sprite, so its region is drawn again without it
+        let mut region_buffer = RenderBuffer::new(region.width(), region.height());
+        self.draw_to_buffer(
+            &mut Context::new().trans(-(region.left as f64), -(region.top as f64)),
+            &mut region_buffer,
+            buffer_glyphs.deref_mut(),
+            Some(&without),
+        )
+        .await?;
+        canvas_image.copy_from(region_buffer.deref(), 0, 0)?;
+        Ok(canvas_image)
+    }
 }
 
 #[derive(Debug)]
 struct DrawOrder {
     /// Lowest index = back, highest index = front
     ids: Vec<SpriteID>,
+    /// The stage is always at the back
+    stage: Option<SpriteID>,
 }
 
 impl DrawOrder {
@@ -266,6 +539,10 @@ impl DrawOrder {
 
         Self {
             ids: id_layer_order.iter().map(|i| i.0).collect(),
+            stage: targets
+                .iter()
+                .find(|t| t.is_stage)
+                .map(|t| SpriteID::from_sprite_name(&t.name)),
         }
     }
 
@@ -274,15 +551,26 @@ impl DrawOrder {
     }
 
     fn change_layer(&mut self, id: SpriteID, change: LayerChange) -> Result<()> {
-        match self.ids.iter().position(|sprite_id| sprite_id == &id) {
-            Some(index) => self.ids.remove(index),
+        if self.stage == Some(id) {
+            return Ok(());
+        }
+
+        let index = match self.ids.iter().position(|sprite_id| sprite_id == &id) {
+            Some(index) => index,
             None => return Err(Error::msg(format!("id not found: {}", id))),
         };
-
-        match change {
-            LayerChange::Front => self.ids.push(id),
-            LayerChange::Back => self.ids.insert(0, id),
-        }
+        self.ids.remove(index);
+
+        let back = if self.stage.is_some() { 1 } else { 0 };
+        let new_index = match change {
+            LayerChange::Front => self.ids.len(),
+            LayerChange::Back => back,
+            LayerChange::Forward(n) => (index as i64)
+                .saturating_add(n)
+                .max(back as i64)
+                .min(self.ids.len() as i64) as usize,
+        };
+        self.ids.insert(new_index, id);
         Ok(())
     }
 
@@ -290,3 +578,29 @@ impl DrawOrder {
         self.ids.insert(index, id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    #[case("b", LayerChange::Front, &["stage", "a", "c", "b"])]
+    #[case("b", LayerChange::Back, &["stage", "b", "a", "c"])]
+    #[case("a", LayerChange::Forward(1), &["stage", "b", "a", "c"])]
+    #[case("a", LayerChange::Forward(10), &["stage", "b", "c", "a"])]
+    #[case("c", LayerChange::Forward(-1), &["stage", "a", "c", "b"])]
+    #[case("c", LayerChange::Forward(-10), &["stage", "c", "a", "b"])]
+    #[case("stage", LayerChange::Front, &["stage", "a", "b", "c"])]
+    fn change_layer(#[case] name: &str, #[case] change: LayerChange, #[case] expected: &[&str]) {
+        let id = |name: &str| SpriteID::from_sprite_name(name);
+        let mut draw_order = DrawOrder {
+            ids: ["stage", "a", "b", "c"].iter().map(|n| id(n)).collect(),
+            stage: Some(id("stage")),
+        };
+        draw_order.change_layer(id(name), change).unwrap();
+        assert_eq!(
+            draw_order.ids,
+            expected.iter().map(|n| id(n)).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,6 +1,12 @@
 use super::*;
-use crate::coordinate::{CanvasCoordinate, Scale, Size, SpriteCoordinate, SpriteRectangle};
+use crate::audio::Sound;
+use crate::collision::{CollisionMask, CostumeMask, MaskTransform};
+use crate::coordinate::{
+    canvas_const, CanvasCoordinate, Scale, Size, SpriteCoordinate, SpriteRectangle,
+};
+use crate::graphic_effects::{GraphicEffect, GraphicEffects};
 use crate::pen::Pen;
+use crate::runtime::{Lists, Variables};
 use flo_curves::{bezier, BezierCurve, Coord2};
 use gfx_device_gl::Resources;
 use gfx_graphics::{CreateTexture, Format};
@@ -8,59 +14,121 @@ use gfx_texture::{Texture, TextureSettings};
 use graphics::character::CharacterCache;
 use graphics::types::FontSize;
 use graphics::{line, CircleArc, Context, Graphics, Transformed};
-use graphics_buffer::{BufferGlyphs, RenderBuffer};
+use graphics_buffer::{buffer_glyphs_from_path, BufferGlyphs, RenderBuffer};
 use image::codecs::png::PngDecoder;
 use image::{DynamicImage, ImageBuffer, ImageDecoder, RgbaImage};
 use piston_window::{G2d, G2dTextureContext, Glyphs};
 use std::f64::consts::TAU;
 use std::fs::File;
 use std::io::{Cursor, Read};
+use std::str::FromStr;
+use std::sync::Mutex;
+use strum::EnumString;
+
+lazy_static::lazy_static! {
+    /// Font cache for drawing to a RenderBuffer. It is shared because loading the font is slow.
+    pub static ref BUFFER_GLYPHS: RwLock<BufferGlyphs<'static>>
+        = RwLock::new(buffer_glyphs_from_path("assets/Roboto-Regular.ttf").unwrap());
+}
 
 #[derive(Debug, Default)]
 pub struct SpriteRuntime {
     sprite_name: String,
+    is_stage: bool,
     is_a_clone: bool,
     position: SpriteCoordinate,
     scale: Scale,
     direction: f64,
+    rotation_style: RotationStyle,
     costumes: Costumes,
-    /// 0.0 = transparent, 1.0 = opaque
-    costume_transparency: f64,
+    sounds: Vec<Sound>,
+    graphic_effects: GraphicEffects,
+    /// Current costume with graphic effects applied
+    effect_textures: Mutex<EffectTextures>,
+    /// Collision mask of each costume for the last transform it was used with
+    costume_masks: Mutex<HashMap<usize, Arc<CostumeMask>>>,
     text: Text,
     pen: Pen,
     hide: HideStatus,
+    /// true if the sprite can be dragged with the mouse in the player
+    draggable: bool,
+    /// Variables that are "for this sprite only"
+    variables: Variables,
+    lists: Lists,
+    /// true if something that is drawn has changed
+    redraw_requested: bool,
+    /// Same as redraw_requested, but is taken by the cached canvas of SpriteMap
+    canvas_changed: bool,
 }
 
 impl SpriteRuntime {
-    pub fn new(target: &Target) -> Self {
+    /// The monitors of the sprite's local variables and lists are taken from monitors.
+    pub fn new(target: &Target, monitors: &[Monitor]) -> Self {
         let scale = if target.is_stage {
             1.0
         } else {
-            target.size / 100.0
+            target.size.unwrap_or(100.0) / 100.0
         };
         Self {
             sprite_name: target.name.clone(),
+            is_stage: target.is_stage,
             position: SpriteCoordinate {
-                x: target.x,
-                y: target.y,
+                x: target.x.unwrap_or_default(),
+                y: target.y.unwrap_or_default(),
             },
             scale: Scale { x: scale, y: scale },
-            direction: 90.0,
+            direction: target.direction.unwrap_or(90.0),
+            rotation_style: target
+                .rotation_style
+                .as_deref()
+                .and_then(|s| RotationStyle::from_str(s).ok())
+                .unwrap_or_default(),
             costumes: Costumes::default(),
-            costume_transparency: 1.0,
+            sounds: Vec::new(),
+            graphic_effects: GraphicEffects::default(),
+            effect_textures: Mutex::default(),
+            costume_masks: Mutex::default(),
             text: Text::default(),
             pen: Pen::default(),
             is_a_clone: false,
-            hide: if target.is_stage || target.visible {
+            hide: if target.is_stage || target.visible.unwrap_or(true) {
                 HideStatus::Show
             } else {
                 HideStatus::Hide
             },
+            draggable: target
+                .extra
+                .get("draggable")
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false),
+            // The stage's variables are global
+            variables: if target.is_stage {
+                Variables::default()
+            } else {
+                Variables::new(&target.variables, monitors)
+            },
+            lists: if target.is_stage {
+                Lists::default()
+            } else {
+                Lists::new(&target.lists, monitors)
+            },
+            redraw_requested: true,
+            canvas_changed: true,
         }
     }
 
+    pub fn set_sounds(&mut self, sounds: Vec<Sound>) {
+        self.sounds = sounds;
+    }
+
+    pub fn sounds(&self) -> &[Sound] {
+        &self.sounds
+    }
+
     pub fn set_costumes(&mut self, costumes: Costumes) {
         self.costumes = costumes;
+        self.effect_textures = Mutex::default();
+        self.costume_masks = Mutex::default();
     }
 
     pub fn draw<G, C>(
@@ -80,15 +148,7 @@ impl SpriteRuntime {
         self.pen.draw(context, graphics);
 
         if let Some(c) = self.costumes.current_costume() {
-            SpriteRuntime::draw_costume(
-                context,
-                graphics,
-                c,
-                &self.position.into(),
-                &self.scale,
-                self.costume_transparency,
-                self.direction,
-            );
+            self.draw_costume(context, graphics, character_cache, c)?;
         }
 
         if let Some(text) = &self.text.text {
@@ -108,25 +168,51 @@ impl SpriteRuntime {
                 ),
                 ..*context
             };
-            SpriteRuntime::draw_text_bubble(text, &c, graphics, character_cache)?;
+            SpriteRuntime::draw_text_bubble(text, self.text.bubble, &c, graphics, character_cache)?;
         }
         Ok(())
     }
 
     fn draw_costume<G, C>(
+        &self,
         context: &Context,
         graphics: &mut G,
+        character_cache: &mut C,
         costume: &Costume,
-        position: &CanvasCoordinate,
-        scale: &Scale,
-        alpha: f64,
-        rotation: f64,
-    ) where
+    ) -> Result<()>
+    where
         G: GraphicsCostumeTexture<C>,
         C: CharacterCache,
     {
+        let mut effect_textures = self
+            .effect_textures
+            .lock()
+            .map_err(|_| Error::msg("lock poisoned"))?;
+        let texture = if self.graphic_effects.changes_pixels() {
+            let key = (self.costumes.current_costume, self.graphic_effects);
+            if effect_textures.key != Some(key) {
+                *effect_textures = EffectTextures {
+                    key: Some(key),
+                    image: self
+                        .graphic_effects
+                        .apply(&costume.image, costume.image_size),
+                    ..EffectTextures::default()
+                };
+            }
+            G::get_effect_texture(&mut effect_textures, character_cache)?
+        } else {
+            match G::get_costume_texture(costume) {
+                Some(t) => t,
+                None => return Ok(()),
+            }
+        };
+
+        let position: CanvasCoordinate = self.position.into();
+        let scale = &self.scale;
+        let (rotation, flip) = self.rotation_and_flip();
+
         graphics::Image {
-            color: Some([1.0, 1.0, 1.0, alpha as f32]),
+            color: Some([1.0, 1.0, 1.0, self.graphic_effects.alpha() as f32]),
             source_rectangle: None,
             rectangle: Some([
                 0.0,
@@ -136,22 +222,71 @@ impl SpriteRuntime {
             ]),
         }
         .draw(
-            G::get_costume_texture(costume),
+            texture,
             &context.draw_state,
             context
                 .transform
                 .trans(position.x, position.y)
-                .rot_deg(rotation - 90.0)
+                .rot_deg(rotation)
+                .scale(flip, 1.0)
                 .trans(
                     -costume.center.x * costume.scale * scale.x,
                     -costume.center.y * costume.scale * scale.y,
                 ),
             graphics,
         );
+        Ok(())
+    }
+
+    /// Returns the clockwise rotation in degrees and -1.0 if the costume is mirrored.
+    fn rotation_and_flip(&self) -> (f64, f64) {
+        match self.rotation_style {
+            RotationStyle::AllAround => (self.direction - 90.0, 1.0),
+            RotationStyle::LeftRight if self.direction < 0.0 => (0.0, -1.0),
+            RotationStyle::LeftRight | RotationStyle::DontRotate => (0.0, 1.0),
+        }
+    }
+
+    /// Opaque pixels of the current costume as drawn on the stage. Returns None if there is no
+    /// costume.
+    pub fn collision_mask(&self) -> Result<Option<CollisionMask>> {
+        let costume = match self.costumes.current_costume() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        let (rotation, flip) = self.rotation_and_flip();
+        let transform = MaskTransform {
+            rotation,
+            flip,
+            scale: self.scale,
+        };
+
+        let mut costume_masks = self
+            .costume_masks
+            .lock()
+            .map_err(|_| Error::msg("lock poisoned"))?;
+        let mask = match costume_masks.get(&self.costumes.current_costume) {
+            Some(mask) if mask.transform() == transform => mask.clone(),
+            _ => {
+                let mask = Arc::new(CostumeMask::new(
+                    &costume.image,
+                    costume.image_size,
+                    SpriteCoordinate {
+                        x: costume.center.x * costume.scale,
+                        y: costume.center.y * costume.scale,
+                    },
+                    transform,
+                ));
+                costume_masks.insert(self.costumes.current_costume, mask.clone());
+                mask
+            }
+        };
+        Ok(Some(CollisionMask::new(mask, self.position.into())))
     }
 
     fn draw_text_bubble<G, C>(
         text: &str,
+        bubble: Bubble,
         context: &Context,
         graphics: &mut G,
         character_cache: &mut C,
@@ -234,30 +369,55 @@ impl SpriteRuntime {
         line(0.0, RADIUS, 0.0, HEIGHT - RADIUS, graphics);
         arc(DOWN, LEFT, 0.0, HEIGHT - RADIUS * 2.0, graphics);
 
-        curve(
-            bezier::Curve {
-                start_point: Coord2(RADIUS, HEIGHT),
-                end_point: Coord2(-3.3 + RADIUS, 9.7 + HEIGHT),
-                control_points: (
-                    Coord2(RADIUS - 2.4, 4.0 + HEIGHT),
-                    Coord2(-4.6 + RADIUS, 8.3 + HEIGHT),
-                ),
-            },
-            graphics,
-        );
-        curve(
-            bezier::Curve {
-                start_point: Coord2(-3.3 + RADIUS, 9.7 + HEIGHT),
-                end_point: Coord2(16.0 + RADIUS, HEIGHT),
-                control_points: (
-                    Coord2(-0.6 + RADIUS, 11.0 + HEIGHT),
-                    Coord2(4.5 + RADIUS, 11.1 + HEIGHT),
-                ),
-            },
-            graphics,
-        );
-
-        line(16.0 + RADIUS, HEIGHT, width - RADIUS, HEIGHT, graphics);
+        match bubble {
+            Bubble::Say => {
+                curve(
+                    bezier::Curve {
+                        start_point: Coord2(RADIUS, HEIGHT),
+                        end_point: Coord2(-3.3 + RADIUS, 9.7 + HEIGHT),
+                        control_points: (
+                            Coord2(RADIUS - 2.4, 4.0 + HEIGHT),
+                            Coord2(-4.6 + RADIUS, 8.3 + HEIGHT),
+                        ),
+                    },
+                    graphics,
+                );
+                curve(
+                    bezier::Curve {
+                        start_point: Coord2(-3.3 + RADIUS, 9.7 + HEIGHT),
+                        end_point: Coord2(16.0 + RADIUS, HEIGHT),
+                        control_points: (
+                            Coord2(-0.6 + RADIUS, 11.0 + HEIGHT),
+                            Coord2(4.5 + RADIUS, 11.1 + HEIGHT),
+                        ),
+                    },
+                    graphics,
+                );
+                line(16.0 + RADIUS, HEIGHT, width - RADIUS, HEIGHT, graphics);
+            }
+            Bubble::Think => {
+                line(RADIUS, HEIGHT, width - RADIUS, HEIGHT, graphics);
+                // Trail of shrinking circles toward the sprite
+                for &(x, y, radius) in &[
+                    (RADIUS, 6.0 + HEIGHT, 4.0),
+                    (RADIUS - 6.0, 13.0 + HEIGHT, 2.5),
+                ] {
+                    CircleArc {
+                        color: COLOR,
+                        radius: LINE_THICKNESS,
+                        start: 0.0,
+                        end: TAU,
+                        resolution: 16,
+                    }
+                    .draw(
+                        [x - radius, y - radius, radius * 2.0, radius * 2.0],
+                        &context.draw_state,
+                        context.transform,
+                        graphics,
+                    );
+                }
+            }
+        }
 
         graphics::text::Text {
             color: [0.34, 0.37, 0.46, 1.0],
@@ -275,21 +435,58 @@ impl SpriteRuntime {
     }
 
     pub fn costumes(&mut self) -> &mut Costumes {
+        self.request_redraw();
         &mut self.costumes
     }
 
+    /// Starts at 1 like in Scratch.
+    pub fn costume_number(&self) -> usize {
+        self.costumes.current_costume + 1
+    }
+
+    pub fn costume_name(&self) -> Option<&str> {
+        self.costumes.current_costume().map(|c| c.name.as_str())
+    }
+
     pub fn say(&mut self, text: Text) {
+        self.request_redraw();
         self.text.replace(text);
     }
 
     pub fn pen(&mut self) -> &mut Pen {
+        self.request_redraw();
         &mut self.pen
     }
 
+    /// Returns true if the window has to be drawn again because the sprite changed since the last
+    /// call.
+    pub fn take_redraw_requested(&mut self) -> bool {
+        std::mem::replace(&mut self.redraw_requested, false)
+    }
+
+    /// Returns true if the sprite changed since the last call, which makes the canvas cached for
+    /// "touching color" blocks invalid.
+    pub fn take_canvas_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.canvas_changed, false)
+    }
+
+    fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+        self.canvas_changed = true;
+    }
+
     pub fn is_a_clone(&self) -> bool {
         self.is_a_clone
     }
 
+    pub fn is_stage(&self) -> bool {
+        self.is_stage
+    }
+
+    pub fn is_visible(&self) -> bool {
+        matches!(self.hide, HideStatus::Show)
+    }
+
     pub fn rectangle(&self) -> SpriteRectangle {
         let size = if let Some(c) = self.costumes.current_costume() {
             c.image_size.multiply(&self.scale)
@@ -309,46 +506,183 @@ impl SpriteRuntime {
         self.position
     }
 
+    /// Moves the sprite. Part of the sprite always stays on the stage.
     pub fn set_center(&mut self, center: SpriteCoordinate) {
+        let center = SpriteRectangle {
+            center,
+            size: self.rectangle().size,
+        }
+        .fenced_center();
+        self.request_redraw();
         self.position = center;
         self.pen().set_position(&center);
     }
 
-    pub fn set_scale(&mut self, scale: Scale) {
-        self.scale = scale;
+    /// Size in percent of the costume size.
+    pub fn size(&self) -> f64 {
+        self.scale.x * 100.0
+    }
+
+    /// Sets the size in percent. Like in Scratch, the costume can't become smaller than 5 pixels
+    /// or larger than 1.5 times the stage.
+    pub fn set_size(&mut self, size: f64) {
+        let scale = match self.costumes.current_costume() {
+            Some(costume) => {
+                let Size { width, height } = costume.image_size;
+                let min = f64::min(1.0, f64::max(5.0 / width, 5.0 / height));
+                let max = f64::min(
+                    1.5 * canvas_const::X_MAX / width,
+                    1.5 * canvas_const::Y_MAX / height,
+                );
+                (size / 100.0).max(min).min(max)
+            }
+            None => size / 100.0,
+        };
+        self.request_redraw();
+        self.scale = Scale { x: scale, y: scale };
     }
 
     pub fn set_hide(&mut self, hide: HideStatus) {
+        self.request_redraw();
         self.hide = hide;
     }
 
-    pub fn transparency(&self) -> f64 {
-        self.costume_transparency
+    pub fn draggable(&self) -> bool {
+        self.draggable
+    }
+
+    pub fn set_draggable(&mut self, draggable: bool) {
+        self.draggable = draggable;
     }
 
-    /// 0.0 = transparent, 1.0 = opaque
-    pub fn set_transparency(&mut self, transparency: f64) {
-        self.costume_transparency = transparency;
+    pub fn graphic_effects(&self) -> &GraphicEffects {
+        &self.graphic_effects
     }
 
-    pub fn clone_sprite_runtime(&self) -> SpriteRuntime {
+    pub fn set_graphic_effect(&mut self, effect: GraphicEffect, value: f64) {
+        self.request_redraw();
+        self.graphic_effects.set(effect, value);
+    }
+
+    pub fn clear_graphic_effects(&mut self) {
+        self.request_redraw();
+        self.graphic_effects = GraphicEffects::default();
+    }
+
+    pub async fn clone_sprite_runtime(&self) -> SpriteRuntime {
         SpriteRuntime {
             sprite_name: self.sprite_name.clone() + "-clone",
             is_a_clone: true,
             costumes: self.costumes.clone(),
+            sounds: self.sounds.clone(),
             text: Text::default(),
             pen: Pen::default(),
+            effect_textures: Mutex::default(),
+            costume_masks: Mutex::default(),
+            variables: self.variables.clone_variables().await,
+            lists: self.lists.clone_lists().await,
+            redraw_requested: true,
+            canvas_changed: true,
             ..*self
         }
     }
 
+    pub fn variables(&self) -> &Variables {
+        &self.variables
+    }
+
+    /// Draws the monitors of the sprite's local variables and lists. Clones do not show monitors.
+    pub async fn draw_monitors<G, C>(
+        &self,
+        context: &Context,
+        graphics: &mut G,
+        character_cache: &mut C,
+    ) -> Result<()>
+    where
+        G: Graphics<Texture = <C as CharacterCache>::Texture>,
+        C: CharacterCache,
+    {
+        if self.is_a_clone {
+            return Ok(());
+        }
+        self.variables
+            .draw_monitors(context, graphics, character_cache, Some(&self.sprite_name))
+            .await?;
+        self.lists
+            .draw_monitors(context, graphics, character_cache, Some(&self.sprite_name))
+            .await
+    }
+
+    pub fn lists(&self) -> &Lists {
+        &self.lists
+    }
+
     /// 0 = Up
     pub fn direction(&self) -> f64 {
         self.direction
     }
 
+    /// The direction is wrapped to the range -179 to 180.
     pub fn set_direction(&mut self, direction: f64) {
-        self.direction = direction;
+        if !direction.is_finite() {
+            return;
+        }
+        self.request_redraw();
+        self.direction = direction - ((direction + 179.0) / 360.0).floor() * 360.0;
+    }
+
+    pub fn rotation_style(&self) -> RotationStyle {
+        self.rotation_style
+    }
+
+    pub fn set_rotation_style(&mut self, rotation_style: RotationStyle) {
+        self.request_redraw();
+        self.rotation_style = rotation_style;
+    }
+
+    /// Points the sprite away from the nearest edge if it touches the edge and moves it back
+    /// onto the stage.
+    pub fn if_on_edge_bounce(&mut self) {
+        let rectangle = self.rectangle();
+        let distances = rectangle.distances_to_edges();
+        let mut nearest_edge = 0;
+        for (edge, distance) in distances.iter().enumerate() {
+            if *distance < distances[nearest_edge] {
+                nearest_edge = edge;
+            }
+        }
+        if distances[nearest_edge] > 0.0 {
+            return;
+        }
+
+        // Screen coordinates where y points down
+        let radians = (90.0 - self.direction).to_radians();
+        let mut dx = radians.cos();
+        let mut dy = -radians.sin();
+        match nearest_edge {
+            0 => dx = f64::max(0.2, dx.abs()),
+            1 => dy = f64::max(0.2, dy.abs()),
+            2 => dx = -f64::max(0.2, dx.abs()),
+            _ => dy = -f64::max(0.2, dy.abs()),
+        }
+        self.set_direction(dy.atan2(dx).to_degrees() + 90.0);
+        self.set_center(rectangle.center_inside_stage());
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, EnumString, strum::Display)]
+pub enum RotationStyle {
+    #[strum(serialize = "all around")]
+    AllAround,
+    #[strum(serialize = "left-right")]
+    LeftRight,
+    #[strum(serialize = "don't rotate")]
+    DontRotate,
+}
+
+impl Default for RotationStyle {
+    fn default() -> Self {
+        RotationStyle::AllAround
     }
 }
 
@@ -357,19 +691,76 @@ pub trait GraphicsCostumeTexture<C>: Graphics<Texture = <C as CharacterCache>::T
 where
     C: CharacterCache,
 {
-    fn get_costume_texture(costume: &Costume) -> &Self::Texture;
+    /// Returns None if the costume does not have a texture of this type.
+    fn get_costume_texture(costume: &Costume) -> Option<&Self::Texture>;
+
+    /// Creates the texture of the effect image on first use.
+    fn get_effect_texture<'a>(
+        effect_textures: &'a mut EffectTextures,
+        character_cache: &mut C,
+    ) -> Result<&'a Self::Texture>;
 }
 
 impl GraphicsCostumeTexture<Glyphs> for G2d<'_> {
-    fn get_costume_texture(costume: &Costume) -> &Self::Texture {
-        &costume.gfx_texture
+    fn get_costume_texture(costume: &Costume) -> Option<&Self::Texture> {
+        costume.gfx_texture.as_ref()
+    }
+
+    fn get_effect_texture<'a>(
+        effect_textures: &'a mut EffectTextures,
+        character_cache: &mut Glyphs,
+    ) -> Result<&'a Self::Texture> {
+        let texture = match effect_textures.gfx_texture.take() {
+            Some(t) => t,
+            None => CreateTexture::create(
+                &mut character_cache.factory,
+                Format::Rgba8,
+                &effect_textures.image,
+                [
+                    effect_textures.image.width(),
+                    effect_textures.image.height(),
+                ],
+                &TextureSettings::new(),
+            )?,
+        };
+        Ok(effect_textures.gfx_texture.get_or_insert(texture))
     }
 }
 
 impl GraphicsCostumeTexture<BufferGlyphs<'_>> for RenderBuffer {
-    fn get_costume_texture(costume: &Costume) -> &Self::Texture {
-        &costume.render_buffer_texture
+    fn get_costume_texture(costume: &Costume) -> Option<&Self::Texture> {
+        Some(&costume.render_buffer_texture)
     }
+
+    fn get_effect_texture<'a>(
+        effect_textures: &'a mut EffectTextures,
+        _character_cache: &mut BufferGlyphs<'_>,
+    ) -> Result<&'a Self::Texture> {
+        let texture = match effect_textures.render_buffer_texture.take() {
+            Some(t) => t,
+            None => CreateTexture::create(
+                &mut (),
+                Format::Rgba8,
+                &effect_textures.image,
+                [
+                    effect_textures.image.width(),
+                    effect_textures.image.height(),
+                ],
+                &TextureSettings::new(),
+            )?,
+        };
+        Ok(effect_textures.render_buffer_texture.get_or_insert(texture))
+    }
+}
+
+/// Textures of a costume with graphic effects. The image is recreated when the costume or the
+/// effects change.
+#[derive(Debug, Default)]
+pub struct EffectTextures {
+    key: Option<(usize, GraphicEffects)>,
+    image: RgbaImage,
+    gfx_texture: Option<Texture<Resources>>,
+    render_buffer_texture: Option<RenderBuffer>,
 }
 
 #[derive(Debug, Clone)]
@@ -379,17 +770,20 @@ pub struct Costume {
     name: String,
     /// Center point of image
     center: SpriteCoordinate,
-    gfx_texture: Texture<Resources>,
+    /// None if the VM was created without a window
+    gfx_texture: Option<Texture<Resources>>,
     render_buffer_texture: RenderBuffer,
+    /// Source pixels for graphic effects
+    image: RgbaImage,
 }
 
 impl Costume {
     pub async fn new(
-        texture_context: &mut G2dTextureContext,
+        texture_context: Option<&mut G2dTextureContext>,
         costume: &scratch_file::Costume,
         image_file: &Image,
     ) -> Result<Self> {
-        let (gfx_texture, render_buffer_texture, width, height) = match image_file {
+        let (gfx_texture, render_buffer_texture, image, width, height) = match image_file {
             Image::SVG(b) => Costume::svg_texture(b, texture_context)?,
             Image::PNG(b) => Costume::png_texture(b, texture_context)?,
         };
@@ -412,13 +806,20 @@ impl Costume {
             },
             gfx_texture,
             render_buffer_texture,
+            image,
         })
     }
 
     fn svg_texture(
         data: &[u8],
-        texture_context: &mut G2dTextureContext,
-    ) -> Result<(Texture<Resources>, RenderBuffer, u32, u32)> {
+        texture_context: Option<&mut G2dTextureContext>,
+    ) -> Result<(
+        Option<Texture<Resources>>,
+        RenderBuffer,
+        RgbaImage,
+        u32,
+        u32,
+    )> {
         let mut options = usvg::Options::default();
         options.fontdb.load_system_fonts();
 
@@ -434,13 +835,7 @@ impl Costume {
         let image: RgbaImage = ImageBuffer::from_raw(width, height, pixmap.take())
             .ok_or_else(|| Error::msg("svg error"))?;
         Ok((
-            CreateTexture::create(
-                texture_context,
-                Format::Rgba8,
-                &image,
-                [width, height],
-                &TextureSettings::new(),
-            )?,
+            Costume::gfx_texture(texture_context, &image)?,
             CreateTexture::create(
                 &mut (),
                 Format::Rgba8,
@@ -448,6 +843,7 @@ impl Costume {
                 [width, height],
                 &TextureSettings::new(),
             )?,
+            image,
             width,
             height,
         ))
@@ -455,8 +851,14 @@ impl Costume {
 
     fn png_texture(
         data: &[u8],
-        texture_context: &mut G2dTextureContext,
-    ) -> Result<(Texture<Resources>, RenderBuffer, u32, u32)> {
+        texture_context: Option<&mut G2dTextureContext>,
+    ) -> Result<(
+        Option<Texture<Resources>>,
+        RenderBuffer,
+        RgbaImage,
+        u32,
+        u32,
+    )> {
         let decoder = PngDecoder::new(Cursor::new(data))?;
         let x = decoder.dimensions().0;
         let y = decoder.dimensions().1;
@@ -465,13 +867,7 @@ impl Costume {
             .as_rgba8()
             .ok_or_else(|| Error::msg("not in RGBA color space"))?;
         Ok((
-            CreateTexture::create(
-                texture_context,
-                Format::Rgba8,
-                &image,
-                [image.width(), image.height()],
-                &TextureSettings::new(),
-            )?,
+            Costume::gfx_texture(texture_context, &image)?,
             CreateTexture::create(
                 &mut (),
                 Format::Rgba8,
@@ -479,19 +875,36 @@ impl Costume {
                 [image.width(), image.height()],
                 &TextureSettings::new(),
             )?,
+            image.clone(),
             x * 2,
             y * 2,
         ))
     }
 
+    fn gfx_texture(
+        texture_context: Option<&mut G2dTextureContext>,
+        image: &RgbaImage,
+    ) -> Result<Option<Texture<Resources>>> {
+        match texture_context {
+            Some(texture_context) => Ok(Some(CreateTexture::create(
+                texture_context,
+                Format::Rgba8,
+                image,
+                [image.width(), image.height()],
+                &TextureSettings::new(),
+            )?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn new_blank(
-        texture_context: &mut G2dTextureContext,
+        texture_context: Option<&mut G2dTextureContext>,
         costume: &scratch_file::Costume,
     ) -> Result<Self> {
         let mut file = File::open("assets/blank_backdrop.png")?;
         let mut buffer: Vec<u8> = Vec::new();
         file.read_to_end(&mut buffer)?;
-        let (gfx_texture, render_buffer_texture, width, height) =
+        let (gfx_texture, render_buffer_texture, image, width, height) =
             Costume::png_texture(&buffer, texture_context)?;
         Ok(Self {
             image_size: Size {
@@ -506,6 +919,7 @@ impl Costume {
             scale: 1.0,
             gfx_texture,
             render_buffer_texture,
+            image,
         })
     }
 }
@@ -517,8 +931,9 @@ pub struct Costumes {
 }
 
 impl Costumes {
+    /// Textures for the window are only created if texture_context is Some.
     pub async fn new(
-        texture_context: &mut G2dTextureContext,
+        mut texture_context: Option<&mut G2dTextureContext>,
         costume_data: &[scratch_file::Costume],
         images: &HashMap<String, Image>,
     ) -> Result<Self> {
@@ -527,13 +942,15 @@ impl Costumes {
         for costume in costume_data {
             let costume = if let Some(md5ext) = &costume.md5ext {
                 match images.get(md5ext) {
-                    Some(file) => Costume::new(texture_context, &costume, file).await?,
+                    Some(file) => {
+                        Costume::new(texture_context.as_deref_mut(), &costume, file).await?
+                    }
                     None => return Err(Error::msg(format!("image not found: {}", md5ext))),
                 }
             } else {
                 // Pre-made Scratch backdrops are not included in the .sb3 file. A blank image is
                 // used as a placeholder.
-                Costume::new_blank(texture_context, &costume)?
+                Costume::new_blank(texture_context.as_deref_mut(), &costume)?
             };
             costumes.push(costume);
         }
@@ -591,6 +1008,7 @@ impl Default for HideStatus {
 pub struct Text {
     pub id: BlockID,
     pub text: Option<String>,
+    pub bubble: Bubble,
 }
 
 impl Text {
@@ -600,3 +1018,15 @@ impl Text {
         }
     }
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bubble {
+    Say,
+    Think,
+}
+
+impl Default for Bubble {
+    fn default() -> Self {
+        Bubble::Say
+    }
+}
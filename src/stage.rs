@@ -0,0 +1,108 @@
+use super::*;
+use crate::collision::CollisionMask;
+use crate::coordinate::{CanvasRegion, SpriteRectangle};
+use crate::sprite::SpriteID;
+use async_trait::async_trait;
+use image::RgbaImage;
+use std::fmt::Debug;
+use std::sync::Weak;
+
+/// Lets blocks look at other sprites. It is implemented by SpriteMap and can be replaced in
+/// tests.
+#[async_trait]
+pub trait StageQuery: Debug + Send + Sync {
+    async fn sprite_rectangle(&self, id: SpriteID) -> Result<SpriteRectangle>;
+
+    /// Returns None if the sprite is hidden or was removed.
+    async fn collision_mask(&self, id: SpriteID) -> Result<Option<CollisionMask>>;
+
+    /// Renders all sprites except for the one with the ID. Only the pixels inside region are
+    /// returned.
+    async fn canvas_image(&self, without: SpriteID, region: CanvasRegion) -> Result<RgbaImage>;
+}
+
+/// Gives blocks access to the StageQuery. The query is set after the sprites are created, and it
+/// is held weakly because the sprites hold the Global that contains this.
+#[derive(Debug, Default)]
+pub struct Stage {
+    query: RwLock<Option<Weak<dyn StageQuery>>>,
+}
+
+impl Stage {
+    pub async fn set_query(&self, query: Weak<dyn StageQuery>) {
+        *self.query.write().await = Some(query);
+    }
+
+    async fn query(&self) -> Result<Arc<dyn StageQuery>> {
+        self.query
+            .read()
+            .await
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .ok_or_else(|| Error::msg("stage is not available"))
+    }
+
+    pub async fn sprite_rectangle(&self, id: SpriteID) -> Result<SpriteRectangle> {
+        self.query().await?.sprite_rectangle(id).await
+    }
+
+    pub async fn collision_mask(&self, id: SpriteID) -> Result<Option<CollisionMask>> {
+        self.query().await?.collision_mask(id).await
+    }
+
+    pub async fn canvas_image(&self, without: SpriteID, region: CanvasRegion) -> Result<RgbaImage> {
+        self.query().await?.canvas_image(without, region).await
+    }
+}
+
+/// Stage with sprites that only have a rectangle.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct TestStage {
+    pub rectangles: HashMap<SpriteID, SpriteRectangle>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl StageQuery for TestStage {
+    async fn sprite_rectangle(&self, id: SpriteID) -> Result<SpriteRectangle> {
+        self.rectangles
+            .get(&id)
+            .copied()
+            .ok_or_else(|| Error::msg(format!("id not found: {}", id)))
+    }
+
+    async fn collision_mask(&self, _id: SpriteID) -> Result<Option<CollisionMask>> {
+        Ok(None)
+    }
+
+    async fn canvas_image(&self, _without: SpriteID, _region: CanvasRegion) -> Result<RgbaImage> {
+        Err(Error::msg("canvas_image is not supported"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::SpriteCoordinate;
+
+    #[tokio::test]
+    async fn stage() {
+        let stage = Stage::default();
+        let id = SpriteID::from_sprite_name("a");
+        assert!(stage.sprite_rectangle(id).await.is_err());
+
+        let rectangle = SpriteRectangle {
+            center: SpriteCoordinate { x: 1.0, y: 2.0 },
+            ..SpriteRectangle::default()
+        };
+        let mut test_stage = TestStage::default();
+        test_stage.rectangles.insert(id, rectangle);
+        let query: Arc<dyn StageQuery> = Arc::new(test_stage);
+        stage.set_query(Arc::downgrade(&query)).await;
+        assert_eq!(stage.sprite_rectangle(id).await.unwrap(), rectangle);
+
+        drop(query);
+        assert!(stage.sprite_rectangle(id).await.is_err());
+    }
+}
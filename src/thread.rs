@@ -1,22 +1,104 @@
 use super::*;
-use crate::blocks::{Block, BlockInfo, BlockInputsPartial, Next};
+use crate::blocks::{Block, BlockInfo, BlockInputsPartial, Hat, Next};
+use std::time::{Duration, Instant};
+
+/// Procedures that run without screen refresh yield after this duration, like in Scratch.
+const WARP_TIMEOUT: Duration = Duration::from_millis(500);
 
 #[derive(Debug)]
 pub struct Thread {
     blocks: HashMap<BlockID, Box<dyn Block>>,
+    hat: BlockID,
     curr_block: BlockID,
-    loop_stack: Vec<BlockID>,
+    loop_stack: Vec<LoopFrame>,
+    /// Set when curr_block is returned to at the end of a loop iteration
+    loop_iterations: Option<usize>,
+    call_stack: Vec<CallFrame>,
     done: bool,
+    /// Condition of an edge-triggered hat when it was last evaluated
+    hat_condition: bool,
+}
+
+#[derive(Debug)]
+struct LoopFrame {
+    /// The block that loops
+    block: BlockID,
+    /// Number of times the substack has run
+    iterations: usize,
+    /// False for the branches of if blocks, which do not yield when they end
+    yields: bool,
+}
+
+#[derive(Debug)]
+struct CallFrame {
+    /// The block that called the procedure
+    call_block: BlockID,
+    /// Loop stack of the caller
+    loop_stack: Vec<LoopFrame>,
+    warp: bool,
 }
 
 impl Thread {
     pub fn new(hat: BlockID, blocks: HashMap<BlockID, Box<dyn Block>>) -> Self {
         Thread {
             blocks,
+            hat,
             curr_block: hat,
             loop_stack: Vec::new(),
+            loop_iterations: None,
+            call_stack: Vec::new(),
             done: false,
+            hat_condition: false,
+        }
+    }
+
+    /// Returns what starts the thread.
+    pub fn hat(&self) -> Option<Hat> {
+        self.blocks.get(&self.hat).and_then(|hat| hat.hat())
+    }
+
+    /// Runs the thread from the hat again the next time it is stepped. The state of every block
+    /// is reset.
+    pub async fn restart(&mut self) {
+        self.curr_block = self.hat;
+        self.loop_stack.clear();
+        self.loop_iterations = None;
+        self.call_stack.clear();
+        self.done = false;
+        for block in self.blocks.values_mut() {
+            block.reset().await;
+        }
+    }
+
+    /// Ends the thread before it reaches the end of the script. The state of every block is reset,
+    /// so that no procedure arguments are left.
+    pub async fn stop(&mut self) {
+        self.restart().await;
+        self.done = true;
+    }
+
+    /// Evaluates the condition of an edge-triggered hat. If the condition changed from false to
+    /// true, the thread is restarted and true is returned.
+    pub async fn hat_triggered(&mut self) -> Result<bool> {
+        let hat = self
+            .blocks
+            .get_mut(&self.hat)
+            .ok_or_else(|| Error::msg(format!("{} does not exist", &self.hat)))?;
+        let condition = hat
+            .hat_condition()
+            .await
+            .map_err(|error| ScratchError::Block {
+                id: hat.block_info().id,
+                name: hat.block_info().name,
+                error,
+            })?;
+
+        let triggered = condition && !self.hat_condition;
+        self.hat_condition = condition;
+        if triggered {
+            self.restart().await;
         }
+        Ok(triggered)
     }
 
     pub async fn step(&mut self) -> Result<StepStatus> {
@@ -24,34 +106,108 @@ impl Thread {
             return Err(Error::msg("this thread already ended"));
         }
 
-        let curr_block = self.curr_block;
-        let block = self
-            .blocks
-            .get_mut(&curr_block)
-            .ok_or_else(|| Error::msg(format!("{} does not exist", &curr_block)))?;
-
-        let execute_result = block.execute().await.map_err(|error| ScratchError::Block {
-            id: block.block_info().id,
-            name: block.block_info().name,
-            error,
-        })?;
-
-        match execute_result {
-            Next::None => match self.loop_stack.pop() {
-                None => {
+        let start = Instant::now();
+        loop {
+            let curr_block = self.curr_block;
+            let block = self
+                .blocks
+                .get_mut(&curr_block)
+                .ok_or_else(|| Error::msg(format!("{} does not exist", &curr_block)))?;
+
+            let iterations = self.loop_iterations.take();
+            let execute_result = match iterations {
+                Some(iterations) => block.loop_return(iterations).await,
+                None => block.execute().await,
+            }
+            .map_err(|error| ScratchError::Block {
+                id: block.block_info().id,
+                name: block.block_info().name,
+                error,
+            })?;
+
+            let status = self.next(execute_result, iterations.unwrap_or(0)).await?;
+            if !self.warp() {
+                if status == StepStatus::Done {
                     self.done = true;
-                    return Ok(StepStatus::Done);
                 }
-                Some(b) => self.curr_block = b,
-            },
-            Next::Continue(b) => self.curr_block = b,
-            Next::Loop(b) => {
-                self.loop_stack.push(self.curr_block);
-                self.curr_block = b;
+                return Ok(status);
+            }
+
+            // Procedures that run without screen refresh continue in the same step
+            if start.elapsed() > WARP_TIMEOUT {
+                return Ok(StepStatus::Yield);
             }
         }
+    }
 
-        Ok(StepStatus::Continue)
+    /// Moves curr_block to the next block. iterations is the number of loop iterations of
+    /// curr_block.
+    async fn next(&mut self, mut next: Next, mut iterations: usize) -> Result<StepStatus> {
+        loop {
+            match next {
+                Next::None => {
+                    if let Some(frame) = self.loop_stack.pop() {
+                        self.curr_block = frame.block;
+                        self.loop_iterations = Some(frame.iterations);
+                        // Threads yield at the end of each loop iteration
+                        if frame.yields {
+                            return Ok(StepStatus::Yield);
+                        }
+                    } else if let Some(frame) = self.call_stack.pop() {
+                        self.loop_stack = frame.loop_stack;
+                        let block = self.blocks.get_mut(&frame.call_block).ok_or_else(|| {
+                            Error::msg(format!("{} does not exist", &frame.call_block))
+                        })?;
+                        next = block.procedure_return().await.map_err(|error| {
+                            ScratchError::Block {
+                                id: block.block_info().id,
+                                name: block.block_info().name,
+                                error,
+                            }
+                        })?;
+                        iterations = 0;
+                        continue;
+                    } else {
+                        return Ok(StepStatus::Done);
+                    }
+                }
+                Next::Continue(b) => self.curr_block = b,
+                Next::Loop(b) if b == self.curr_block => {
+                    self.loop_iterations = Some(iterations + 1);
+                    return Ok(StepStatus::Yield);
+                }
+                Next::Loop(b) => {
+                    self.loop_stack.push(LoopFrame {
+                        block: self.curr_block,
+                        iterations: iterations + 1,
+                        yields: true,
+                    });
+                    self.curr_block = b;
+                }
+                Next::Branch(b) => {
+                    self.loop_stack.push(LoopFrame {
+                        block: self.curr_block,
+                        iterations: iterations + 1,
+                        yields: false,
+                    });
+                    self.curr_block = b;
+                }
+                Next::Call { procedure, warp } => {
+                    self.call_stack.push(CallFrame {
+                        call_block: self.curr_block,
+                        loop_stack: std::mem::take(&mut self.loop_stack),
+                        warp,
+                    });
+                    self.curr_block = procedure;
+                }
+            }
+            return Ok(StepStatus::Continue);
+        }
+    }
+
+    /// Returns true if the thread is in a procedure that runs without screen refresh.
+    fn warp(&self) -> bool {
+        self.call_stack.iter().any(|frame| frame.warp)
     }
 
     pub fn block_inputs(&self) -> Result<BlockInputs> {
@@ -110,6 +266,8 @@ impl BlockInputs {
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum StepStatus {
     Continue,
+    /// The thread lets other threads run before it continues.
+    Yield,
     Done,
 }
 
@@ -227,9 +385,9 @@ mod test {
 
                 let mut thread = Thread::new(block0_id, blocks);
                 assert!(matches!(thread.step().await.unwrap(), StepStatus::Continue));
+                assert!(matches!(thread.step().await.unwrap(), StepStatus::Yield));
                 assert!(matches!(thread.step().await.unwrap(), StepStatus::Continue));
-                assert!(matches!(thread.step().await.unwrap(), StepStatus::Continue));
-                assert!(matches!(thread.step().await.unwrap(), StepStatus::Continue));
+                assert!(matches!(thread.step().await.unwrap(), StepStatus::Yield));
 
                 *next.write().await = Next::None;
                 assert!(matches!(thread.step().await.unwrap(), StepStatus::Done));
@@ -257,5 +415,88 @@ mod test {
                 assert!(receiver.try_recv().is_err());
             }
         }
+
+        #[tokio::test]
+        async fn loop_to_itself() {
+            let runtime = Runtime::default();
+            let mut receiver = runtime.global.broadcaster.subscribe();
+            let mut gen = BlockIDGenerator::new();
+            let block_id = gen.get_id();
+            let next = Arc::new(RwLock::new(Next::Loop(block_id)));
+
+            let blocks = block_map(vec![(
+                block_id,
+                Box::new(BlockStub::with_behavior(
+                    block_id,
+                    runtime.clone(),
+                    None,
+                    next.clone(),
+                )),
+            )]);
+
+            let mut thread = Thread::new(block_id, blocks);
+            for _ in 0..2 {
+                assert_eq!(thread.step().await.unwrap(), StepStatus::Yield);
+                assert_eq!(thread.block_info().unwrap().id, block_id);
+                assert!(thread.loop_stack.is_empty());
+            }
+            assert_eq!(thread.loop_iterations, Some(2));
+
+            *next.write().await = Next::None;
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+
+            for _ in 0..3 {
+                assert_eq!(
+                    receiver.try_recv().unwrap(),
+                    BroadcastMsg::BlockStub(block_id, BlockStubMsg::Executed)
+                );
+            }
+            assert!(receiver.try_recv().is_err());
+        }
+
+        #[tokio::test]
+        async fn restart() {
+            let runtime = Runtime::default();
+            let mut gen = BlockIDGenerator::new();
+            let block0_id = gen.get_id();
+            let block1_id = gen.get_id();
+            let next = Arc::new(RwLock::new(Next::Loop(block1_id)));
+
+            let blocks = block_map(vec![
+                (
+                    block0_id,
+                    Box::new(BlockStub::with_behavior(
+                        block0_id,
+                        runtime.clone(),
+                        None,
+                        next.clone(),
+                    )),
+                ),
+                (
+                    block1_id,
+                    Box::new(BlockStub::with_behavior(
+                        block1_id,
+                        runtime.clone(),
+                        None,
+                        Arc::new(RwLock::new(Next::None)),
+                    )),
+                ),
+            ]);
+
+            let mut thread = Thread::new(block0_id, blocks);
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Continue);
+            assert_eq!(thread.block_info().unwrap().id, block1_id);
+
+            thread.restart().await;
+            assert_eq!(thread.block_info().unwrap().id, block0_id);
+            assert!(thread.loop_stack.is_empty());
+
+            *next.write().await = Next::None;
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+            assert!(thread.step().await.is_err());
+
+            thread.restart().await;
+            assert_eq!(thread.step().await.unwrap(), StepStatus::Done);
+        }
     }
 }
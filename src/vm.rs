@@ -1,22 +1,40 @@
 use super::*;
-use crate::blocks::BlockInfo;
-use crate::broadcaster::{BroadcastMsg, Broadcaster, Stop};
-use crate::coordinate::{canvas_const, CanvasCoordinate};
+use crate::audio::{Mixer, Sound};
+use crate::blocks::value::Value;
+use crate::blocks::{BlockInfo, Hat, HatEvent};
+use crate::broadcaster::{BroadcastMsg, Broadcaster, LayerChange, Stop};
+use crate::clock::Clock;
+use crate::coordinate::{canvas_const, CanvasCoordinate, SpriteCoordinate};
 use crate::interface::CANVAS_TOP_LEFT;
 use crate::runtime::Global;
 use crate::sprite::{Sprite, SpriteID};
 use crate::sprite_map::SpriteMap;
-use crate::sprite_runtime::{Costumes, SpriteRuntime};
+use crate::sprite_runtime::{Costumes, SpriteRuntime, BUFFER_GLYPHS};
+use crate::stage::StageQuery;
+use crate::thread::StepStatus;
 use conrod_core::input::Button;
+use futures::future::{AbortHandle, Abortable, Aborted};
 use futures::stream::FuturesUnordered;
-use futures::StreamExt;
+use futures::{Future, StreamExt};
 use graphics::Context;
-use graphics_buffer::{buffer_glyphs_from_path, RenderBuffer};
-use input::{ButtonState, Input, Motion, MouseButton};
+use graphics_buffer::RenderBuffer;
+use input::{ButtonState, Input, Key, Motion, MouseButton};
 use piston_window::{G2d, G2dTextureContext, Glyphs};
-use std::fmt::Debug;
+use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::select;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::time::{sleep_until, Instant};
+
+/// Scratch redraws the stage 30 times per second.
+pub const FRAME_DURATION: std::time::Duration = std::time::Duration::from_nanos(1_000_000_000 / 30);
+
+/// Threads are run again in the same frame until this much of the frame is used.
+const WORK_TIME: std::time::Duration =
+    std::time::Duration::from_nanos(FRAME_DURATION.as_nanos() as u64 * 3 / 4);
+
+/// A pressed sprite is dragged after the mouse moves farther than this many pixels.
+const DRAG_THRESHOLD: f64 = 3.0;
 
 #[derive(Debug)]
 pub struct VM {
@@ -25,18 +43,34 @@ pub struct VM {
     vm_task: JoinHandle<()>,
     sprites: Arc<SpriteMap>,
     global: Arc<Global>,
+    finished: Arc<Notify>,
+    /// Notified when a frame started by step_frame() has ended
+    frame_finished: Arc<Notify>,
+    turbo: Arc<AtomicBool>,
+    /// Draggable sprite that is held down with the mouse
+    mouse_press: RwLock<Option<MousePress>>,
 }
 
 impl VM {
+    /// Creates a VM without textures for the window if texture_context is None. The VM can still
+    /// run, but it can only be drawn to a RenderBuffer.
     pub async fn new(
-        texture_context: &mut G2dTextureContext,
+        texture_context: Option<&mut G2dTextureContext>,
         scratch_file: ScratchFile,
+        mixer: Arc<dyn Mixer>,
+        clock: Arc<dyn Clock>,
     ) -> Result<Self> {
         let (control_sender, control_receiver) = mpsc::channel(1);
+        let finished = Arc::new(Notify::new());
+        let frame_finished = Arc::new(Notify::new());
+        let turbo = Arc::new(AtomicBool::new(false));
 
         let global = Arc::new(Global::new(
             &scratch_file.project.targets[0].variables,
+            &scratch_file.project.targets[0].lists,
             &scratch_file.project.monitors,
+            mixer,
+            clock,
         ));
 
         let sprites = VM::sprites(texture_context, &scratch_file, global.clone()).await?;
@@ -46,16 +80,31 @@ impl VM {
             &scratch_file.project.targets,
             global.clone(),
         ));
+        let stage_query: Arc<dyn StageQuery> = sprite_map.clone();
+        global.stage.set_query(Arc::downgrade(&stage_query)).await;
 
         let vm_task = spawn({
             let mut control_receiver = control_receiver;
+            // Subscribed before the task runs, so that no broadcast sent after new() is missed
+            let mut broadcast_receiver = global.broadcaster.subscribe();
             let broadcaster = global.broadcaster.clone();
             let sprite_map = sprite_map.clone();
+            let finished = finished.clone();
+            let frame_finished = frame_finished.clone();
+            let turbo = turbo.clone();
 
             async move {
                 loop {
-                    if let Err(e) =
-                        VM::run(sprite_map.clone(), &mut control_receiver, &broadcaster).await
+                    if let Err(e) = VM::run(
+                        sprite_map.clone(),
+                        &mut control_receiver,
+                        &mut broadcast_receiver,
+                        &broadcaster,
+                        &finished,
+                        &frame_finished,
+                        &turbo,
+                    )
+                    .await
                     {
                         log::error!("{:?}", e);
                         std::process::exit(1);
@@ -70,11 +119,15 @@ impl VM {
             vm_task,
             sprites: sprite_map,
             global,
+            finished,
+            frame_finished,
+            turbo,
+            mouse_press: RwLock::default(),
         })
     }
 
     async fn sprites(
-        texture_context: &mut G2dTextureContext,
+        mut texture_context: Option<&mut G2dTextureContext>,
         scratch_file: &ScratchFile,
         global: Arc<Global>,
     ) -> Result<HashMap<SpriteID, Sprite>> {
@@ -85,33 +138,59 @@ impl VM {
             Default::default(),
         );
         for target in &scratch_file.project.targets {
-            let sprite_runtime = SpriteRuntime::new(&target);
+            let sprite_runtime = SpriteRuntime::new(&target, &scratch_file.project.monitors);
             let id = SpriteID::from_sprite_name(&target.name);
             let mut sprite =
                 Sprite::new(id, sprite_runtime, global.clone(), target.blocks.clone())?;
-            let costumes = Costumes::new(texture_context, &target.costumes, &images).await?;
+            let costumes =
+                Costumes::new(texture_context.as_deref_mut(), &target.costumes, &images).await?;
             sprite.set_costumes(costumes).await;
+            let sounds = target
+                .sounds
+                .iter()
+                .map(|sound| Sound::new(sound, &scratch_file.sounds))
+                .collect::<Result<Vec<Sound>>>()?;
+            sprite.set_sounds(sounds).await;
             sprites.insert(id, sprite);
         }
         Ok(sprites)
     }
 
+    /// Runs threads in frames. In each frame every ready thread runs until it yields. Threads
+    /// that yield are run again in the same frame until a sprite needs to be redrawn or the work
+    /// time of the frame is used up. In turbo mode, redraws do not end the frame. Frames that are
+    /// started with Control::Frame run every ready thread until it yields once.
     async fn run(
         sprites: Arc<SpriteMap>,
         control_receiver: &mut mpsc::Receiver<Control>,
+        broadcast_receiver: &mut broadcast::Receiver<BroadcastMsg>,
         broadcaster: &Broadcaster,
+        finished: &Notify,
+        frame_finished: &Notify,
+        turbo: &AtomicBool,
     ) -> Result<()> {
-        let mut broadcast_receiver = broadcaster.subscribe();
         let mut futures = FuturesUnordered::new();
+        // Cancels the run of a thread that is in futures
+        let mut abort_handles: HashMap<ThreadID, AbortHandle> = HashMap::default();
 
         let mut paused_threads: Vec<ThreadID> = Vec::new();
+        let mut ready_threads: Vec<ThreadID> = Vec::new();
+        // Threads that were started by their hat and have not ended
+        let mut active_threads: HashSet<ThreadID> = HashSet::default();
+        // Threads that wait for the condition of their hat to become true
+        let mut hat_threads: Vec<ThreadID> = Vec::new();
         for thread_id in sprites.all_thread_ids().await {
-            paused_threads.push(thread_id);
+            if matches!(sprites.hat(thread_id).await?, Some(Hat::EdgeTriggered)) {
+                hat_threads.push(thread_id);
+            }
         }
 
-        let mut buffer_glyphs = buffer_glyphs_from_path("assets/Roboto-Regular.ttf")?;
-
         let mut current_state = Control::Pause;
+        let mut frame_start = Instant::now();
+        let mut next_frame = frame_start;
+        let mut redraw_requested = false;
+        // The next frame starts without waiting for the frame time
+        let mut frame_requested = false;
 
         loop {
             select! {
@@ -121,21 +200,41 @@ impl VM {
                         log::info!("control: {:?}", &control);
                         current_state = control;
                         match control {
-                            Control::Continue | Control::Step => {
+                            Control::Continue => {
+                                ready_threads.append(&mut paused_threads);
+                                next_frame = Instant::now();
+                            }
+                            Control::Step => {
                                 for thread_id in paused_threads.drain(..) {
-                                    futures.push(sprites.step(thread_id));
+                                    futures.push(VM::run_thread(&sprites, thread_id, true, &mut abort_handles));
+                                }
+                                if futures.is_empty() {
+                                    finished.notify_one();
                                 }
                             }
+                            Control::Frame => {
+                                ready_threads.append(&mut paused_threads);
+                                frame_requested = true;
+                                next_frame = Instant::now();
+                            }
                             Control::Stop => return Ok(()),
-                            Control::Pause => {}
+                            Control::Pause => paused_threads.append(&mut ready_threads),
                         }
                     }
                 },
                 recv_result = broadcast_receiver.recv() => {
                     match recv_result {
                         Ok(msg) => {
-                            log::info!("broadcast: {:?}", BroadcastMsgDebug(&msg));
+                            log::info!("broadcast: {:?}", msg);
+                            let mut hat_event = VM::hat_event(&sprites, &msg).await;
                             match msg {
+                                BroadcastMsg::GreenFlag => {
+                                    sprites.remove_clones().await;
+                                    for thread_id in &active_threads {
+                                        sprites.stop(*thread_id).await;
+                                        VM::abort(&abort_handles, *thread_id);
+                                    }
+                                }
                                 BroadcastMsg::Clone(from_sprite) => {
                                     let new_sprite_id = sprites.clone_sprite(from_sprite).await?;
                                     for thread_id in 0..sprites.number_of_threads(&new_sprite_id).await? {
@@ -143,14 +242,11 @@ impl VM {
                                             sprite_id: new_sprite_id,
                                             thread_id,
                                         };
-                                        match current_state {
-                                            Control::Continue | Control::Step => {
-                                                futures.push(sprites.step(id))
-                                            }
-                                            Control::Pause => paused_threads.push(id),
-                                            _ => unreachable!(),
+                                        if matches!(sprites.hat(id).await?, Some(Hat::EdgeTriggered)) {
+                                            hat_threads.push(id);
                                         }
                                     }
+                                    hat_event = Some((HatEvent::StartAsClone, Some(new_sprite_id)));
                                 }
                                 BroadcastMsg::DeleteClone(sprite_id) => {
                                     sprites.remove(sprite_id).await;
@@ -159,6 +255,7 @@ impl VM {
                                     Stop::All => {
                                         for thread_id in sprites.all_thread_ids().await {
                                             sprites.stop(thread_id).await;
+                                            VM::abort(&abort_handles, thread_id);
                                         }
                                     }
                                     Stop::ThisThread(thread_id) => {
@@ -169,7 +266,8 @@ impl VM {
                                             if id.sprite_id == thread_id.sprite_id
                                                 && id.thread_id != thread_id.thread_id
                                             {
-                                                sprites.stop(thread_id).await;
+                                                sprites.stop(id).await;
+                                                VM::abort(&abort_handles, id);
                                             }
                                         }
                                     }
@@ -177,22 +275,28 @@ impl VM {
                                 BroadcastMsg::ChangeLayer { sprite, action } => {
                                     sprites.change_layer(sprite, action).await?;
                                 }
-                                BroadcastMsg::RequestSpriteRectangle(sprite_id) => {
-                                    let rectangle = sprites.sprite_rectangle(&sprite_id).await?;
-                                    broadcaster.send(BroadcastMsg::SpriteRectangle {
-                                        sprite: sprite_id,
-                                        rectangle,
-                                    })?;
+                                _ => {}
+                            }
+
+                            if let Some((event, sprite_id)) = hat_event {
+                                for (thread_id, hat) in sprites.triggered_threads(&event, sprite_id).await {
+                                    if active_threads.insert(thread_id) {
+                                        sprites.restart(thread_id).await;
+                                        match current_state {
+                                            Control::Continue | Control::Frame => ready_threads.push(thread_id),
+                                            Control::Step | Control::Pause => paused_threads.push(thread_id),
+                                            _ => unreachable!(),
+                                        }
+                                    } else if hat.restarts_running_thread() {
+                                        sprites.restart(thread_id).await;
+                                        VM::abort(&abort_handles, thread_id);
+                                    }
                                 }
-                                BroadcastMsg::RequestCanvasImage(sprite_id) => {
-                                    let mut render_buffer =
-                                        RenderBuffer::new(canvas_const::X_MAX as u32, canvas_const::Y_MAX as u32);
-                                    sprites
-                                        .draw_to_buffer(&mut Context::new(), &mut render_buffer, &mut buffer_glyphs, &sprite_id)
-                                        .await?;
-                                    broadcaster.send(BroadcastMsg::CanvasImage(render_buffer))?;
+
+                                // The project has nothing to run
+                                if event == HatEvent::GreenFlag && active_threads.is_empty() {
+                                    finished.notify_one();
                                 }
-                                _ => {}
                             }
                         }
                         Err(e) => {
@@ -200,11 +304,76 @@ impl VM {
                         }
                     }
                 },
-                futures_result = futures.next() => {
+                // Broadcasts that were sent before a frame is requested are handled before the frame
+                _ = sleep_until(next_frame), if frame_requested
+                    || (matches!(current_state, Control::Continue)
+                        && !(ready_threads.is_empty() && hat_threads.is_empty())) => {
+                    frame_requested = false;
+                    frame_start = Instant::now();
+                    next_frame = frame_start + FRAME_DURATION;
+                    sprites.take_redraw_requested().await;
+                    redraw_requested = false;
+
+                    let mut waiting_hat_threads: Vec<ThreadID> = Vec::with_capacity(hat_threads.len());
+                    for thread_id in hat_threads.drain(..) {
+                        match sprites.hat_triggered(thread_id).await? {
+                            Some(true) => {
+                                active_threads.insert(thread_id);
+                                ready_threads.push(thread_id);
+                            }
+                            Some(false) => waiting_hat_threads.push(thread_id),
+                            None => {}
+                        }
+                    }
+                    hat_threads = waiting_hat_threads;
+                    for thread_id in ready_threads.drain(..) {
+                        futures.push(VM::run_thread(&sprites, thread_id, false, &mut abort_handles));
+                    }
+                    if matches!(current_state, Control::Frame) && futures.is_empty() {
+                        frame_finished.notify_one();
+                    }
+                },
+                futures_result = futures.next(), if !futures.is_empty() => {
                     if let Some(step_result) = futures_result {
-                        if let Some(thread_id) = step_result? {
-                            match current_state {
-                                Control::Continue => futures.push(sprites.step(thread_id)),
+                        let (thread_id, status) = step_result?;
+                        abort_handles.remove(&thread_id);
+                        match status {
+                            // The run was cancelled and the thread starts from the hat
+                            None => match current_state {
+                                Control::Continue | Control::Frame => ready_threads.push(thread_id),
+                                Control::Step | Control::Pause => paused_threads.push(thread_id),
+                                _ => unreachable!("{:?}", current_state),
+                            },
+                            Some(StepStatus::Done) => {
+                                active_threads.remove(&thread_id);
+                                match sprites.hat(thread_id).await? {
+                                    Some(Hat::EdgeTriggered) => hat_threads.push(thread_id),
+                                    Some(Hat::BroadcastReceived(message)) => {
+                                        broadcaster.send(BroadcastMsg::Finished(message))?;
+                                    }
+                                    _ => {}
+                                }
+                                // Threads that wait for their edge-triggered hat do not count
+                                if futures.is_empty()
+                                    && ready_threads.is_empty()
+                                    && paused_threads.is_empty()
+                                {
+                                    finished.notify_one();
+                                }
+                            }
+                            Some(status) => match current_state {
+                                Control::Continue => {
+                                    redraw_requested |= sprites.take_redraw_requested().await;
+                                    let rerun = matches!(status, StepStatus::Continue)
+                                        || ((turbo.load(Ordering::Relaxed) || !redraw_requested)
+                                            && frame_start.elapsed() < WORK_TIME);
+                                    if rerun {
+                                        futures.push(VM::run_thread(&sprites, thread_id, false, &mut abort_handles));
+                                    } else {
+                                        ready_threads.push(thread_id);
+                                    }
+                                }
+                                Control::Frame => ready_threads.push(thread_id),
                                 Control::Step | Control::Pause => {
                                     paused_threads.push(thread_id);
                                     log::trace!(
@@ -217,7 +386,10 @@ impl VM {
                                     current_state = Control::Pause;
                                 }
                                 _ => unreachable!("{:?}", current_state),
-                            }
+                            },
+                        }
+                        if matches!(current_state, Control::Frame) && futures.is_empty() {
+                            frame_finished.notify_one();
                         }
                     }
                 },
@@ -225,11 +397,93 @@ impl VM {
         }
     }
 
+    /// Returns the event that starts hats for the broadcast message, and the sprite that the event
+    /// is sent to if it is only for one sprite.
+    async fn hat_event(
+        sprites: &SpriteMap,
+        msg: &BroadcastMsg,
+    ) -> Option<(HatEvent, Option<SpriteID>)> {
+        Some(match msg {
+            BroadcastMsg::GreenFlag => (HatEvent::GreenFlag, None),
+            BroadcastMsg::Start(message) => (HatEvent::Broadcast(message.clone()), None),
+            BroadcastMsg::KeyPressed(key) => (HatEvent::KeyPressed(*key), None),
+            BroadcastMsg::MouseClick(position) => {
+                match sprites.sprite_at(&(*position).into()).await {
+                    Some(sprite_id) => (HatEvent::SpriteClicked, Some(sprite_id)),
+                    None => (HatEvent::StageClicked, None),
+                }
+            }
+            BroadcastMsg::BackdropSwitched(backdrop) => {
+                (HatEvent::BackdropSwitched(backdrop.clone()), None)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Runs the thread until it yields or is done. Only one block is run if single_block is true.
+    /// The run can be cancelled with the handle that is added to abort_handles, and the status is
+    /// None if it was cancelled.
+    fn run_thread<'a>(
+        sprites: &'a SpriteMap,
+        thread_id: ThreadID,
+        single_block: bool,
+        abort_handles: &mut HashMap<ThreadID, AbortHandle>,
+    ) -> impl Future<Output = Result<(ThreadID, Option<StepStatus>)>> + 'a {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        abort_handles.insert(thread_id, abort_handle);
+        async move {
+            let run = VM::step_thread(sprites, thread_id, single_block);
+            match Abortable::new(run, abort_registration).await {
+                Ok(status) => Ok((thread_id, Some(status?))),
+                Err(Aborted) => Ok((thread_id, None)),
+            }
+        }
+    }
+
+    async fn step_thread(
+        sprites: &SpriteMap,
+        thread_id: ThreadID,
+        single_block: bool,
+    ) -> Result<StepStatus> {
+        loop {
+            let status = sprites.step(thread_id).await?;
+            if single_block || !matches!(status, StepStatus::Continue) {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Cancels the run of the thread if it is running. A thread that waits inside a block is
+    /// stopped or restarted without waiting for the block to finish.
+    fn abort(abort_handles: &HashMap<ThreadID, AbortHandle>, thread_id: ThreadID) {
+        if let Some(abort_handle) = abort_handles.get(&thread_id) {
+            abort_handle.abort();
+        }
+    }
+
+    /// Resets the project and starts the scripts under "when flag clicked" hats. Running scripts
+    /// are stopped and clones are removed.
+    pub async fn green_flag(&self) {
+        self.continue_().await;
+        self.start_green_flag().await;
+    }
+
+    /// Like green_flag(), but the VM stays paused if it is paused. The scripts then run with
+    /// step() or step_frame().
+    pub async fn start_green_flag(&self) {
+        self.global.timer.reset();
+        self.global.mixer.stop_all();
+        self.global.questions.clear().await;
+        self.broadcaster.send(BroadcastMsg::GreenFlag).unwrap();
+    }
+
     pub async fn continue_(&self) {
+        self.global.timer.resume();
         self.control_sender.send(Control::Continue).await.unwrap();
     }
 
     pub async fn pause(&self) {
+        self.global.timer.pause();
         self.control_sender.send(Control::Pause).await.unwrap();
     }
 
@@ -237,10 +491,42 @@ impl VM {
         self.control_sender.send(Control::Step).await.unwrap();
     }
 
+    /// Runs one frame without waiting for the frame time and returns when the frame has ended.
+    /// Every ready thread runs until it yields once. Threads do not run between frames.
+    pub async fn step_frame(&self) {
+        self.global.timer.resume();
+        self.control_sender.send(Control::Frame).await.unwrap();
+        self.frame_finished.notified().await;
+    }
+
     pub async fn stop(&self) {
+        self.global.mixer.stop_all();
+        self.global.questions.clear().await;
         self.control_sender.send(Control::Stop).await.unwrap();
     }
 
+    /// Turbo mode runs threads until the work time of the frame is used up, even if the stage
+    /// needs to be redrawn.
+    pub fn set_turbo(&self, turbo: bool) {
+        self.turbo.store(turbo, Ordering::Relaxed);
+    }
+
+    /// Waits until every thread has ended. Threads of edge-triggered hats, such as "when timer >",
+    /// have ended while they wait for their condition.
+    pub async fn wait_until_finished(&self) {
+        self.finished.notified().await;
+    }
+
+    /// Answers questions with answers instead of waiting for keyboard input.
+    pub async fn set_scripted_answers(&self, answers: Vec<String>) {
+        self.global.questions.set_scripted_answers(answers).await;
+    }
+
+    /// Returns the name and value of all global variables.
+    pub async fn variables(&self) -> Vec<(String, Value)> {
+        self.global.variables.name_values().await
+    }
+
     pub async fn draw(
         &mut self,
         context: &Context,
@@ -250,34 +536,134 @@ impl VM {
         self.sprites.draw(context, graphics, character_cache).await
     }
 
+    /// Renders the stage as it is currently shown, including pen lines, speech bubbles and
+    /// monitors. This works without a window.
+    pub async fn render_stage(&self) -> Result<RenderBuffer> {
+        let mut render_buffer =
+            RenderBuffer::new(canvas_const::X_MAX as u32, canvas_const::Y_MAX as u32);
+        graphics::clear([1.0, 1.0, 1.0, 1.0], &mut render_buffer);
+        let mut buffer_glyphs = BUFFER_GLYPHS.write().await;
+        self.sprites
+            .draw_stage_to_buffer(
+                &mut Context::new(),
+                &mut render_buffer,
+                buffer_glyphs.deref_mut(),
+            )
+            .await?;
+        Ok(render_buffer)
+    }
+
     pub async fn input(&self, input: Input) -> Result<()> {
         match input {
             Input::Button(button) => match button.button {
-                Button::Keyboard(key) => self.global.inputs.set_key(key, button.state).await,
+                Button::Keyboard(key) => {
+                    if matches!(button.state, ButtonState::Press) {
+                        match key {
+                            Key::Return => self.global.questions.submit().await,
+                            Key::Backspace => self.global.questions.backspace().await,
+                            _ => {}
+                        }
+                        self.global
+                            .broadcaster
+                            .send(BroadcastMsg::KeyPressed(key))?;
+                    }
+                    self.global.inputs.set_key(key, button.state).await;
+                }
                 Button::Mouse(mouse) => {
-                    if matches!(mouse, MouseButton::Left)
-                        && matches!(button.state, ButtonState::Press)
-                    {
-                        self.global.broadcaster.send(BroadcastMsg::MouseClick(
-                            self.global.inputs.mouse_position().await,
-                        ))?;
+                    if matches!(mouse, MouseButton::Left) {
+                        let pressed = matches!(button.state, ButtonState::Press);
+                        self.global.inputs.set_mouse_down(pressed).await;
+                        let position = self.global.inputs.mouse_position().await;
+                        if pressed {
+                            self.press_mouse(position).await?;
+                        } else {
+                            self.release_mouse().await?;
+                        }
                     }
                 }
                 _ => {}
             },
+            Input::Text(text) => self.global.questions.type_text(&text).await,
             Input::Move(Motion::MouseCursor(position)) => {
-                self.global
-                    .inputs
-                    .set_mouse_position(CanvasCoordinate {
-                        x: position[0] - CANVAS_TOP_LEFT.x,
-                        y: position[1] - CANVAS_TOP_LEFT.y,
-                    })
-                    .await;
+                let position = CanvasCoordinate {
+                    x: position[0] - CANVAS_TOP_LEFT.x,
+                    y: position[1] - CANVAS_TOP_LEFT.y,
+                };
+                self.global.inputs.set_mouse_position(position).await;
+                self.move_mouse(position).await?;
             }
             _ => {}
         }
         Ok(())
     }
+
+    /// Clicks the sprite or the stage under the mouse. A draggable sprite is only clicked when the
+    /// button is released without dragging the sprite.
+    async fn press_mouse(&self, position: CanvasCoordinate) -> Result<()> {
+        let mouse = SpriteCoordinate::from(position);
+        if let Some(sprite_id) = self.sprites.sprite_at(&mouse).await {
+            if self.sprites.draggable(sprite_id).await? {
+                let center = self.sprites.sprite_rectangle(sprite_id).await?.center;
+                *self.mouse_press.write().await = Some(MousePress {
+                    sprite_id,
+                    start: position,
+                    offset: SpriteCoordinate {
+                        x: center.x - mouse.x,
+                        y: center.y - mouse.y,
+                    },
+                    dragging: false,
+                });
+                return Ok(());
+            }
+        }
+        self.global
+            .broadcaster
+            .send(BroadcastMsg::MouseClick(position))?;
+        Ok(())
+    }
+
+    async fn release_mouse(&self) -> Result<()> {
+        if let Some(mouse_press) = self.mouse_press.write().await.take() {
+            if !mouse_press.dragging {
+                self.global
+                    .broadcaster
+                    .send(BroadcastMsg::MouseClick(mouse_press.start))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the pressed sprite with the mouse. The sprite comes to the front when the drag
+    /// starts.
+    async fn move_mouse(&self, position: CanvasCoordinate) -> Result<()> {
+        let mut mouse_press = self.mouse_press.write().await;
+        if let Some(mouse_press) = mouse_press.as_mut() {
+            let mouse = SpriteCoordinate::from(position);
+            if !mouse_press.dragging {
+                if mouse.distance(&mouse_press.start.into()) <= DRAG_THRESHOLD {
+                    return Ok(());
+                }
+                mouse_press.dragging = true;
+                self.sprites
+                    .change_layer(mouse_press.sprite_id, LayerChange::Front)
+                    .await?;
+            }
+            self.sprites
+                .set_center(mouse_press.sprite_id, mouse.add(&mouse_press.offset))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct MousePress {
+    sprite_id: SpriteID,
+    /// Mouse position when the button was pressed
+    start: CanvasCoordinate,
+    /// Sprite center relative to the mouse
+    offset: SpriteCoordinate,
+    dragging: bool,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -285,6 +671,8 @@ enum Control {
     Continue,
     Pause,
     Step,
+    /// Runs one frame, see VM::step_frame()
+    Frame,
     Stop,
 }
 
@@ -312,14 +700,3 @@ impl Display for DebugInfo {
         )
     }
 }
-
-struct BroadcastMsgDebug<'a>(&'a BroadcastMsg);
-
-impl Debug for BroadcastMsgDebug<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self.0 {
-            BroadcastMsg::CanvasImage(_) => write!(f, "CanvasImage(RgbaImage)"),
-            _ => write!(f, "{:?}", self.0),
-        }
-    }
-}